@@ -0,0 +1,231 @@
+use crate::engine::eval::{Score, MATE_BOUND};
+use crate::engine::search::{move_scores, Resources};
+use crate::engine::tt::TranspositionTable;
+use crate::game::moves::{Move, MoveKind};
+use crate::game::position::Position;
+use crate::game::record::GameRecord;
+use crate::game::token::Owner;
+use crate::game::tree::{GameTree, Node};
+
+/// Losses beyond this count as this much in averages, so that a single missed win doesn't swamp
+/// them.
+const MAX_LOSS: Score = 1_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Judgement {
+    Good,
+    Inaccuracy,
+    Blunder,
+}
+
+/// The least loss, relative to the best move, of each [Judgement].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Thresholds {
+    pub inaccuracy: Score,
+    pub blunder: Score,
+}
+
+impl Default for Thresholds {
+    fn default() -> Thresholds {
+        Thresholds { inaccuracy: 50, blunder: 150 }
+    }
+}
+
+impl Thresholds {
+    pub fn judge(&self, loss: Score) -> Judgement {
+        if loss >= self.blunder {
+            Judgement::Blunder
+        } else if loss >= self.inaccuracy {
+            Judgement::Inaccuracy
+        } else {
+            Judgement::Good
+        }
+    }
+}
+
+/// A move compared with the engine's best. Scores are relative to the player who moved, and
+/// summons are scored by their expectation over the bag, since the draw isn't the player's choice.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MoveAnalysis {
+    /// 0-based.
+    pub ply: usize,
+    pub player: Owner,
+    pub played: Move,
+    pub best: Move,
+    /// The score of the best move, i.e., of the position before the move.
+    pub before: Score,
+    /// The score of the move played.
+    pub after: Score,
+    pub judgement: Judgement,
+    /// The best move forced the capture of the opposing Duke, and the move played doesn't.
+    pub missed_duke_capture: bool,
+    /// The best move was a summon, the move played wasn't, and it's no [Judgement::Good] move.
+    pub missed_summon: bool,
+}
+
+impl MoveAnalysis {
+    pub fn loss(&self) -> Score {
+        self.before - self.after
+    }
+}
+
+/// Compares m, which must be legal, with the best move in p, using searches of the given depth.
+pub fn analyze_move(
+    p: &Position,
+    m: Move,
+    depth: u32,
+    thresholds: &Thresholds,
+    tt: &TranspositionTable,
+) -> MoveAnalysis {
+    let scores = move_scores(p, depth, &Resources::new(tt));
+    let played = p.canonical(m);
+    let after = scores.iter().find(|(c, _)| *c == played).expect("The move must be legal").1;
+    let (best, before) = scores.iter().copied().max_by_key(|(_, score)| *score).unwrap();
+    let judgement = thresholds.judge(before - after);
+    MoveAnalysis {
+        ply: 0,
+        player: p.to_move,
+        played: m,
+        best,
+        before,
+        after,
+        judgement,
+        missed_duke_capture: before >= MATE_BOUND && after < MATE_BOUND,
+        missed_summon: best.kind() == MoveKind::Summon
+            && m.kind() != MoveKind::Summon
+            && judgement != Judgement::Good,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PlayerSummary {
+    pub player: Owner,
+    pub moves: usize,
+    pub inaccuracies: usize,
+    pub blunders: usize,
+    pub missed_duke_captures: usize,
+    pub missed_summons: usize,
+    /// Per move, in the engine's units, with each loss capped.
+    pub average_loss: Score,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GameAnalysis {
+    pub moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    pub fn summary(&self, player: Owner) -> PlayerSummary {
+        let moves: Vec<&MoveAnalysis> = self.moves.iter().filter(|a| a.player == player).collect();
+        let count = |judgement| moves.iter().filter(|a| a.judgement == judgement).count();
+        let total_loss: Score = moves.iter().map(|a| a.loss().clamp(0, MAX_LOSS)).sum();
+        PlayerSummary {
+            player,
+            moves: moves.len(),
+            inaccuracies: count(Judgement::Inaccuracy),
+            blunders: count(Judgement::Blunder),
+            missed_duke_captures: moves.iter().filter(|a| a.missed_duke_capture).count(),
+            missed_summons: moves.iter().filter(|a| a.missed_summon).count(),
+            average_loss: if moves.is_empty() { 0 } else { total_loss / moves.len() as Score },
+        }
+    }
+
+    /// The record as a [GameTree], with every move annotated with its score for Player 1, and the
+    /// best move as a variation to every inaccuracy and blunder.
+    pub fn annotate(&self, record: &GameRecord) -> GameTree {
+        let mut tree = GameTree::from_record(record);
+        let mut children = &mut tree.children;
+        for a in self.moves.iter() {
+            let sign = if a.player == Owner::Player1 { 1 } else { -1 };
+            let node = &mut children[0];
+            node.eval = Some(sign * a.after);
+            node.comment = match a.judgement {
+                Judgement::Good => String::new(),
+                Judgement::Inaccuracy => "Inaccuracy".to_owned(),
+                Judgement::Blunder => "Blunder".to_owned(),
+            };
+            if a.missed_duke_capture {
+                node.comment.push_str(", missed a Duke capture");
+            } else if a.missed_summon {
+                node.comment.push_str(", missed a summon");
+            }
+            if a.judgement != Judgement::Good {
+                let mut best = Node::new(a.best);
+                best.eval = Some(sign * a.before);
+                children.push(best);
+            }
+            children = &mut children[0].children;
+        }
+        tree
+    }
+}
+
+/// Replays the record, comparing every move with the best, see [analyze_move].
+pub fn analyze_game(record: &GameRecord, depth: u32, thresholds: &Thresholds, tt: &TranspositionTable) -> GameAnalysis {
+    let positions = record.positions();
+    let moves = record
+        .moves
+        .iter()
+        .enumerate()
+        .map(|(ply, m)| MoveAnalysis { ply, ..analyze_move(&positions[ply], *m, depth, thresholds, tt) })
+        .collect();
+    GameAnalysis { moves }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::puzzle::{Puzzle, PINNED};
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::units::UnitId;
+
+    use super::*;
+
+    fn puzzle() -> Puzzle {
+        PINNED.parse().unwrap()
+    }
+
+    #[test]
+    fn thresholds_judge_losses() {
+        let t = Thresholds::default();
+        assert_eq!(Judgement::Good, t.judge(49));
+        assert_eq!(Judgement::Inaccuracy, t.judge(50));
+        assert_eq!(Judgement::Blunder, t.judge(150));
+    }
+
+    #[test]
+    fn flags_missed_duke_captures() {
+        let p = puzzle().position;
+        let tt = TranspositionTable::new(1 << 12);
+        let best = analyze_move(&p, p.parse_move("d3-c4").unwrap(), 3, &Thresholds::default(), &tt);
+        assert_eq!(Judgement::Good, best.judgement);
+        assert!(!best.missed_duke_capture);
+        let other = analyze_move(&p, p.parse_move("d3-d5").unwrap(), 3, &Thresholds::default(), &tt);
+        assert_eq!(Judgement::Blunder, other.judgement);
+        assert!(other.missed_duke_capture);
+        assert!(other.before >= MATE_BOUND);
+    }
+
+    #[test]
+    fn games_are_summarized_and_annotated_per_player() {
+        let mut record = GameRecord::new(
+            [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Left, FootmenSetup::Sides)],
+            vec![UnitId::Footman; 2],
+        );
+        let mut p = record.initial_position();
+        for text in ["c1-c2", "b6-b5", "Footman@d2"].iter() {
+            let m = p.parse_move(text).unwrap();
+            record.moves.push(m);
+            p = p.make(m);
+        }
+        let tt = TranspositionTable::new(1 << 12);
+        let analysis = analyze_game(&record, 2, &Thresholds::default(), &tt);
+        assert_eq!(vec![0, 1, 2], analysis.moves.iter().map(|a| a.ply).collect::<Vec<usize>>());
+        assert_eq!(2, analysis.summary(Owner::Player1).moves);
+        assert_eq!(1, analysis.summary(Owner::Player2).moves);
+        assert!(analysis.moves.iter().all(|a| a.loss() >= 0));
+        let tree = analysis.annotate(&record);
+        assert_eq!(record, tree.main_line());
+        assert_eq!(Some(-analysis.moves[1].after), tree.children[0].children[0].eval);
+        assert_eq!(Ok(tree.clone()), tree.to_string().parse());
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use crate::common::coordinates::Coordinates;
+use crate::game::moves::GameMove;
+use crate::game::state::GameState;
+use crate::game::token::{Owner, TokenAction};
+
+/// A square the token can act on, and how.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Destination {
+    /// The square moved to, struck, or commanded to.
+    pub target: Coordinates,
+    pub action: TokenAction,
+    /// The friendly token moved by a Command.
+    pub commanded: Option<Coordinates>,
+    /// True if an enemy token on the target is captured.
+    pub capture: bool,
+}
+
+/// Why the token on a square can't act.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Immobile {
+    OffBoard,
+    Empty,
+    /// The token belongs to the player who isn't to move.
+    NotToMove(Owner),
+    /// Every action leads off the board, onto a friendly token, or through another token.
+    Blocked,
+    /// Every action would put its own Duke in guard.
+    Pinned,
+    /// Its Duke is in guard, and no action of the token gets it out.
+    InGuard,
+}
+
+impl fmt::Display for Immobile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Immobile::OffBoard => write!(f, "The square is off the board"),
+            Immobile::Empty => write!(f, "There's no token there"),
+            Immobile::NotToMove(owner) => write!(f, "It's {:?}'s token, but it's {:?}'s turn", owner, owner.other()),
+            Immobile::Blocked => write!(f, "The token is blocked"),
+            Immobile::Pinned => write!(f, "The token is pinned, moving it would put its Duke in guard"),
+            Immobile::InGuard => write!(f, "The Duke is in guard, and the token can't get it out"),
+        }
+    }
+}
+
+impl std::error::Error for Immobile {}
+
+fn destination(gs: &GameState, m: &GameMove) -> Destination {
+    let commanded = match m {
+        GameMove::Command { from, .. } => Some(*from),
+        _ => None,
+    };
+    let owner = gs.current_player_turn;
+    Destination {
+        target: m.target(),
+        action: m.action(),
+        commanded,
+        capture: gs.board.get(m.target()).is_some_and(|t| t.owner != owner),
+    }
+}
+
+/// The legal actions of the token on c, by the player to move.
+pub fn destinations(gs: &GameState, c: Coordinates) -> Result<Vec<Destination>, Immobile> {
+    let owner = gs.board.try_get(c).map_err(|_| Immobile::OffBoard)?.ok_or(Immobile::Empty)?.owner;
+    if owner != gs.current_player_turn {
+        return Err(Immobile::NotToMove(owner));
+    }
+    let moves = gs.board.moves_from(c);
+    if moves.is_empty() {
+        return Err(Immobile::Blocked);
+    }
+    let legal: Vec<Destination> = moves
+        .iter()
+        .filter(|m| {
+            let mut next = gs.board.clone();
+            next.make_move(m);
+            !next.is_in_guard(owner)
+        })
+        .map(|m| destination(gs, m))
+        .collect();
+    match (legal.is_empty(), gs.board.is_in_guard(owner)) {
+        (false, _) => Ok(legal),
+        (true, true) => Err(Immobile::InGuard),
+        (true, false) => Err(Immobile::Pinned),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::legality::guarded;
+    use crate::game::state::initial;
+    use crate::game::units;
+
+    use super::*;
+
+    #[test]
+    fn lists_legal_destinations() {
+        let gs = initial(0);
+        let result = destinations(&gs, "c1".parse().unwrap()).unwrap();
+        let targets: Vec<String> = result.iter().map(|d| d.target.to_string()).collect();
+        assert_eq!(vec!["c2", "b1"], targets);
+        assert!(result.iter().all(|d| d.action == TokenAction::Move && !d.capture && d.commanded.is_none()));
+    }
+
+    #[test]
+    fn explains_immobile_tokens() {
+        let gs = initial(0);
+        assert_eq!(Err(Immobile::Blocked), destinations(&gs, "d1".parse().unwrap()));
+        assert_eq!(Err(Immobile::Empty), destinations(&gs, "d3".parse().unwrap()));
+        assert_eq!(Err(Immobile::OffBoard), destinations(&gs, "g1".parse().unwrap()));
+        assert_eq!(Err(Immobile::NotToMove(Owner::Player2)), destinations(&gs, "c6".parse().unwrap()));
+
+        let mut gs = guarded();
+        let mut footman = units::footman(Owner::Player1);
+        footman.token.flip();
+        gs.board.place(Coordinates { x: 0, y: 1 }, footman);
+        assert_eq!(Err(Immobile::Pinned), destinations(&gs, "a2".parse().unwrap()));
+
+        let mut gs = guarded();
+        gs.board.place(Coordinates { x: 5, y: 0 }, units::footman(Owner::Player1));
+        assert_eq!(Err(Immobile::InGuard), destinations(&gs, "f1".parse().unwrap()));
+    }
+
+    #[test]
+    fn marks_captures() {
+        let mut gs = guarded();
+        gs.board.place(Coordinates { x: 0, y: 1 }, units::footman(Owner::Player1));
+        let result = destinations(&gs, "a2".parse().unwrap()).unwrap();
+        assert_eq!(1, result.len());
+        assert_eq!("a3", result[0].target.to_string());
+        assert!(result[0].capture);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::common::coordinates::Coordinates;
+use crate::engine::eval::{token_value, Score};
+use crate::game::board::GameBoard;
+use crate::game::moves::GameMove;
+use crate::game::state::GameState;
+use crate::game::token::Owner;
+
+fn captures_on(board: &GameBoard, owner: Owner, target: Coordinates) -> Vec<GameMove> {
+    let is_enemy = board.get(target).is_some_and(|t| t.owner != owner);
+    if !is_enemy {
+        return Vec::new();
+    }
+    board.legal_moves(owner).into_iter().filter(|m| m.target() == target).collect()
+}
+
+fn exchange(board: &GameBoard, owner: Owner, target: Coordinates) -> Score {
+    captures_on(board, owner, target)
+        .iter()
+        .map(|m| {
+            let mut next = board.clone();
+            let victim = next.make_move(m).map_or(0, |t| token_value(&t));
+            victim - exchange(&next, owner.other(), target)
+        })
+        .max()
+        .unwrap_or(0)
+        .max(0)
+}
+
+/// The net material the player to move gains by starting an exchange on target, in
+/// [crate::engine::eval] units. All attackers are considered, including strikes, which capture
+/// without exposing the striker, and either side may stop capturing at any point. Unlike
+/// [crate::engine::see::see], this supports custom tokens.
+pub fn static_exchange(gs: &GameState, target: Coordinates) -> Score {
+    exchange(&gs.board, gs.current_player_turn, target)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hashmap;
+    use crate::game::offset::{Offsets, VerticalOffset};
+    use crate::game::token::{DiscardBag, GameToken, OwnedToken, TokenAction, TokenBag, TokenSide};
+    use crate::game::units;
+
+    use super::*;
+
+    fn c(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    fn state(board: GameBoard) -> GameState {
+        GameState {
+            board,
+            current_player_turn: Owner::Player1,
+            player_1_bag: TokenBag::new(vec![]),
+            player_1_discard: DiscardBag::empty(),
+            player_2_bag: TokenBag::new(vec![]),
+            player_2_discard: DiscardBag::empty(),
+        }
+    }
+
+    fn striker() -> OwnedToken {
+        let side = TokenSide::new(hashmap![Offsets::centered(VerticalOffset::FarTop) => TokenAction::Strike]);
+        OwnedToken { token: GameToken::new(side.clone(), side, "Striker".to_owned()), owner: Owner::Player1 }
+    }
+
+    /// Player 2's footman on c4 is defended by the one on c5.
+    fn defended() -> GameBoard {
+        let mut board = GameBoard::empty();
+        board.place(c(0, 0), units::duke(Owner::Player1));
+        board.place(c(5, 5), units::duke(Owner::Player2));
+        board.place(c(2, 3), units::footman(Owner::Player2));
+        board.place(c(2, 4), units::footman(Owner::Player2));
+        board
+    }
+
+    #[test]
+    fn capturing_a_defended_token_is_an_even_trade() {
+        let mut board = defended();
+        board.place(c(2, 2), units::footman(Owner::Player1));
+        assert_eq!(0, static_exchange(&state(board), c(2, 3)));
+    }
+
+    #[test]
+    fn capturing_an_undefended_token_wins_it() {
+        let mut board = defended();
+        board.place(c(3, 0), units::footman(Owner::Player1));
+        board.place(c(3, 1), units::footman(Owner::Player2));
+        assert_eq!(100, static_exchange(&state(board), c(3, 1)));
+    }
+
+    #[test]
+    fn striking_a_defended_token_wins_it() {
+        let mut board = defended();
+        board.place(c(2, 1), striker());
+        assert_eq!(100, static_exchange(&state(board), c(2, 3)));
+    }
+
+    #[test]
+    fn friendly_and_empty_squares_are_worth_nothing() {
+        let mut board = defended();
+        board.place(c(2, 1), striker());
+        let gs = state(board);
+        assert_eq!(0, static_exchange(&gs, c(2, 1)));
+        assert_eq!(0, static_exchange(&gs, c(3, 3)));
+    }
+
+    #[test]
+    fn agrees_with_the_engine_on_standard_units() {
+        let mut board = defended();
+        board.place(c(2, 2), units::footman(Owner::Player1));
+        board.place(c(1, 3), units::footman(Owner::Player1));
+        let gs = state(board);
+        let p = crate::game::position::Position::from_state(&gs).unwrap();
+        for target in [c(2, 3), c(2, 4), c(1, 3)].iter() {
+            assert_eq!(crate::engine::see::see(&p, *target), static_exchange(&gs, *target));
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+use crate::engine::eval::{Score, MATE};
+use crate::engine::search::{search_with, Resources, SearchOptions};
+use crate::engine::solver::{solution_line, win_distance, winning_moves};
+use crate::engine::tt::TranspositionTable;
+use crate::game::moves::{Move, MoveKind, MoveList};
+use crate::game::position::Position;
+use crate::game::puzzle::Puzzle;
+use crate::game::record::{GameRecord, GameResult, Setup};
+use crate::game::state::{DukeInitialLocation, FootmenSetup};
+use crate::game::units::UnitId;
+
+/// The puzzle in p, if the player to move can force a win within max_moves and a single first move
+/// does it in the least number of moves. A search quickly rules out most positions, then the
+/// [crate::engine::solver] proves the win and its uniqueness exhaustively. The title gives the
+/// number of moves, which rates the puzzle.
+pub fn find_puzzle(p: &Position, max_moves: u32, tt: &TranspositionTable) -> Option<Puzzle> {
+    if max_moves == 0 {
+        return None;
+    }
+    let options = SearchOptions { depth: 2 * max_moves, threads: 1, table_size: tt.size(), use_book: false };
+    let result = search_with(p, &options, &Resources::new(tt));
+    if result.score < MATE - (2 * max_moves as Score - 1) {
+        return None;
+    }
+    let moves = win_distance(p, max_moves)?;
+    if winning_moves(p, moves).len() != 1 {
+        return None;
+    }
+    Some(Puzzle { title: format!("Win in {}", moves), position: *p, solution: solution_line(p, moves) })
+}
+
+/// The puzzles found in the games' positions, easiest first. Positions along the solution of a
+/// puzzle already found are skipped, since they'd only be shorter versions of it.
+pub fn mine_records(records: &[GameRecord], max_moves: u32, tt: &TranspositionTable) -> Vec<Puzzle> {
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for p in records.iter().flat_map(|r| r.positions()) {
+        if !seen.insert(p.zobrist()) {
+            continue;
+        }
+        if let Some(puzzle) = find_puzzle(&p, max_moves, tt) {
+            let mut q = p;
+            for m in puzzle.solution.iter() {
+                q = q.make(*m);
+                seen.insert(q.zobrist());
+            }
+            result.push(puzzle);
+        }
+    }
+    result.sort_by_key(|p| p.moves());
+    result
+}
+
+fn random_setup<R: Rng>(rng: &mut R) -> Setup {
+    let dukes = [DukeInitialLocation::Left, DukeInitialLocation::Right];
+    let footmen = [FootmenSetup::Sides, FootmenSetup::Left, FootmenSetup::Right];
+    (dukes[rng.gen_range(0..dukes.len())], footmen[rng.gen_range(0..footmen.len())])
+}
+
+/// Summons draw a unit from the bag at random, other moves are unchanged.
+fn draw<R: Rng>(p: &Position, m: Move, rng: &mut R) -> Move {
+    if m.kind() != MoveKind::Summon {
+        return m;
+    }
+    let bag = p.bag(p.to_move);
+    let distribution = WeightedIndex::new(bag.iter()).expect("Summons need a unit in the bag");
+    Move::summon(m.to(), UnitId::ALL[distribution.sample(rng)])
+}
+
+/// A game of the engine against itself from random setups, with the given bag. The first
+/// random_plies moves are random, so that games differ. Games still on after max_plies are
+/// unfinished.
+pub fn self_play<R: Rng>(
+    bag: &[UnitId],
+    options: &SearchOptions,
+    random_plies: usize,
+    max_plies: usize,
+    rng: &mut R,
+) -> GameRecord {
+    let mut record = GameRecord::new([random_setup(rng), random_setup(rng)], bag.to_vec());
+    let tt = TranspositionTable::new(options.table_size);
+    let mut p = record.initial_position();
+    while record.moves.len() < max_plies {
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        if moves.is_empty() {
+            record.result = if p.is_in_guard() { GameResult::Win(p.to_move.other()) } else { GameResult::Draw };
+            break;
+        }
+        let m = if record.moves.len() < random_plies {
+            moves.as_slice()[rng.gen_range(0..moves.len())]
+        } else {
+            search_with(&p, options, &Resources::new(&tt)).best_move.unwrap()
+        };
+        let m = draw(&p, m, rng);
+        record.moves.push(m);
+        p = p.make(m);
+    }
+    record
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::engine::solver::verify;
+    use crate::game::puzzle::PINNED;
+
+    use super::*;
+
+    fn puzzle() -> Puzzle {
+        PINNED.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_unique_wins() {
+        let puzzle = puzzle();
+        let tt = TranspositionTable::new(1 << 12);
+        let found = find_puzzle(&puzzle.position, 2, &tt).unwrap();
+        assert_eq!("Win in 2", found.title);
+        assert_eq!(puzzle.solution, found.solution);
+        assert!(verify(&found));
+        assert_eq!(None, find_puzzle(&puzzle.position, 1, &tt));
+    }
+
+    #[test]
+    fn skips_several_wins() {
+        let puzzle = puzzle();
+        let tt = TranspositionTable::new(1 << 12);
+        // After b2-c2, both e1-a1 and c4-b4 mate.
+        let first = puzzle.position.make(puzzle.solution[0]);
+        let p = first.make(first.parse_move("b2-c2").unwrap());
+        assert_eq!(2, winning_moves(&p, 1).len());
+        assert_eq!(None, find_puzzle(&p, 1, &tt));
+    }
+
+    #[test]
+    fn self_play_games_are_legal_records() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let options = SearchOptions { depth: 1, table_size: 1 << 12, ..SearchOptions::default() };
+        let record = self_play(&[UnitId::Footman; 2], &options, 4, 30, &mut rng);
+        assert!(record.moves.len() <= 30);
+        assert_eq!(Ok(record.clone()), record.to_string().parse());
+        let tt = TranspositionTable::new(1 << 12);
+        for puzzle in mine_records(&[record], 1, &tt) {
+            assert!(verify(&puzzle));
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod blunders;
+pub mod destinations;
+pub mod exchange;
+pub mod mining;
+pub mod report;
+pub mod summon;
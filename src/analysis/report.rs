@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use crate::common::coordinates::Coordinates;
+use crate::game::state::GameState;
+use crate::game::token::Owner;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TokenReport {
+    pub coordinates: Coordinates,
+    pub name: String,
+    /// The number of moves available to the token, ignoring guard.
+    pub mobility: usize,
+    /// The squares the token threatens, including strikes, see [crate::game::board::GameBoard::attacks_from].
+    pub attacks: Vec<Coordinates>,
+    /// True if another friendly token threatens this token's square.
+    pub defended: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlayerReport {
+    pub owner: Owner,
+    pub tokens: Vec<TokenReport>,
+    pub duke_in_guard: bool,
+}
+
+impl PlayerReport {
+    pub fn mobility(&self) -> usize {
+        self.tokens.iter().map(|t| t.mobility).sum()
+    }
+    /// All the squares threatened by any of the player's tokens.
+    pub fn attacked_squares(&self) -> HashSet<Coordinates> {
+        self.tokens.iter().flat_map(|t| t.attacks.iter().cloned()).collect()
+    }
+    pub fn undefended(&self) -> Vec<&TokenReport> {
+        self.tokens.iter().filter(|t| !t.defended).collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Report {
+    pub player_1: PlayerReport,
+    pub player_2: PlayerReport,
+}
+
+impl Report {
+    pub fn player(&self, owner: Owner) -> &PlayerReport {
+        match owner {
+            Owner::Player1 => &self.player_1,
+            Owner::Player2 => &self.player_2,
+        }
+    }
+}
+
+fn player_report(gs: &GameState, owner: Owner) -> PlayerReport {
+    let board = &gs.board;
+    let tokens: Vec<(Coordinates, Vec<Coordinates>)> = board
+        .active_coordinates()
+        .into_iter()
+        .filter(|(_, t)| t.owner == owner)
+        .map(|(c, _)| (c, board.attacks_from(c)))
+        .collect();
+    let defended = |c: Coordinates| tokens.iter().any(|(src, attacks)| *src != c && attacks.contains(&c));
+    PlayerReport {
+        owner,
+        tokens: tokens
+            .iter()
+            .map(|(c, attacks)| TokenReport {
+                coordinates: *c,
+                name: board.get(*c).unwrap().token.name.clone(),
+                mobility: board.moves_from(*c).len(),
+                attacks: attacks.clone(),
+                defended: defended(*c),
+            })
+            .collect(),
+        duke_in_guard: board.is_in_guard(owner),
+    }
+}
+
+pub fn report(gs: &GameState) -> Report {
+    Report {
+        player_1: player_report(gs, Owner::Player1),
+        player_2: player_report(gs, Owner::Player2),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::board::GameBoard;
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::token::TokenBag;
+    use crate::game::units;
+
+    use super::*;
+
+    fn empty_state() -> GameState {
+        let mut gs = GameState::new(
+            &TokenBag::new(vec![]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        gs.board = GameBoard::empty();
+        gs
+    }
+
+    #[test]
+    fn initial_position() {
+        let gs = GameState::new(
+            &TokenBag::new(vec![]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        let report = report(&gs);
+        let p1 = report.player(Owner::Player1);
+        assert_eq!(3, p1.tokens.len());
+        assert!(!p1.duke_in_guard);
+        // The Duke's slides are blocked by its own footmen, which can each move forward or outwards.
+        assert_eq!(4, p1.mobility());
+        // The footmen and the Duke are adjacent, so they all defend each other.
+        assert!(p1.undefended().is_empty());
+    }
+
+    #[test]
+    fn duke_in_guard_and_attacks_include_strikes() {
+        let mut gs = empty_state();
+        gs.board.place(Coordinates { x: 0, y: 0 }, units::duke(Owner::Player1));
+        gs.board.place(Coordinates { x: 0, y: 5 }, units::duke(Owner::Player2));
+        let mut footman = units::footman(Owner::Player2);
+        footman.token.flip();
+        gs.board.place(Coordinates { x: 1, y: 1 }, footman);
+        let report = report(&gs);
+        assert!(report.player_1.duke_in_guard);
+        assert!(!report.player_2.duke_in_guard);
+        assert!(report.player_2.attacked_squares().contains(&Coordinates { x: 0, y: 0 }));
+        assert!(report.player_1.attacked_squares().contains(&Coordinates { x: 1, y: 0 }));
+        assert_eq!(
+            vec!["Footman", units::DUKE],
+            report.player_2.undefended().iter().map(|t| t.name.as_str()).collect::<Vec<_>>(),
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use crate::common::coordinates::Coordinates;
+use crate::engine::eval::Score;
+use crate::engine::search::{move_scores, Resources};
+use crate::engine::tt::TranspositionTable;
+use crate::game::bitboard::square_at;
+use crate::game::moves::{Move, MoveKind};
+use crate::game::position::Position;
+use crate::game::state::GameState;
+use crate::game::token::TokenBag;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SummonReport {
+    /// The probability of drawing each token, by name, see [draw_probabilities].
+    pub probabilities: Vec<(String, f64)>,
+    /// The expected score of summoning onto each legal square next to the Duke, for the summoner.
+    pub squares: Vec<(Coordinates, Score)>,
+    /// The best move which isn't a summon and its score, [None] if there are no such moves.
+    pub best_move: Option<(Move, Score)>,
+}
+
+impl SummonReport {
+    pub fn best_square(&self) -> Option<(Coordinates, Score)> {
+        self.squares.iter().cloned().max_by_key(|(_, score)| *score)
+    }
+    /// True if the best summon scores better than the best move, or if there are only summons.
+    pub fn summoning_is_better(&self) -> bool {
+        match (self.best_square(), self.best_move) {
+            (Some((_, summon)), Some((_, other))) => summon > other,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// The probability of drawing each token in the bag, by name, sorted by name. Empty for an empty bag.
+pub fn draw_probabilities(bag: &TokenBag) -> Vec<(String, f64)> {
+    let mut counts = BTreeMap::new();
+    for t in bag.remaining() {
+        *counts.entry(t.name.clone()).or_insert(0u32) += 1;
+    }
+    let total = bag.remaining().len() as f64;
+    counts.into_iter().map(|(name, count)| (name, f64::from(count) / total)).collect()
+}
+
+/// The current player's summoning prospects, scored by a search of the given depth. [None] if the
+/// state has tokens which aren't standard units.
+pub fn summon_report(gs: &GameState, depth: u32) -> Option<SummonReport> {
+    let p = Position::from_state(gs)?;
+    let tt = TranspositionTable::new(1 << 16);
+    let scores = move_scores(&p, depth, &Resources::new(&tt));
+    let (summons, others): (Vec<_>, Vec<_>) = scores.into_iter().partition(|(m, _)| m.kind() == MoveKind::Summon);
+    Some(SummonReport {
+        probabilities: draw_probabilities(gs.bag(gs.current_player_turn)),
+        squares: summons.into_iter().map(|(m, score)| (square_at(m.to()), score)).collect(),
+        best_move: others.into_iter().max_by_key(|(_, score)| *score),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::token::Owner;
+    use crate::game::units;
+
+    use super::*;
+
+    #[test]
+    fn probabilities_are_by_name() {
+        let bag = TokenBag::new(vec![
+            units::footman(Owner::Player1).token,
+            units::duke(Owner::Player1).token,
+            units::footman(Owner::Player1).token,
+            units::footman(Owner::Player1).token,
+        ]);
+        assert_eq!(
+            vec![("Duke".to_owned(), 0.25), ("Footman".to_owned(), 0.75)],
+            draw_probabilities(&bag),
+        );
+        assert!(draw_probabilities(&TokenBag::new(vec![])).is_empty());
+    }
+
+    #[test]
+    fn report_scores_every_summoning_square() {
+        let gs = GameState::new(
+            &TokenBag::new(vec![units::footman(Owner::Player1).token]),
+            (DukeInitialLocation::Left, FootmenSetup::Left),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        let report = summon_report(&gs, 1).unwrap();
+        assert_eq!(vec![("Footman".to_owned(), 1.0)], report.probabilities);
+        let squares: Vec<Coordinates> = report.squares.iter().map(|(c, _)| *c).collect();
+        assert_eq!(vec![Coordinates { x: 2, y: 0 }], squares);
+        assert!(report.best_move.is_some());
+        assert_eq!(
+            report.summoning_is_better(),
+            report.best_square().unwrap().1 > report.best_move.unwrap().1,
+        );
+    }
+
+    #[test]
+    fn empty_bag_has_no_summons() {
+        let gs = GameState::new(
+            &TokenBag::new(vec![]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        let report = summon_report(&gs, 1).unwrap();
+        assert!(report.squares.is_empty());
+        assert!(!report.summoning_is_better());
+    }
+}
@@ -0,0 +1,54 @@
+//! Replays game records with the engine, listing each player's inaccuracies and blunders, and
+//! optionally writes the games annotated with evaluations and the best alternatives.
+//!
+//! Usage: `cargo run --release --bin analyze [--depth <n>] [--inaccuracy <score>] [--blunder <score>]
+//! [--annotate <output>] <records>...`, where scores are in the engine's units.
+
+use std::env;
+use std::fs;
+
+use duke_rust::analysis::blunders::{analyze_game, Thresholds};
+use duke_rust::cli::args::{fail, number};
+use duke_rust::engine::search::SearchOptions;
+use duke_rust::engine::tt::TranspositionTable;
+use duke_rust::game::record::GameRecord;
+use duke_rust::view::dumb_printer::print_game_analysis;
+
+const USAGE: &str =
+    "Usage: analyze [--depth <n>] [--inaccuracy <score>] [--blunder <score>] [--annotate <output>] <records>...";
+const DEFAULT_DEPTH: u32 = 4;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut depth = DEFAULT_DEPTH;
+    let mut thresholds = Thresholds::default();
+    let mut annotate = None;
+    let mut records = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = number(&arg, args.next()),
+            "--inaccuracy" => thresholds.inaccuracy = number(&arg, args.next()),
+            "--blunder" => thresholds.blunder = number(&arg, args.next()),
+            "--annotate" => annotate = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            _ => {
+                let text = fs::read_to_string(&arg).unwrap_or_else(|e| fail(&format!("{}: {}", arg, e)));
+                let parsed = GameRecord::parse_all(&text).unwrap_or_else(|e| fail(&format!("{}: {}", arg, e)));
+                records.extend(parsed);
+            }
+        }
+    }
+    if records.is_empty() {
+        fail(USAGE);
+    }
+    let tt = TranspositionTable::new(SearchOptions::default().table_size);
+    let mut annotated = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        let analysis = analyze_game(record, depth, &thresholds, &tt);
+        println!("Game {}:\n{}\n", i + 1, print_game_analysis(&analysis));
+        annotated.push(analysis.annotate(record).to_string());
+    }
+    if let Some(output) = annotate {
+        fs::write(&output, annotated.join("\n")).unwrap_or_else(|e| fail(&format!("{}: {}", output, e)));
+        println!("Wrote {} annotated games to {}", annotated.len(), output);
+    }
+}
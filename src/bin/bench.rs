@@ -0,0 +1,68 @@
+//! Reports move generation speed, in nodes per second, by running perft on fixed positions.
+//!
+//! Usage: `cargo run --release --bin bench [depth]`
+
+use std::env;
+use std::time::Instant;
+
+use duke_rust::common::coordinates::Coordinates;
+use duke_rust::game::bitboard::BitBoard;
+use duke_rust::game::position::Position;
+use duke_rust::game::state::{DukeInitialLocation, FootmenSetup, GameState};
+use duke_rust::game::token::{CurrentSide, Owner, TokenBag};
+use duke_rust::game::units::{self, UnitId};
+
+const DEFAULT_DEPTH: u32 = 6;
+
+fn bag() -> TokenBag {
+    TokenBag::new((0..4).map(|_| units::footman(Owner::Player1).token).collect())
+}
+
+fn initial(p1: (DukeInitialLocation, FootmenSetup), p2: (DukeInitialLocation, FootmenSetup)) -> Position {
+    Position::from_state(&GameState::new(&bag(), p1, p2)).unwrap()
+}
+
+fn middle_game() -> Position {
+    let mut board = BitBoard::empty();
+    let mut put = |x, y, owner, unit, side| board.put(Coordinates { x, y }, owner, unit, side);
+    put(2, 1, Owner::Player1, UnitId::Duke, CurrentSide::Flipped);
+    put(1, 1, Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+    put(3, 2, Owner::Player1, UnitId::Footman, CurrentSide::Flipped);
+    put(2, 3, Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+    put(3, 4, Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+    put(4, 4, Owner::Player2, UnitId::Footman, CurrentSide::Flipped);
+    put(1, 4, Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+    Position::new(board, Owner::Player1, [[0, 2], [0, 3]])
+}
+
+fn main() {
+    let depth = env::args()
+        .nth(1)
+        .map(|d| d.parse().expect("depth must be a non-negative integer"))
+        .unwrap_or(DEFAULT_DEPTH);
+    let positions = vec![
+        ("initial, sides", initial(
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        )),
+        ("initial, mixed", initial(
+            (DukeInitialLocation::Right, FootmenSetup::Left),
+            (DukeInitialLocation::Left, FootmenSetup::Right),
+        )),
+        ("middle game", middle_game()),
+    ];
+    let mut total_nodes = 0;
+    let mut total_seconds = 0.0;
+    for (name, position) in positions {
+        let start = Instant::now();
+        let nodes = position.perft(depth);
+        let seconds = start.elapsed().as_secs_f64();
+        total_nodes += nodes;
+        total_seconds += seconds;
+        println!(
+            "{:<16} depth {:>2}: {:>12} nodes in {:>8.3}s, {:>12.0} nodes/s",
+            name, depth, nodes, seconds, nodes as f64 / seconds,
+        );
+    }
+    println!("{:<25} {:>12} nodes in {:>8.3}s, {:>12.0} nodes/s", "total", total_nodes, total_seconds, total_nodes as f64 / total_seconds);
+}
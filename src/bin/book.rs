@@ -0,0 +1,37 @@
+//! Builds an opening book from game record files, each holding one or more records.
+//!
+//! Usage: `cargo run --release --bin book <output> [--plies <n>] <records>...`
+
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use duke_rust::cli::args::{fail, number};
+use duke_rust::engine::book::OpeningBook;
+use duke_rust::game::record::GameRecord;
+
+const DEFAULT_PLIES: usize = 12;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let output = args.next().unwrap_or_else(|| fail("Usage: book <output> [--plies <n>] <records>..."));
+    let mut plies = DEFAULT_PLIES;
+    let mut book = OpeningBook::new();
+    let mut games = 0;
+    while let Some(arg) = args.next() {
+        if arg == "--plies" {
+            plies = number(&arg, args.next());
+            continue;
+        }
+        let text = fs::read_to_string(&arg).unwrap_or_else(|e| fail(&format!("{}: {}", arg, e)));
+        let records = GameRecord::parse_all(&text).unwrap_or_else(|e| fail(&format!("{}: {}", arg, e)));
+        for r in records.iter() {
+            book.add(r, plies);
+        }
+        games += records.len();
+    }
+    let mut file = BufWriter::new(File::create(&output).unwrap_or_else(|e| fail(&format!("{}: {}", output, e))));
+    book.write(&mut file).and_then(|_| file.flush()).unwrap_or_else(|e| fail(&format!("{}: {}", output, e)));
+    println!("Wrote {} positions from {} games to {}", book.len(), games, output);
+}
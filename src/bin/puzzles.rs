@@ -0,0 +1,59 @@
+//! Mines "win in N" puzzles from game record files, or from engine self-play games if none are
+//! given, and writes them to a puzzle file, shortest solutions first.
+//!
+//! Usage: `cargo run --release --bin puzzles <output> [--moves <n>] [--games <n>] [--depth <n>]
+//! [--seed <n>] [records]...`
+
+use std::env;
+use std::fs;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use duke_rust::analysis::mining::{mine_records, self_play};
+use duke_rust::cli::args::{fail, number};
+use duke_rust::engine::search::SearchOptions;
+use duke_rust::engine::tt::TranspositionTable;
+use duke_rust::game::record::GameRecord;
+use duke_rust::game::units::UnitId;
+
+const USAGE: &str = "Usage: puzzles <output> [--moves <n>] [--games <n>] [--depth <n>] [--seed <n>] [records]...";
+/// Self-play games start with this many random moves, so that they differ.
+const RANDOM_PLIES: usize = 4;
+const MAX_PLIES: usize = 120;
+const BAG: [UnitId; 3] = [UnitId::Footman; 3];
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let output = args.next().unwrap_or_else(|| fail(USAGE));
+    let mut moves = 2;
+    let mut games = 20;
+    let mut options = SearchOptions { depth: 3, ..SearchOptions::default() };
+    let mut seed = 0;
+    let mut records = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--moves" => moves = number(&arg, args.next()),
+            "--games" => games = number(&arg, args.next()),
+            "--depth" => options.depth = number(&arg, args.next()),
+            "--seed" => seed = number(&arg, args.next()),
+            _ => {
+                let text = fs::read_to_string(&arg).unwrap_or_else(|e| fail(&format!("{}: {}", arg, e)));
+                let parsed = GameRecord::parse_all(&text).unwrap_or_else(|e| fail(&format!("{}: {}", arg, e)));
+                records.extend(parsed);
+            }
+        }
+    }
+    if records.is_empty() {
+        let mut rng = StdRng::seed_from_u64(seed);
+        records = (0..games).map(|_| self_play(&BAG, &options, RANDOM_PLIES, MAX_PLIES, &mut rng)).collect();
+    }
+    let tt = TranspositionTable::new(options.table_size);
+    let puzzles = mine_records(&records, moves, &tt);
+    let text: Vec<String> = puzzles.iter().map(|p| p.to_string()).collect();
+    fs::write(&output, text.join("\n")).unwrap_or_else(|e| fail(&format!("{}: {}", output, e)));
+    println!("Wrote {} puzzles from {} games to {}", puzzles.len(), records.len(), output);
+    for n in 1..=moves {
+        println!("Win in {}: {}", n, puzzles.iter().filter(|p| p.moves() == n).count());
+    }
+}
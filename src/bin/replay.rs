@@ -0,0 +1,24 @@
+//! Writes a game record, with its comments, as a single HTML page which replays it in a browser.
+//!
+//! Usage: `cargo run --bin replay <record> <output.html>`
+
+use std::env;
+use std::fs;
+
+use duke_rust::cli::args::fail;
+use duke_rust::game::tree::GameTree;
+use duke_rust::view::html::replay_html;
+
+const USAGE: &str = "Usage: replay <record> <output.html>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (record, output) = match args.as_slice() {
+        [record, output] => (record, output),
+        _ => fail(USAGE),
+    };
+    let text = fs::read_to_string(record).unwrap_or_else(|e| fail(&format!("{}: {}", record, e)));
+    let tree: GameTree = text.parse().unwrap_or_else(|e| fail(&format!("{}: {}", record, e)));
+    fs::write(output, replay_html(&tree)).unwrap_or_else(|e| fail(&format!("{}: {}", output, e)));
+    println!("Wrote {}", output);
+}
@@ -0,0 +1,34 @@
+//! Serves the JSON API for creating and playing games, see [duke_rust::server::api::Api].
+//!
+//! Usage: `cargo run --release --bin server [--address <host:port>] [--depth <n>]`, which listens
+//! on localhost only unless given another address.
+
+use std::env;
+use std::io;
+use std::net::TcpListener;
+
+use duke_rust::cli::args::{fail, number};
+use duke_rust::engine::search::SearchOptions;
+use duke_rust::server::api::Api;
+use duke_rust::server::http::{serve, Request};
+
+const USAGE: &str = "Usage: server [--address <host:port>] [--depth <n>]";
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let mut address = DEFAULT_ADDRESS.to_owned();
+    let mut options = SearchOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--address" => address = args.next().unwrap_or_else(|| fail(USAGE)),
+            "--depth" => options.depth = number(&arg, args.next()),
+            _ => fail(USAGE),
+        }
+    }
+    let listener = TcpListener::bind(&address)?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    let api = Api::new(options);
+    serve(listener, move |r: &Request| api.reply(r));
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Generates the endgame tablebase of a material, and of every material reachable from it by
+//! captures, writing each table to its own file.
+//!
+//! Usage: `cargo run --release --bin tablebase <material> [dir]`, where material lists each
+//! player's units by initial, e.g., `DF-D` for Duke and Footman against a lone Duke.
+
+use std::env;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use duke_rust::cli::args::fail;
+use duke_rust::engine::tablebase::{Material, Outcome, Tablebases};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let material: Material = match args.next().map(|m| m.parse()) {
+        Some(Ok(m)) => m,
+        Some(Err(e)) => fail(&e.to_string()),
+        None => fail("Usage: tablebase <material> [dir]"),
+    };
+    let dir = args.next().map_or_else(|| PathBuf::from("."), PathBuf::from);
+    let start = Instant::now();
+    let mut tables = Tablebases::new();
+    tables.generate(&material);
+    println!("Generated {} tables in {:.1}s", tables.len(), start.elapsed().as_secs_f64());
+    let table = tables.get(&material).unwrap();
+    let (mut wins, mut losses, mut draws, mut longest) = (0, 0, 0, 0);
+    for outcome in table.outcomes() {
+        match outcome {
+            Outcome::Win(d) => {
+                wins += 1;
+                longest = longest.max(d);
+            }
+            Outcome::Loss(_) => losses += 1,
+            Outcome::Draw => draws += 1,
+        }
+    }
+    println!("{}: {} wins, {} losses, {} draws, longest mate {} plies", material, wins, losses, draws, longest);
+    if let Err(e) = tables.write_dir(&dir) {
+        fail(&format!("Failed writing to {}: {}", dir.display(), e));
+    }
+}
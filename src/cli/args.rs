@@ -0,0 +1,36 @@
+use std::process;
+use std::str::FromStr;
+
+/// Prints the message, e.g., the usage, and exits with an error.
+pub fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+/// The flag's value, e.g., the next argument, failing with "<flag> requires a number" if it's
+/// missing or doesn't parse.
+pub fn number<T: FromStr>(flag: &str, value: Option<String>) -> T {
+    value.and_then(|v| v.parse().ok()).unwrap_or_else(|| fail(&format!("{} requires a number", flag)))
+}
+
+/// The argument following the flag, or [None] if the flag isn't given. Fails with "<flag> requires
+/// <what>" if the flag is the last argument.
+pub fn flag_value<'a>(args: &'a [String], flag: &str, what: &str) -> Option<&'a str> {
+    let i = args.iter().position(|a| a == flag)?;
+    match args.get(i + 1) {
+        Some(value) => Some(value),
+        None => fail(&format!("{} requires {}", flag, what)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flag_values_follow_their_flag() {
+        let args: Vec<String> = vec!["--tui".to_owned(), "--book".to_owned(), "book.bin".to_owned()];
+        assert_eq!(Some("book.bin"), flag_value(&args, "--book", "a file"));
+        assert_eq!(None, flag_value(&args, "--tablebases", "a directory"));
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::common::coordinates::Coordinates;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Board,
+    Moves,
+    /// Where the token on the square can go, or why it can't.
+    Show(Coordinates),
+    /// A move in [crate::game::moves::Move] notation, or "@" followed by a square to summon there.
+    Play(String),
+    Engine { depth: Option<u32> },
+    Summon { depth: Option<u32> },
+    Report,
+    Clock,
+    /// Steps back through the game tree, keeping the moves for [Command::Forward].
+    Back,
+    /// Follows the numbered variation, 1 being the main continuation.
+    Forward(usize),
+    Start,
+    End,
+    Promote,
+    Delete,
+    Comment(String),
+    /// An evaluation for Player 1 in the engine's units, given in pawns, e.g. 0.25, or [None] to
+    /// remove it.
+    Annotate(Option<i32>),
+    Record,
+    /// Writes the board, with an arrow for the last move, as an SVG file.
+    Svg(String),
+    /// Compares every move of the main line with the engine's best.
+    Analyze { depth: Option<u32> },
+    /// Puzzle mode only, like the two commands which follow.
+    Hint,
+    Solution,
+    Next,
+    Help,
+    Quit,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CommandError {
+    Empty,
+    Unknown(String),
+    InvalidArgument(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "No command, try 'help'"),
+            CommandError::Unknown(c) => write!(f, "Unknown command '{}', try 'help'", c),
+            CommandError::InvalidArgument(a) => write!(f, "Invalid argument '{}'", a),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+fn depth(arg: Option<&str>) -> Result<Option<u32>, CommandError> {
+    arg.map(|a| a.parse().map_err(|_| CommandError::InvalidArgument(a.to_owned()))).transpose()
+}
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    fn from_str(s: &str) -> Result<Command, CommandError> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or(CommandError::Empty)?;
+        let arg = words.next();
+        let rest = s.trim_start()[name.len()..].trim();
+        match name {
+            "board" | "b" => Ok(Command::Board),
+            "moves" | "m" => Ok(Command::Moves),
+            "show" => arg
+                .ok_or_else(|| CommandError::InvalidArgument("missing square".to_owned()))
+                .and_then(|a| a.parse().map_err(|_| CommandError::InvalidArgument(a.to_owned())))
+                .map(Command::Show),
+            "play" | "p" => arg
+                .map(|a| Command::Play(a.to_owned()))
+                .ok_or_else(|| CommandError::InvalidArgument("missing move".to_owned())),
+            "engine" | "e" => Ok(Command::Engine { depth: depth(arg)? }),
+            "summon" | "s" => Ok(Command::Summon { depth: depth(arg)? }),
+            "report" | "r" => Ok(Command::Report),
+            "clock" | "c" => Ok(Command::Clock),
+            "back" | "<" => Ok(Command::Back),
+            "forward" | ">" => {
+                let n = depth(arg)?.unwrap_or(1) as usize;
+                if n == 0 {
+                    return Err(CommandError::InvalidArgument("0".to_owned()));
+                }
+                Ok(Command::Forward(n))
+            }
+            "start" => Ok(Command::Start),
+            "end" => Ok(Command::End),
+            "promote" => Ok(Command::Promote),
+            "delete" => Ok(Command::Delete),
+            "comment" => Ok(Command::Comment(rest.to_owned())),
+            "annotate" => arg
+                .map(|a| match a.parse::<f64>() {
+                    Ok(pawns) if pawns.is_finite() => Ok((pawns * 100.0).round() as i32),
+                    _ => Err(CommandError::InvalidArgument(a.to_owned())),
+                })
+                .transpose()
+                .map(Command::Annotate),
+            "record" => Ok(Command::Record),
+            "svg" => arg
+                .map(|a| Command::Svg(a.to_owned()))
+                .ok_or_else(|| CommandError::InvalidArgument("missing file".to_owned())),
+            "analyze" => Ok(Command::Analyze { depth: depth(arg)? }),
+            "hint" => Ok(Command::Hint),
+            "solution" => Ok(Command::Solution),
+            "next" | "n" => Ok(Command::Next),
+            "help" | "h" | "?" => Ok(Command::Help),
+            "quit" | "q" | "exit" => Ok(Command::Quit),
+            other => Err(CommandError::Unknown(other.to_owned())),
+        }
+    }
+}
+
+pub const HELP: &str = "\
+board, b            Print the board
+moves, m            List the legal moves
+show <square>       Show where the token on the square can go, e.g., 'show c1'
+play, p <move>      Play a move, e.g., 'c1-c2', 'c1xc3', 'c1:b1-b2', or '@d2' to summon
+engine, e [depth]   Search for the best move
+summon, s [depth]   Show the bag's draw probabilities and the value of summoning
+report, r           Print the threats and mobility of every token
+clock, c            Show both players' remaining time
+back, <             Take back the last move, keeping it as a variation
+forward, > [n]      Replay the next move, or the nth variation
+start, end          Go to the start, or the end of the current line
+promote             Make the current variation the main line where it branches off
+delete              Delete the last move and its variations
+comment <text>      Comment on the last move, or the game at the start
+annotate [score]    Annotate the last move with an evaluation, e.g. '+0.25', or remove it
+record              Print the game with its variations
+svg <file>          Write the board as an SVG image, e.g., 'svg board.svg'
+analyze [depth]     List the inaccuracies and blunders of the main line
+help, h             Print this message
+quit, q             Quit";
+
+pub const PUZZLE_HELP: &str = "\
+board, b            Print the board
+moves, m            List the legal moves
+show <square>       Show where the token on the square can go, e.g., 'show c1'
+play, p <move>      Play a move, e.g., 'c1-c2', 'c1xc3', 'c1:b1-b2', or '@d2' to summon
+hint                Show which token to move, or the whole move if asked again
+solution            Show the rest of the solution
+next, n             Go to the next puzzle
+help, h             Print this message
+quit, q             Quit";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_arguments() {
+        assert_eq!(Ok(Command::Board), "board".parse());
+        assert_eq!(Ok(Command::Play("c1-c2".to_owned())), "  p   c1-c2 ".parse());
+        assert_eq!(Ok(Command::Engine { depth: None }), "engine".parse());
+        assert_eq!(Ok(Command::Summon { depth: Some(3) }), "s 3".parse());
+        assert_eq!(Ok(Command::Next), "n".parse());
+        assert_eq!(Ok(Command::Show(Coordinates { x: 2, y: 0 })), "show c1".parse());
+        assert_eq!(Ok(Command::Forward(2)), "> 2".parse());
+        assert_eq!(Ok(Command::Comment("Too {slow}".to_owned())), " comment  Too {slow} ".parse());
+        assert_eq!(Ok(Command::Annotate(Some(-50))), "annotate -0.5".parse());
+        assert_eq!(Ok(Command::Annotate(None)), "annotate".parse());
+        assert_eq!(Ok(Command::Svg("board.svg".to_owned())), "svg board.svg".parse());
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(Err(CommandError::Empty), "  ".parse::<Command>());
+        assert_eq!(Err(CommandError::Unknown("fly".to_owned())), "fly".parse::<Command>());
+        assert_eq!(Err(CommandError::InvalidArgument("x".to_owned())), "engine x".parse::<Command>());
+        assert!("play".parse::<Command>().is_err());
+        assert_eq!(Err(CommandError::InvalidArgument("9z".to_owned())), "show 9z".parse::<Command>());
+        assert_eq!(Err(CommandError::InvalidArgument("0".to_owned())), "forward 0".parse::<Command>());
+    }
+}
@@ -0,0 +1,4 @@
+pub mod args;
+pub mod command;
+pub mod puzzle;
+pub mod session;
@@ -0,0 +1,262 @@
+use std::io;
+use std::io::{BufRead, Write};
+
+use crate::cli::command::{Command, PUZZLE_HELP};
+use crate::cli::session::{find_move, print_moves, show_token};
+use crate::engine::solver::{best_defence, is_mated, move_wins, solution_line, verify};
+use crate::game::bitboard::square_at;
+use crate::game::moves::{Move, MoveKind};
+use crate::game::position::Position;
+use crate::game::puzzle::Puzzle;
+use crate::view::dumb_printer::print_board;
+
+/// Training puzzles, driven by text commands. The player finds the puzzle's win, and the opponent
+/// answers by itself: along the puzzle's solution while the player follows it, otherwise with the
+/// [best_defence].
+pub struct PuzzleSession {
+    puzzles: Vec<Puzzle>,
+    index: usize,
+    position: Position,
+    /// Both players' moves since the start of the puzzle.
+    played: Vec<Move>,
+    /// The hints given for the current move.
+    hints: u32,
+}
+
+impl PuzzleSession {
+    /// Errs if there are no puzzles, or naming the first whose solution isn't a forced win, see
+    /// [verify].
+    pub fn new(puzzles: Vec<Puzzle>) -> Result<PuzzleSession, String> {
+        let position = puzzles.first().ok_or("No puzzles")?.position;
+        if let Some(i) = puzzles.iter().position(|p| !verify(p)) {
+            return Err(format!("Puzzle {} isn't a forced win in {}", i + 1, puzzles[i].moves()));
+        }
+        Ok(PuzzleSession { puzzles, index: 0, position, played: Vec::new(), hints: 0 })
+    }
+
+    fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.index]
+    }
+
+    fn introduction(&self) -> String {
+        let puzzle = self.puzzle();
+        let title = if puzzle.title.is_empty() { String::new() } else { format!(": {}", puzzle.title) };
+        format!(
+            "Puzzle {}/{}{}\n{:?} to move and win in {}\n{}",
+            self.index + 1,
+            self.puzzles.len(),
+            title,
+            puzzle.solver(),
+            puzzle.moves(),
+            print_board(&self.position.to_state()),
+        )
+    }
+
+    /// The player's moves left to win in.
+    fn remaining(&self) -> u32 {
+        self.puzzle().moves() - (self.played.len() as u32).div_ceil(2)
+    }
+
+    fn is_solved(&self) -> bool {
+        is_mated(&self.position)
+    }
+
+    /// Both players' moves from the current position to mate, from the puzzle while the player
+    /// follows it, otherwise from the solver.
+    fn line(&self) -> Vec<Move> {
+        let solution = &self.puzzle().solution;
+        if solution.starts_with(&self.played) {
+            return solution[self.played.len()..].to_vec();
+        }
+        solution_line(&self.position, self.remaining())
+    }
+
+    fn apply(&mut self, m: Move) -> () {
+        self.played.push(m);
+        self.position = self.position.make(m);
+    }
+
+    fn play(&mut self, text: &str) -> Result<String, String> {
+        if self.is_solved() {
+            return Err("Already solved, try 'next'".to_owned());
+        }
+        let mut m = find_move(&self.position, text)?;
+        let line = self.line();
+        if m.kind() == MoveKind::Summon && line[0].kind() == MoveKind::Summon && m.to() == line[0].to() {
+            // The unit drawn follows the solution.
+            m = line[0];
+        }
+        if !move_wins(&self.position, m, self.remaining()) {
+            return Err(format!("{} doesn't win in {}, try again", m, self.remaining()));
+        }
+        self.hints = 0;
+        let reply = if line[0] == m { line.get(1).copied() } else { None };
+        self.apply(m);
+        if self.is_solved() {
+            return Ok(format!("{} mates, solved!\n{}", m, print_board(&self.position.to_state())));
+        }
+        let reply = reply.or_else(|| best_defence(&self.position, self.remaining())).unwrap();
+        self.apply(reply);
+        Ok(format!(
+            "Correct, {:?} answers {}\n{}",
+            self.position.to_move.other(),
+            reply,
+            print_board(&self.position.to_state()),
+        ))
+    }
+
+    /// First which token to move, then the whole move.
+    fn hint(&mut self) -> Result<String, String> {
+        if self.is_solved() {
+            return Err("Already solved, try 'next'".to_owned());
+        }
+        let m = self.line()[0];
+        self.hints += 1;
+        Ok(match (self.hints, m.kind(), m.actor()) {
+            (1, MoveKind::Summon, _) => "Summon a token".to_owned(),
+            (1, _, Some(actor)) => format!("Use the token on {}", square_at(actor)),
+            _ => format!("Play {}", m),
+        })
+    }
+
+    fn solution(&self) -> String {
+        let line: Vec<String> = self.line().iter().map(|m| m.to_string()).collect();
+        if line.is_empty() { "Already solved".to_owned() } else { line.join(" ") }
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        if self.index + 1 == self.puzzles.len() {
+            return Err("That was the last puzzle".to_owned());
+        }
+        self.index += 1;
+        self.position = self.puzzle().position;
+        self.played.clear();
+        self.hints = 0;
+        Ok(self.introduction())
+    }
+
+    /// The output of the command, like [crate::cli::session::Session::execute].
+    pub fn execute(&mut self, command: &Command) -> Option<String> {
+        let result = match command {
+            Command::Board => Ok(print_board(&self.position.to_state())),
+            Command::Moves => Ok(print_moves(&self.position)),
+            Command::Show(c) => Ok(show_token(&self.position.to_state(), *c)),
+            Command::Play(m) => self.play(m),
+            Command::Hint => self.hint(),
+            Command::Solution => Ok(self.solution()),
+            Command::Next => self.next(),
+            Command::Help => Ok(PUZZLE_HELP.to_owned()),
+            Command::Engine { .. }
+            | Command::Summon { .. }
+            | Command::Report
+            | Command::Clock
+            | Command::Back
+            | Command::Forward(_)
+            | Command::Start
+            | Command::End
+            | Command::Promote
+            | Command::Delete
+            | Command::Comment(_)
+            | Command::Annotate(_)
+            | Command::Record
+            | Command::Svg(_)
+            | Command::Analyze { .. } => {
+                Err("Not available in puzzle mode".to_owned())
+            }
+            Command::Quit => return None,
+        };
+        Some(result.unwrap_or_else(|e| e))
+    }
+
+    /// Executes commands line by line, until a quit command or the end of the input.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> io::Result<()> {
+        writeln!(output, "{}", self.introduction())?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let printed = match line.parse::<Command>() {
+                Ok(command) => match self.execute(&command) {
+                    Some(printed) => printed,
+                    None => break,
+                },
+                Err(e) => e.to_string(),
+            };
+            writeln!(output, "{}", printed)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::puzzle::PINNED;
+
+    use super::*;
+
+    const CORNER: &str = "\
+[ToMove \"Player1\"]
+[Player1 \"Duke a1, Footman e6 Flipped, Footman d6, Footman e4 Flipped\"]
+[Player2 \"Duke f6\"]
+
+e4-f5
+";
+
+    fn session() -> PuzzleSession {
+        PuzzleSession::new(Puzzle::parse_all(&format!("{}\n{}", PINNED, CORNER)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn unsound_puzzles_are_refused() {
+        let pinned: Puzzle = PINNED.parse().unwrap();
+        let slow = Puzzle { solution: vec![pinned.solution[0]; 5], ..pinned.clone() };
+        assert_eq!(Some("Puzzle 2 isn't a forced win in 3".to_owned()), PuzzleSession::new(vec![pinned, slow]).err());
+        assert_eq!(Some("No puzzles".to_owned()), PuzzleSession::new(Vec::new()).err());
+    }
+
+    fn run(session: &mut PuzzleSession, input: &str) -> String {
+        let mut output = Vec::new();
+        session.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn wrong_moves_are_rejected_and_right_ones_answered() {
+        let mut s = session();
+        let output = run(&mut s, "p e1-d1\np d3-c4\np c4-b4\n");
+        assert!(output.contains("Puzzle 1/2: Pinned against the edge\nPlayer1 to move and win in 2"));
+        assert!(output.contains("e1-d1 doesn't win in 2, try again"));
+        assert!(output.contains("Correct, Player2 answers b2-b3"));
+        assert!(output.contains("c4-b4 mates, solved!"));
+        assert!(s.is_solved());
+    }
+
+    #[test]
+    fn other_wins_are_accepted() {
+        let mut s = session();
+        s.apply(s.puzzle().solution[0]);
+        let defence = s.position.parse_move("b2-c2").unwrap();
+        s.apply(defence);
+        assert_eq!("e1-a1", s.solution().split(' ').next().unwrap());
+        assert!(run(&mut s, "p e1-a1\n").contains("e1-a1 mates, solved!"));
+    }
+
+    #[test]
+    fn hints_give_the_token_then_the_move() {
+        let mut s = session();
+        assert_eq!(Some("Use the token on d3".to_owned()), s.execute(&Command::Hint));
+        assert_eq!(Some("Play d3-c4".to_owned()), s.execute(&Command::Hint));
+        assert_eq!("d3-c4 b2-b3 c4-b4", s.solution());
+    }
+
+    #[test]
+    fn next_goes_through_the_puzzles() {
+        let mut s = session();
+        let output = run(&mut s, "next\nsolution\np e4-f5\nhint\nnext\n");
+        assert!(output.contains("Puzzle 2/2\nPlayer1 to move and win in 1"));
+        assert!(output.contains("e4-f5 mates, solved!"));
+        assert!(output.contains("Already solved, try 'next'"));
+        assert!(output.contains("That was the last puzzle"));
+    }
+}
@@ -0,0 +1,429 @@
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+
+use crate::analysis::blunders::{analyze_game, Thresholds};
+use crate::analysis::destinations::destinations;
+use crate::analysis::report::report;
+use crate::analysis::summon::summon_report;
+use crate::cli::command::{Command, HELP};
+use crate::engine::book::OpeningBook;
+use crate::engine::search::{search_with, Resources, SearchOptions, SearchResult};
+use crate::engine::tablebase::Tablebases;
+use crate::engine::tt::TranspositionTable;
+use crate::game::bitboard::square_at;
+use crate::game::clock::{Flagged, GameClock};
+use crate::game::legality::{check, Attempt};
+use crate::game::moves::{Move, MoveKind, MoveList};
+use crate::game::position::Position;
+use crate::game::state::GameState;
+use crate::game::tree::GameTree;
+use crate::game::units::UnitId;
+use crate::common::coordinates::Coordinates;
+use crate::view::dumb_printer::{
+    print_board, print_destinations, print_game_analysis, print_report, print_score, print_summon_report,
+};
+use crate::view::svg::{board_svg, SvgOptions};
+
+const UNSUPPORTED: &str = "Only standard units are supported";
+
+/// The legal moves, with summons listed once per square as "@" followed by the square, since
+/// they're the same move whichever unit is drawn.
+pub(crate) fn print_moves(p: &Position) -> String {
+    let mut moves = MoveList::new();
+    p.legal_moves(&mut moves);
+    let mut result: Vec<String> = Vec::new();
+    for m in moves.iter() {
+        let printed = match m.kind() {
+            MoveKind::Summon => format!("@{}", square_at(m.to())),
+            _ => m.to_string(),
+        };
+        if !result.contains(&printed) {
+            result.push(printed);
+        }
+    }
+    result.join(" ")
+}
+
+/// The legal move in [Move] notation, or the first summon on the square for "@" followed by it.
+/// Otherwise, the reason it's illegal, see [crate::game::legality::IllegalMove].
+pub(crate) fn find_move(p: &Position, text: &str) -> Result<Move, String> {
+    let illegal = |reason: String| format!("Illegal move '{}': {}", text, reason);
+    let attempt: Attempt = text.parse().map_err(illegal)?;
+    let summon_square = text.strip_prefix('@');
+    let mut moves = MoveList::new();
+    p.legal_moves(&mut moves);
+    let found = moves.iter().copied().find(|m| match (m.kind(), summon_square) {
+        (MoveKind::Summon, Some(square)) => square_at(m.to()).to_string() == square,
+        _ => m.to_string() == text,
+    });
+    match (found, check(&p.to_state(), &attempt)) {
+        (Some(m), _) => Ok(m),
+        (None, Err(reason)) => Err(illegal(reason.to_string())),
+        (None, Ok(())) => Err(format!("Illegal move '{}'", text)),
+    }
+}
+
+/// The board with the destinations of the token on c, see [print_destinations].
+pub(crate) fn show_token(gs: &GameState, c: Coordinates) -> String {
+    print_destinations(gs, c, &destinations(gs, c))
+}
+
+/// An interactive game, driven by text commands.
+pub struct Session {
+    /// The game so far, with any variations and annotations.
+    pub tree: GameTree,
+    /// The state at the tree's cursor.
+    pub state: GameState,
+    pub options: SearchOptions,
+    pub tablebases: Option<Tablebases>,
+    pub book: Option<OpeningBook>,
+    /// Started for the player to move by whoever sets it, and pressed after every move.
+    pub clock: Option<GameClock>,
+}
+
+impl Session {
+    pub fn new(tree: GameTree) -> Session {
+        let state = tree.state();
+        Session { tree, state, options: SearchOptions::default(), tablebases: None, book: None, clock: None }
+    }
+
+    pub(crate) fn position(&self) -> Result<Position, String> {
+        Position::from_state(&self.state).ok_or_else(|| UNSUPPORTED.to_owned())
+    }
+
+    fn print_moves(&self) -> Result<String, String> {
+        Ok(print_moves(&self.position()?))
+    }
+
+    /// Plays the move, and describes it, e.g., "Player1 played c1-c2".
+    pub(crate) fn make_move(&mut self, text: &str) -> Result<String, String> {
+        if let Some(outcome) = self.outcome()? {
+            return Err(outcome);
+        }
+        let m = find_move(&self.position()?, text)?;
+        if let Some(clock) = self.clock.as_mut() {
+            clock.press().map_err(|flagged| flagged.to_string())?;
+        }
+        let player = self.state.current_player_turn;
+        Ok(match m.to_game_move() {
+            Some(gm) => {
+                self.state.make_move(&gm);
+                self.tree.play(m);
+                format!("{:?} played {}", player, m)
+            }
+            None => {
+                let c = square_at(m.to());
+                let summoned = self.state.summon(c).map(|t| t.token.name.clone()).unwrap();
+                self.tree.play(Move::summon(m.to(), UnitId::from_name(&summoned).unwrap()));
+                format!("{:?} summoned {} on {}", player, summoned, c)
+            }
+        })
+    }
+
+    fn play(&mut self, text: &str) -> Result<String, String> {
+        let mut result = self.make_move(text)?;
+        result.push('\n');
+        result.push_str(&print_board(&self.state));
+        if let Some(clock) = self.clock.as_ref() {
+            result.push('\n');
+            result.push_str(&clock.to_string());
+        }
+        if let Some(outcome) = self.outcome()? {
+            result.push('\n');
+            result.push_str(&outcome);
+        }
+        Ok(result)
+    }
+
+    /// The board at the tree's cursor, the last move's annotations, and the moves which may follow.
+    fn show(&self) -> String {
+        let mut lines = vec![print_board(&self.state)];
+        let (comment, eval) = match self.tree.current() {
+            Some(node) => (&node.comment, node.eval),
+            None => (&self.tree.comment, None),
+        };
+        if let Some(eval) = eval {
+            lines.push(format!("Evaluation: {}", print_score(eval)));
+        }
+        if !comment.is_empty() {
+            lines.push(comment.clone());
+        }
+        let variations: Vec<String> =
+            self.tree.variations().iter().enumerate().map(|(i, n)| format!("{}. {}", i + 1, n.m)).collect();
+        if !variations.is_empty() {
+            lines.push(format!("Next: {}", variations.join(" ")));
+        }
+        lines.join("\n")
+    }
+
+    /// Moves the tree's cursor or edits the tree, for the commands from [Command::Back] to
+    /// [Command::Delete].
+    fn navigate(&mut self, command: &Command) -> Result<String, String> {
+        if self.clock.is_some() {
+            return Err("Not during a timed game".to_owned());
+        }
+        let (done, error) = match command {
+            Command::Back => (self.tree.back(), "Already at the start"),
+            Command::Forward(n) => (self.tree.forward(n - 1), "No such variation"),
+            Command::Start => {
+                self.tree.to_start();
+                (true, "")
+            }
+            Command::End => {
+                self.tree.to_end();
+                (true, "")
+            }
+            Command::Promote => (self.tree.promote(), "Already on the main line"),
+            Command::Delete => (self.tree.delete(), "No move to delete"),
+            _ => unreachable!("Not a navigation command"),
+        };
+        if !done {
+            return Err(error.to_owned());
+        }
+        self.state = self.tree.state();
+        Ok(self.show())
+    }
+
+    /// [None] while the game is still on.
+    pub(crate) fn outcome(&self) -> Result<Option<String>, String> {
+        if let Some(flagged) = self.clock.as_ref().and_then(|c| c.flagged()) {
+            return Ok(Some(Flagged(flagged).to_string()));
+        }
+        let p = self.position()?;
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        Ok(if !moves.is_empty() {
+            None
+        } else if p.is_in_guard() {
+            Some(format!("Checkmate, {:?} wins", p.to_move.other()))
+        } else {
+            Some("Stalemate".to_owned())
+        })
+    }
+
+    /// Searches the position at the tree's cursor, with the session's options unless a depth is
+    /// given.
+    pub(crate) fn search(&self, depth: Option<u32>) -> Result<SearchResult, String> {
+        let options = SearchOptions { depth: depth.unwrap_or(self.options.depth), ..self.options };
+        let tt = TranspositionTable::new(options.table_size);
+        let resources = Resources { tt: &tt, tablebases: self.tablebases.as_ref(), book: self.book.as_ref() };
+        Ok(search_with(&self.position()?, &options, &resources))
+    }
+
+    /// Takes back the last move and deletes it from the tree, see [Command::Delete].
+    pub(crate) fn undo(&mut self) -> Result<(), String> {
+        self.navigate(&Command::Delete).map(|_| ())
+    }
+
+    fn engine(&self, depth: Option<u32>) -> Result<String, String> {
+        let result = self.search(depth)?;
+        Ok(match result.best_move {
+            Some(m) if result.from_book => format!("Book move: {}", m),
+            Some(m) => format!(
+                "Best move: {} {} (depth {}, {} nodes)",
+                m,
+                print_score(result.score),
+                result.depth,
+                result.nodes,
+            ),
+            None => "No legal moves".to_owned(),
+        })
+    }
+
+    fn summon(&self, depth: Option<u32>) -> Result<String, String> {
+        summon_report(&self.state, depth.unwrap_or(self.options.depth))
+            .map(|r| print_summon_report(&r))
+            .ok_or_else(|| UNSUPPORTED.to_owned())
+    }
+
+    /// The output of the command; errors are returned as text, since they're just printed as well.
+    /// [None] for [Command::Quit].
+    pub fn execute(&mut self, command: &Command) -> Option<String> {
+        let result = match command {
+            Command::Board => Ok(print_board(&self.state)),
+            Command::Moves => self.print_moves(),
+            Command::Show(c) => Ok(show_token(&self.state, *c)),
+            Command::Play(m) => self.play(m),
+            Command::Engine { depth } => self.engine(*depth),
+            Command::Summon { depth } => self.summon(*depth),
+            Command::Report => Ok(print_report(&self.state, &report(&self.state))),
+            Command::Clock => self.clock.as_ref().map(|c| c.to_string()).ok_or_else(|| "No clock".to_owned()),
+            Command::Back
+            | Command::Forward(_)
+            | Command::Start
+            | Command::End
+            | Command::Promote
+            | Command::Delete => self.navigate(command),
+            Command::Comment(text) => {
+                self.tree.set_comment(text);
+                Ok("Comment set".to_owned())
+            }
+            Command::Annotate(eval) => {
+                if self.tree.set_eval(*eval) {
+                    Ok("Evaluation set".to_owned())
+                } else {
+                    Err("No move to annotate".to_owned())
+                }
+            }
+            Command::Record => Ok(self.tree.to_string()),
+            Command::Svg(file) => {
+                let options = SvgOptions { last_move: self.tree.current().map(|n| n.m), ..SvgOptions::default() };
+                fs::write(file, board_svg(&self.state, &options))
+                    .map(|_| format!("Wrote {}", file))
+                    .map_err(|e| format!("{}: {}", file, e))
+            }
+            Command::Analyze { depth } => {
+                let tt = TranspositionTable::new(self.options.table_size);
+                let depth = depth.unwrap_or(self.options.depth);
+                Ok(print_game_analysis(&analyze_game(&self.tree.main_line(), depth, &Thresholds::default(), &tt)))
+            }
+            Command::Help => Ok(HELP.to_owned()),
+            Command::Hint | Command::Solution | Command::Next => Err("Only available in puzzle mode".to_owned()),
+            Command::Quit => return None,
+        };
+        Some(result.unwrap_or_else(|e| e))
+    }
+
+    /// Executes commands line by line, until a quit command or the end of the input.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> io::Result<()> {
+        writeln!(output, "{}", print_board(&self.state))?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let printed = match line.parse::<Command>() {
+                Ok(command) => match self.execute(&command) {
+                    Some(printed) => printed,
+                    None => break,
+                },
+                Err(e) => e.to_string(),
+            };
+            writeln!(output, "{}", printed)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::common::coordinates::Coordinates;
+    use crate::game::clock::ManualTime;
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::token::Owner;
+
+    use super::*;
+
+    fn session() -> Session {
+        let tree = GameTree::new(
+            [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Left, FootmenSetup::Sides)],
+            vec![UnitId::Footman],
+        );
+        let mut result = Session::new(tree);
+        result.options.depth = 2;
+        result
+    }
+
+    fn run(session: &mut Session, input: &str) -> String {
+        let mut output = Vec::new();
+        session.run(input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn moves_lists_summons_once() {
+        let s = session();
+        assert_eq!("c1-b1 c1-c2 e1-f1 e1-e2 @d2", s.print_moves().unwrap());
+    }
+
+    #[test]
+    fn play_applies_moves_and_summons() {
+        let mut s = session();
+        let output = run(&mut s, "play c1-c2\nplay xx\nplay c6-c5\nplay @c5\nquit\nplay c2-c3\n");
+        assert!(output.contains("Player1 played c1-c2"));
+        assert!(output.contains("Illegal move 'xx'"));
+        assert!(output.contains("Illegal move 'c6-c5': The token on c6 has no action to c5 on its current side"));
+        assert!(output.contains("Player2 summoned Footman on c5"));
+        assert_eq!(Owner::Player1, s.state.current_player_turn);
+        assert!(s.state.board.get(Coordinates { x: 2, y: 1 }).is_some());
+        assert!(s.state.board.get(Coordinates { x: 2, y: 2 }).is_none());
+    }
+
+    #[test]
+    fn engine_and_summon_reports() {
+        let mut s = session();
+        let output = run(&mut s, "engine 1\nsummon\n");
+        assert!(output.contains("Best move: "));
+        assert!(output.contains("Footman: 100%"));
+        assert!(output.contains("d2: "));
+    }
+
+    #[test]
+    fn clocks_are_pressed_and_flag_falls() {
+        let mut s = session();
+        let time = ManualTime::new();
+        let mut clock = GameClock::new("1m".parse().unwrap(), Box::new(time.clone()));
+        clock.start(Owner::Player1);
+        s.clock = Some(clock);
+        time.advance(Duration::from_secs(10));
+        let output = run(&mut s, "play c1-c2\nclock\n");
+        assert!(output.contains("Player1 0:50.0 | Player2 1:00.0*"));
+        time.advance(Duration::from_secs(61));
+        let output = run(&mut s, "play @c5\nplay c6-c5\n");
+        assert_eq!(2, output.matches("Player2 lost on time, Player1 wins").count());
+        assert_eq!(Owner::Player2, s.state.current_player_turn);
+        assert!(run(&mut s, "clock\n").contains("Player2 flagged"));
+    }
+
+    #[test]
+    fn moves_are_recorded_with_variations() {
+        let mut s = session();
+        let output = run(&mut s, "p c1-c2\np @c5\n<\nback\np e1-e2\ncomment Sharper\nannotate 0.3\nstart\n");
+        assert!(output.contains("Next: 1. c1-c2 2. e1-e2"));
+        assert_eq!(Owner::Player1, s.state.current_player_turn);
+        let output = run(&mut s, "> 2\nforward\n");
+        assert!(output.contains("Evaluation: +0.30\nSharper"));
+        assert!(output.contains("No such variation"));
+        run(&mut s, "promote\nstart\n> 2\nend\ndelete\n");
+        assert!(run(&mut s, "record\n").contains("\n1. e1-e2 {[%eval 30] Sharper} (1. c1-c2) *\n"));
+        assert_eq!(Owner::Player2, s.state.current_player_turn);
+        assert!(run(&mut s, "start\nannotate 1\n<\n").contains("No move to annotate\nAlready at the start"));
+    }
+
+    #[test]
+    fn analyze_summarizes_the_main_line() {
+        let mut s = session();
+        let output = run(&mut s, "p c1-c2\np b6-b5\nanalyze 1\n");
+        assert!(output.contains("Player1: 1 moves"));
+        assert!(output.contains("Player2: 1 moves"));
+    }
+
+    #[test]
+    fn show_explains_destinations() {
+        let mut s = session();
+        let output = run(&mut s, "show c1\nshow d1\np c1-c2\nshow c2\n");
+        assert!(output.contains("c2: Move\nb1: Move"));
+        assert!(output.contains("The token is blocked"));
+        assert!(output.contains("It's Player1's token, but it's Player2's turn"));
+    }
+
+    #[test]
+    fn svg_writes_the_board() {
+        let mut s = session();
+        let file = std::env::temp_dir().join(format!("duke_rust_session_{}.svg", std::process::id()));
+        let path = file.to_str().unwrap();
+        assert!(run(&mut s, &format!("p c1-c2\nsvg {}\n", path)).contains(&format!("Wrote {}", path)));
+        let svg = fs::read_to_string(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+        assert!(svg.contains("<line class=\"arrow\""));
+    }
+
+    #[test]
+    fn unknown_commands_are_reported() {
+        let mut s = session();
+        assert!(run(&mut s, "fly\n").contains("Unknown command 'fly'"));
+    }
+}
@@ -1,8 +1,26 @@
+use std::fmt;
 use std::mem;
 
-use crate::common::coordinates::Coordinates;
+use crate::common::coordinates::{Coordinates, Ray, Vector};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BoardError {
+    OutOfBounds(Coordinates),
+    Occupied(Coordinates),
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::OutOfBounds(c) => write!(f, "Coordinate {:?} is out of bounds", c),
+            BoardError::Occupied(c) => write!(f, "Coordinate {:?} is already occupied", c),
+        }
+    }
+}
+
+impl std::error::Error for BoardError {}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Board<A> {
     board: Vec<Vec<Option<A>>>,
     pub width: u16,
@@ -10,29 +28,51 @@ pub struct Board<A> {
 }
 
 impl<A> Board<A> {
-    pub fn square(side: u16) -> Board<A> {
-        let mut board = Vec::with_capacity(side.into());
-        for _ in 0..side {
-            let mut col = Vec::with_capacity(side.into());
-            for _ in 0..side {
-                col.push(None);
+    pub fn new(width: u16, height: u16) -> Board<A> {
+        let mut board = Vec::with_capacity(height.into());
+        for _ in 0..height {
+            let mut row = Vec::with_capacity(width.into());
+            for _ in 0..width {
+                row.push(None);
             }
-            board.push(col);
+            board.push(row);
         }
 
         Board {
-            width: side,
-            height: side,
+            width,
+            height,
             board,
         }
     }
+    pub fn square(side: u16) -> Board<A> {
+        Board::new(side, side)
+    }
+    /// Panics if the rows are ragged, i.e., not all of the same length.
+    pub fn from_rows(rows: Vec<Vec<Option<A>>>) -> Board<A> {
+        let width = rows.first().map_or(0, |r| r.len());
+        assert!(rows.iter().all(|r| r.len() == width), "All rows must have the same width");
+        Board {
+            width: width as u16,
+            height: rows.len() as u16,
+            board: rows,
+        }
+    }
     fn verify_bounds(&self, c: Coordinates) -> () {
-        assert!(self.is_in_bounds(c), "Coordinate {:?} is out of bounds", c)
+        if let Err(e) = self.check_bounds(c) {
+            panic!("{}", e)
+        }
+    }
+    fn check_bounds(&self, c: Coordinates) -> Result<(), BoardError> {
+        if self.is_in_bounds(c) {
+            Ok(())
+        } else {
+            Err(BoardError::OutOfBounds(c))
+        }
     }
     fn place(&mut self, c: Coordinates, a: Option<A>) -> Option<A> {
         self.verify_bounds(c);
-        let column = self.board.get_mut(usize::from(c.y)).unwrap();
-        mem::replace(&mut column[usize::from(c.x)], a)
+        let row = self.board.get_mut(usize::from(c.y)).unwrap();
+        mem::replace(&mut row[usize::from(c.x)], a)
     }
     pub fn put(&mut self, c: Coordinates, a: A) -> Option<A> {
         self.place(c, Some(a))
@@ -48,6 +88,19 @@ impl<A> Board<A> {
     pub fn remove(&mut self, c: Coordinates) -> Option<A> {
         self.place(c, None)
     }
+    pub fn try_put(&mut self, c: Coordinates, a: A) -> Result<Option<A>, BoardError> {
+        self.check_bounds(c).map(|_| self.put(c, a))
+    }
+    pub fn try_get(&self, c: Coordinates) -> Result<Option<&A>, BoardError> {
+        self.check_bounds(c).map(|_| self.get(c))
+    }
+    pub fn try_get_mut(&mut self, c: Coordinates) -> Result<Option<&mut A>, BoardError> {
+        self.check_bounds(c)?;
+        Ok(self.get_mut(c))
+    }
+    pub fn try_remove(&mut self, c: Coordinates) -> Result<Option<A>, BoardError> {
+        self.check_bounds(c).map(|_| self.remove(c))
+    }
     pub fn is_in_bounds(&self, c: Coordinates) -> bool {
         c.x < self.width && c.y < self.height
     }
@@ -61,23 +114,44 @@ impl<A> Board<A> {
         self.get(c).is_none()
     }
 
+    /// Panics if src is out of bounds.
+    pub fn ray(&self, src: Coordinates, direction: Vector) -> Ray {
+        self.verify_bounds(src);
+        src.ray(direction, self.width, self.height)
+    }
+
     pub fn rows(&self) -> &Vec<Vec<Option<A>>> {
         &self.board
     }
 
+    /// Coordinates are ordered row by row, i.e., the same order as [Board::rows] flattened.
     pub fn coordinates(&self) -> Vec<Coordinates> {
-        (0..self.width)
-            .flat_map(move |x| (0..self.height).map(move |y| Coordinates { x, y }))
+        (0..self.height)
+            .flat_map(move |y| (0..self.width).map(move |x| Coordinates { x, y }))
             .collect()
     }
 
+    /// Iterates over all cells, row by row.
+    pub fn iter(&self) -> impl Iterator<Item=(Coordinates, Option<&A>)> {
+        self.board.iter().enumerate().flat_map(|(y, row)| {
+            row.iter().enumerate().map(move |(x, a)| (Coordinates { x: x as u16, y: y as u16 }, a.as_ref()))
+        })
+    }
+
+    pub fn map<B, F: Fn(&A) -> B>(&self, f: F) -> Board<B> {
+        Board {
+            board: self.board.iter().map(|row| row.iter().map(|a| a.as_ref().map(&f)).collect()).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+
     pub fn all_coordinated_values(&self) -> Vec<(Coordinates, Option<&A>)> {
-        self.coordinates() .iter().map(|c| (*c, self.get(*c))).collect()
+        self.iter().collect()
     }
     pub fn active_coordinates(&self) -> Vec<(Coordinates, &A)> {
-        self.coordinates()
-            .iter()
-            .filter_map(|c| self.get(*c).map(|e| (*c, e)))
+        self.iter()
+            .filter_map(|(c, a)| a.map(|e| (c, e)))
             .collect()
     }
 }
@@ -105,7 +179,7 @@ mod test {
     fn get_mut_indexing() {
         let mut board = Board::square(2);
         board.put(Coordinates { x: 1, y: 0 }, 1);
-        let mut c = board.get_mut(Coordinates { x: 1, y: 0 }).unwrap();
+        let c = board.get_mut(Coordinates { x: 1, y: 0 }).unwrap();
         *c += 1;
         assert_some!(
             2,
@@ -127,4 +201,157 @@ mod test {
             board.rows(),
         )
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn new_rectangular_board_has_width_columns_and_height_rows() {
+        let board: Board<i32> = Board::new(3, 2);
+        assert_eq!(
+            &vec![vec![None, None, None], vec![None, None, None]],
+            board.rows(),
+        )
+    }
+
+    #[test]
+    fn rectangular_bounds() {
+        let board: Board<i32> = Board::new(3, 2);
+        assert!(board.is_in_bounds(Coordinates { x: 2, y: 1 }));
+        assert!(board.is_out_of_bounds(Coordinates { x: 1, y: 2 }));
+        assert!(board.is_out_of_bounds(Coordinates { x: 3, y: 0 }));
+    }
+
+    #[test]
+    fn rectangular_put_and_get() {
+        let mut board = Board::new(3, 2);
+        board.put(Coordinates { x: 2, y: 1 }, 5);
+        assert_some!(5, board.get(Coordinates { x: 2, y: 1 }).cloned());
+        assert_eq!(
+            &vec![vec![None, None, None], vec![None, None, Some(5)]],
+            board.rows(),
+        )
+    }
+
+    #[test]
+    #[should_panic]
+    fn rectangular_get_out_of_bounds_panics() {
+        let board: Board<i32> = Board::new(3, 2);
+        board.get(Coordinates { x: 0, y: 2 });
+    }
+
+    #[test]
+    fn coordinates_of_rectangular_board_are_row_major() {
+        let board: Board<i32> = Board::new(3, 2);
+        assert_eq!(
+            vec![
+                Coordinates { x: 0, y: 0 },
+                Coordinates { x: 1, y: 0 },
+                Coordinates { x: 2, y: 0 },
+                Coordinates { x: 0, y: 1 },
+                Coordinates { x: 1, y: 1 },
+                Coordinates { x: 2, y: 1 },
+            ],
+            board.coordinates(),
+        )
+    }
+
+    #[test]
+    fn active_coordinates_of_rectangular_board() {
+        let mut board = Board::new(2, 3);
+        board.put(Coordinates { x: 1, y: 2 }, 'a');
+        board.put(Coordinates { x: 0, y: 1 }, 'b');
+        assert_eq!(
+            vec![(Coordinates { x: 0, y: 1 }, &'b'), (Coordinates { x: 1, y: 2 }, &'a')],
+            board.active_coordinates(),
+        )
+    }
+
+    #[test]
+    fn iter_matches_coordinates() {
+        let mut board = Board::new(3, 2);
+        board.put(Coordinates { x: 1, y: 1 }, 4);
+        assert_eq!(
+            board.coordinates(),
+            board.iter().map(|e| e.0).collect::<Vec<_>>(),
+        );
+        assert_eq!(
+            vec![None, None, None, None, Some(&4), None],
+            board.iter().map(|e| e.1).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn map_keeps_dimensions_and_empty_cells() {
+        let mut board = Board::new(3, 2);
+        board.put(Coordinates { x: 2, y: 0 }, 4);
+        let mapped = board.map(|e| e * 2);
+        assert_eq!(3, mapped.width);
+        assert_eq!(2, mapped.height);
+        assert_eq!(
+            &vec![vec![None, None, Some(8)], vec![None, None, None]],
+            mapped.rows(),
+        )
+    }
+
+    #[test]
+    fn ray_respects_rectangular_bounds() {
+        let board: Board<i32> = Board::new(3, 5);
+        assert_eq!(4, board.ray(Coordinates { x: 0, y: 0 }, Vector::UP).count());
+        assert_eq!(2, board.ray(Coordinates { x: 0, y: 0 }, Vector::UP_RIGHT).count());
+    }
+
+    #[test]
+    fn from_rows() {
+        let board = Board::from_rows(vec![vec![Some(1), None, None], vec![None, None, Some(2)]]);
+        assert_eq!(3, board.width);
+        assert_eq!(2, board.height);
+        assert_some!(2, board.get(Coordinates { x: 2, y: 1 }).cloned());
+    }
+
+    #[test]
+    fn try_get_out_of_bounds() {
+        let board: Board<i32> = Board::new(3, 2);
+        assert_eq!(
+            Err(BoardError::OutOfBounds(Coordinates { x: 0, y: 2 })),
+            board.try_get(Coordinates { x: 0, y: 2 }),
+        )
+    }
+
+    #[test]
+    fn try_get_in_bounds() {
+        let mut board = Board::new(3, 2);
+        board.put(Coordinates { x: 2, y: 1 }, 5);
+        assert_eq!(Ok(Some(&5)), board.try_get(Coordinates { x: 2, y: 1 }));
+        assert_eq!(Ok(None), board.try_get(Coordinates { x: 1, y: 1 }));
+    }
+
+    #[test]
+    fn try_put_out_of_bounds_leaves_board_unchanged() {
+        let mut board = Board::new(2, 2);
+        assert_eq!(
+            Err(BoardError::OutOfBounds(Coordinates { x: 2, y: 0 })),
+            board.try_put(Coordinates { x: 2, y: 0 }, 1),
+        );
+        assert!(board.active_coordinates().is_empty());
+    }
+
+    #[test]
+    fn try_put_returns_previous_value() {
+        let mut board = Board::new(2, 2);
+        assert_eq!(Ok(None), board.try_put(Coordinates { x: 1, y: 1 }, 1));
+        assert_eq!(Ok(Some(1)), board.try_put(Coordinates { x: 1, y: 1 }, 2));
+    }
+
+    #[test]
+    fn try_get_mut_out_of_bounds() {
+        let mut board: Board<i32> = Board::new(2, 2);
+        assert_eq!(
+            Err(BoardError::OutOfBounds(Coordinates { x: 5, y: 5 })),
+            board.try_get_mut(Coordinates { x: 5, y: 5 }),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_rows_panics_on_ragged_rows() {
+        Board::from_rows(vec![vec![Some(1), None], vec![None]]);
+    }
+}
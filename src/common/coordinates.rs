@@ -1,14 +1,196 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Mul, Neg};
+
 use fstrings::*;
 
 use crate::common::utils::Distance;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Coordinates {
     pub x: u16,
     pub y: u16,
 }
 
+/// Chess like notation, e.g., {x: 2, y: 0} => "c1".
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
+    }
+}
+
+impl std::str::FromStr for Coordinates {
+    type Err = String;
+
+    /// The inverse of Display, e.g., "c1" => {x: 2, y: 0}.
+    fn from_str(s: &str) -> Result<Coordinates, String> {
+        let invalid = || format!("Invalid square '{}'", s);
+        let mut chars = s.chars();
+        let file = chars.next().filter(|c| c.is_ascii_lowercase()).ok_or_else(invalid)?;
+        let rank: u16 = chars.as_str().parse().map_err(|_| invalid())?;
+        if rank == 0 {
+            return Err(invalid());
+        }
+        Ok(Coordinates { x: u16::from(file as u8 - b'a'), y: rank - 1 })
+    }
+}
+
+/// A signed displacement between two [Coordinates]. The y axis grows upwards, i.e., [Vector::UP]
+/// increases y.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Vector {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl Vector {
+    pub const UP: Vector = Vector { x: 0, y: 1 };
+    pub const DOWN: Vector = Vector { x: 0, y: -1 };
+    pub const LEFT: Vector = Vector { x: -1, y: 0 };
+    pub const RIGHT: Vector = Vector { x: 1, y: 0 };
+    pub const UP_LEFT: Vector = Vector { x: -1, y: 1 };
+    pub const UP_RIGHT: Vector = Vector { x: 1, y: 1 };
+    pub const DOWN_LEFT: Vector = Vector { x: -1, y: -1 };
+    pub const DOWN_RIGHT: Vector = Vector { x: 1, y: -1 };
+
+    /// The 8 unit directions: orthogonals first, then diagonals.
+    pub const DIRECTIONS: [Vector; 8] = [
+        Vector::UP,
+        Vector::DOWN,
+        Vector::LEFT,
+        Vector::RIGHT,
+        Vector::UP_LEFT,
+        Vector::UP_RIGHT,
+        Vector::DOWN_LEFT,
+        Vector::DOWN_RIGHT,
+    ];
+
+    /// The 8 L shaped offsets, à la chess knight.
+    pub const KNIGHT_OFFSETS: [Vector; 8] = [
+        Vector { x: 1, y: -2 },
+        Vector { x: 2, y: -1 },
+        Vector { x: 2, y: 1 },
+        Vector { x: 1, y: 2 },
+        Vector { x: -1, y: 2 },
+        Vector { x: -2, y: 1 },
+        Vector { x: -2, y: -1 },
+        Vector { x: -1, y: -2 },
+    ];
+
+    pub fn new(x: i16, y: i16) -> Vector {
+        Vector { x, y }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.x == 0 && self.y == 0
+    }
+
+    /// True for horizontal, vertical, and 45° diagonal vectors.
+    pub fn is_linear(&self) -> bool {
+        !self.is_zero() && (self.x == 0 || self.y == 0 || self.x.abs() == self.y.abs())
+    }
+
+    /// The unit step of a linear vector, e.g., (2, -2) => (1, -1). Panics if the vector isn't linear.
+    pub fn direction(&self) -> Vector {
+        assert!(self.is_linear(), "{:?} has no linear direction", self);
+        Vector { x: self.x.signum(), y: self.y.signum() }
+    }
+
+    /// Chebyshev distance, i.e., the number of king steps this vector spans.
+    pub fn length(&self) -> u16 {
+        self.x.unsigned_abs().max(self.y.unsigned_abs())
+    }
+
+    pub fn vertical_flipped(&self) -> Vector {
+        Vector { x: self.x, y: -self.y }
+    }
+
+    pub fn horizontal_flipped(&self) -> Vector {
+        Vector { x: -self.x, y: self.y }
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+    fn add(self, other: Vector) -> Vector {
+        Vector { x: self.x + other.x, y: self.y + other.y }
+    }
+}
+
+impl Neg for Vector {
+    type Output = Vector;
+    fn neg(self) -> Vector {
+        Vector { x: -self.x, y: -self.y }
+    }
+}
+
+impl Mul<i16> for Vector {
+    type Output = Vector;
+    fn mul(self, n: i16) -> Vector {
+        Vector { x: self.x * n, y: self.y * n }
+    }
+}
+
+/// Returns [None] if the result would have a negative component. Note that this doesn't check for
+/// any board bounds, since [Coordinates] aren't aware of the board.
+impl Add<Vector> for Coordinates {
+    type Output = Option<Coordinates>;
+    fn add(self, v: Vector) -> Option<Coordinates> {
+        let x = u16::try_from(i32::from(self.x) + i32::from(v.x)).ok()?;
+        let y = u16::try_from(i32::from(self.y) + i32::from(v.y)).ok()?;
+        Some(Coordinates { x, y })
+    }
+}
+
+/// Iterates over the squares in a single direction, excluding the starting point, until the edge
+/// of a width × height board.
+#[derive(Debug, Clone)]
+pub struct Ray {
+    current: Coordinates,
+    direction: Vector,
+    width: u16,
+    height: u16,
+}
+
+impl Iterator for Ray {
+    type Item = Coordinates;
+    fn next(&mut self) -> Option<Coordinates> {
+        let next = (self.current + self.direction)
+            .filter(|c| c.x < self.width && c.y < self.height)?;
+        self.current = next;
+        Some(next)
+    }
+}
+
 impl Coordinates {
+    /// The vector v such that self + v == other.
+    pub fn vector_to(&self, other: Coordinates) -> Vector {
+        Vector {
+            x: (i32::from(other.x) - i32::from(self.x)) as i16,
+            y: (i32::from(other.y) - i32::from(self.y)) as i16,
+        }
+    }
+
+    /// Panics if direction is the zero vector.
+    pub fn ray(&self, direction: Vector, width: u16, height: u16) -> Ray {
+        assert!(!direction.is_zero(), "Can't create a ray with no direction");
+        Ray { current: *self, direction, width, height }
+    }
+
+    /// Rays in all of [Vector::DIRECTIONS], in that order.
+    pub fn rays(&self, width: u16, height: u16) -> Vec<Ray> {
+        Vector::DIRECTIONS.iter().map(|d| self.ray(*d, width, height)).collect()
+    }
+
+    /// All [Vector::KNIGHT_OFFSETS] from self which are inside a width × height board.
+    pub fn knight_offsets(&self, width: u16, height: u16) -> Vec<Coordinates> {
+        Vector::KNIGHT_OFFSETS
+            .iter()
+            .filter_map(|v| *self + *v)
+            .filter(|c| c.x < width && c.y < height)
+            .collect()
+    }
+
     /// Panics if src isn't on a linear (horizontal, vertical, or bishop-like diagonal to dst,
     /// or if src == dst.
     pub fn linear_path_to(&self, dst: Coordinates) -> Vec<Coordinates> {
@@ -152,4 +334,98 @@ mod test {
             Coordinates { x: 7, y: 2 }.linear_path_to(Coordinates { x: 3, y: 6 })
         )
     }
+
+    #[test]
+    fn parse_is_inverse_of_display() {
+        let c = Coordinates { x: 2, y: 11 };
+        assert_eq!(Ok(c), c.to_string().parse());
+        assert!("c0".parse::<Coordinates>().is_err());
+        assert!("C1".parse::<Coordinates>().is_err());
+        assert!("c".parse::<Coordinates>().is_err());
+    }
+
+    #[test]
+    fn add_vector() {
+        assert_eq!(
+            Some(Coordinates { x: 3, y: 1 }),
+            Coordinates { x: 1, y: 2 } + Vector::new(2, -1),
+        )
+    }
+
+    #[test]
+    fn add_vector_below_zero_is_none() {
+        assert_eq!(None, Coordinates { x: 1, y: 2 } + Vector::new(-2, 0));
+        assert_eq!(None, Coordinates { x: 1, y: 2 } + Vector::new(0, -3));
+    }
+
+    #[test]
+    fn vector_to_is_inverse_of_add() {
+        let src = Coordinates { x: 4, y: 1 };
+        let dst = Coordinates { x: 2, y: 3 };
+        assert_eq!(Vector::new(-2, 2), src.vector_to(dst));
+        assert_eq!(Some(dst), src + src.vector_to(dst));
+    }
+
+    #[test]
+    fn direction_of_linear_vector() {
+        assert_eq!(Vector::UP_RIGHT, Vector::new(3, 3).direction());
+        assert_eq!(Vector::LEFT, Vector::new(-2, 0).direction());
+    }
+
+    #[test]
+    #[should_panic]
+    fn direction_of_l_shaped_vector_panics() {
+        Vector::new(1, 2).direction();
+    }
+
+    #[test]
+    fn ray_stops_at_the_board_edge() {
+        assert_eq!(
+            vec![
+                Coordinates { x: 3, y: 2 },
+                Coordinates { x: 4, y: 3 },
+                Coordinates { x: 5, y: 4 },
+            ],
+            Coordinates { x: 2, y: 1 }.ray(Vector::UP_RIGHT, 6, 5).collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn ray_stops_at_zero() {
+        assert_eq!(
+            vec![Coordinates { x: 1, y: 0 }, Coordinates { x: 0, y: 0 }],
+            Coordinates { x: 2, y: 0 }.ray(Vector::LEFT, 6, 6).collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn ray_from_edge_is_empty() {
+        assert_eq!(0, Coordinates { x: 5, y: 3 }.ray(Vector::RIGHT, 6, 6).count());
+    }
+
+    #[test]
+    fn rays_cover_all_directions() {
+        let lengths: Vec<usize> = Coordinates { x: 1, y: 1 }.rays(4, 3).into_iter().map(|r| r.count()).collect();
+        // Up, down, left, right, up-left, up-right, down-left, down-right
+        assert_eq!(vec![1, 1, 1, 2, 1, 1, 1, 1], lengths);
+    }
+
+    #[test]
+    fn knight_offsets_in_corner() {
+        assert_eq!(
+            vec![Coordinates { x: 2, y: 1 }, Coordinates { x: 1, y: 2 }],
+            Coordinates { x: 0, y: 0 }.knight_offsets(6, 6),
+        )
+    }
+
+    #[test]
+    fn knight_offsets_in_center() {
+        assert_eq!(8, Coordinates { x: 2, y: 2 }.knight_offsets(6, 6).len());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("c1", Coordinates { x: 2, y: 0 }.to_string());
+        assert_eq!("a6", Coordinates { x: 0, y: 5 }.to_string());
+    }
 }
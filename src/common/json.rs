@@ -0,0 +1,399 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Just enough JSON for the HTTP API and the HTML export, without any dependency.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keeps the order of the keys.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+    }
+
+    /// The value of the key, [None] if it's missing or this isn't an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// [None] unless it's a whole number which fits.
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_f64().filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= f64::from(u32::MAX)).map(|n| n as u32)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(s.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(n: u32) -> Json {
+        Json::Number(f64::from(n))
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Json {
+        Json::Number(f64::from(n))
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl<A: Into<Json>> From<Option<A>> for Json {
+    fn from(o: Option<A>) -> Json {
+        o.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<A: Into<Json>> From<Vec<A>> for Json {
+    fn from(items: Vec<A>) -> Json {
+        Json::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A string literal, with '<' escaped too, so it can't end a script it's embedded in.
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '<' => write!(f, "\\u003c")?,
+            c if u32::from(c) < 0x20 => write!(f, "\\u{:04x}", u32::from(c))?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Compact, i.e., without any whitespace. Whole numbers are written without a fraction.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(s) => write_string(f, s),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum JsonError {
+    UnexpectedEnd,
+    /// The character at the byte offset.
+    Unexpected(char, usize),
+    /// Arrays and objects nested deeper than [MAX_NESTING], at the byte offset.
+    TooDeep(usize),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::UnexpectedEnd => write!(f, "Unexpected end of JSON"),
+            JsonError::Unexpected(c, i) => write!(f, "Unexpected '{}' at {} in JSON", c, i),
+            JsonError::TooDeep(i) => write!(f, "JSON nested deeper than {} at {}", MAX_NESTING, i),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Parsing recurses into arrays and objects, so deeper ones are refused rather than overflowing
+/// the stack.
+pub const MAX_NESTING: usize = 64;
+
+struct Parser<'a> {
+    text: &'a str,
+    i: usize,
+    /// The arrays and objects being parsed.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.i..].chars().next()
+    }
+
+    fn unexpected(&self) -> JsonError {
+        self.peek().map_or(JsonError::UnexpectedEnd, |c| JsonError::Unexpected(c, self.i))
+    }
+
+    fn skip_whitespace(&mut self) -> () {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.i += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), JsonError> {
+        if self.peek() != Some(c) {
+            return Err(self.unexpected());
+        }
+        self.i += c.len_utf8();
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, JsonError> {
+        for c in word.chars() {
+            self.expect(c)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        let result = match self.peek().ok_or(JsonError::UnexpectedEnd)? {
+            'n' => self.literal("null", Json::Null),
+            't' => self.literal("true", Json::Bool(true)),
+            'f' => self.literal("false", Json::Bool(false)),
+            '"' => self.string().map(Json::String),
+            '[' => self.nested(Parser::array),
+            '{' => self.nested(Parser::object),
+            c if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => Err(self.unexpected()),
+        };
+        self.skip_whitespace();
+        result
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, JsonError>) -> Result<Json, JsonError> {
+        if self.depth == MAX_NESTING {
+            return Err(JsonError::TooDeep(self.i));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.i;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(c)) {
+            self.i += 1;
+        }
+        self.text[start..self.i]
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| JsonError::Unexpected(self.text[start..].chars().next().unwrap(), start))
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut result = String::new();
+        loop {
+            let c = self.peek().ok_or(JsonError::UnexpectedEnd)?;
+            self.i += c.len_utf8();
+            match c {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escaped = self.peek().ok_or(JsonError::UnexpectedEnd)?;
+                    self.i += 1;
+                    result.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let hex = self.text.get(self.i..self.i + 4).ok_or(JsonError::UnexpectedEnd)?;
+                            let code = u32::from_str_radix(hex, 16).map_err(|_| self.unexpected())?;
+                            self.i += 4;
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        '"' | '\\' | '/' => escaped,
+                        _ => return Err(JsonError::Unexpected(escaped, self.i - 1)),
+                    });
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let mut items = Vec::new();
+        if self.peek() == Some(']') {
+            self.i += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek() {
+                Some(',') => self.i += 1,
+                Some(']') => {
+                    self.i += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.expect('{')?;
+        self.skip_whitespace();
+        let mut fields = Vec::new();
+        if self.peek() == Some('}') {
+            self.i += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            match self.peek() {
+                Some(',') => self.i += 1,
+                Some('}') => {
+                    self.i += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+}
+
+impl FromStr for Json {
+    type Err = JsonError;
+
+    fn from_str(s: &str) -> Result<Json, JsonError> {
+        let mut parser = Parser { text: s, i: 0, depth: 0 };
+        let result = parser.value()?;
+        match parser.peek() {
+            None => Ok(result),
+            Some(_) => Err(parser.unexpected()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prints_compact_json() {
+        let json = Json::object(vec![
+            ("name", "a\"b\\c\nd\t</".into()),
+            ("n", 3u32.into()),
+            ("x", Json::Number(-0.5)),
+            ("none", Option::<u32>::None.into()),
+            ("list", vec![true, false].into()),
+        ]);
+        assert_eq!(
+            r#"{"name":"a\"b\\c\nd\u0009\u003c/","n":3,"x":-0.5,"none":null,"list":[true,false]}"#,
+            json.to_string(),
+        );
+    }
+
+    #[test]
+    fn parses_what_it_prints() {
+        let text = r#" { "move" : "c1-c2", "depth": 3, "deep": [1.5e1, {"a": null}, []], "u": "é\/" } "#;
+        let json: Json = text.parse().unwrap();
+        assert_eq!(Some("c1-c2"), json.get("move").and_then(Json::as_str));
+        assert_eq!(Some(3), json.get("depth").and_then(Json::as_u32));
+        assert_eq!(Some(15.0), json.get("deep").and_then(|d| d.as_array()).and_then(|d| d[0].as_f64()));
+        assert_eq!(Some("é/"), json.get("u").and_then(Json::as_str));
+        assert_eq!(Ok(json.clone()), json.to_string().parse());
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!(Err(JsonError::UnexpectedEnd), "{\"a\": ".parse::<Json>());
+        assert_eq!(Err(JsonError::Unexpected('x', 5)), "[1, 2x]".parse::<Json>());
+        assert_eq!(Err(JsonError::Unexpected('}', 3)), "{} }".parse::<Json>());
+        assert_eq!(Err(JsonError::UnexpectedEnd), "tru".parse::<Json>());
+    }
+
+    #[test]
+    fn refuses_deep_nesting() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(nested(MAX_NESTING).parse::<Json>().is_ok());
+        assert_eq!(Err(JsonError::TooDeep(MAX_NESTING)), nested(MAX_NESTING + 1).parse::<Json>());
+        assert!(matches!("[".repeat(60_000).parse::<Json>(), Err(JsonError::TooDeep(_))));
+        assert!(matches!("[{\"a\":".repeat(30_000).parse::<Json>(), Err(JsonError::TooDeep(_))));
+    }
+}
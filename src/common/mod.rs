@@ -1,4 +1,5 @@
 pub mod board;
 pub mod coordinates;
+pub mod json;
 pub mod tests;
 pub mod utils;
@@ -1,7 +1,16 @@
 #[macro_export]
 macro_rules! assert_not {
-    ($b: expr) => {assert!($b)};
-    ($b: expr, $msg: expr) => {assert!($b, $msg)};
+    ($b: expr) => {assert!(!$b)};
+    ($b: expr, $msg: expr) => {assert!(!$b, $msg)};
+}
+
+#[macro_export]
+macro_rules! hashmap {
+    ($( $key: expr => $val: expr ),*) => {{
+         let mut map = ::std::collections::HashMap::new();
+         $( map.insert($key, $val); )*
+         map
+    }}
 }
 
 pub trait Distance {
@@ -28,14 +37,6 @@ impl Distance for u16 {
     }
 }
 
-fn diff_or_zero(x: usize, other: usize) -> usize {
-    if x < other {
-        x - other
-    } else {
-        0
-    }
-}
-
 pub trait MkString {
     fn mk_string_full(&self, start: &str, separator: &str, end: &str) -> String;
     fn mk_string(&self, sep: &str) -> String {
@@ -69,7 +70,7 @@ fn intercalate_aux<A: Clone>(v: &mut Vec<A>, a: A, starting_index: usize) -> ()
     (2 + starting_index..v.len()).step_by(2).rev().for_each(|i| v.swap(i / 2 + starting_index, i));
 }
 
-fn reserved_length_for_intercalated_items<T>(v: &Vec<T>) -> usize {
+fn reserved_length_for_intercalated_items<T>(v: &[T]) -> usize {
     if v.is_empty() {
         0
     } else {
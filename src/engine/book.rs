@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+use std::io::{Read, Write};
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+use crate::game::moves::Move;
+use crate::game::position::Position;
+use crate::game::record::{GameRecord, GameResult};
+
+const MAGIC: &[u8; 8] = b"DUKEBK1\0";
+
+/// Moves played in recorded games, keyed by the [Position::zobrist] of the position they were played
+/// in. Moves are weighted by how their games ended for the player who made them: 2 for a win, 1 for
+/// a draw or an unfinished game, and none for a loss.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OpeningBook {
+    entries: HashMap<u64, Vec<(Move, u32)>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> OpeningBook {
+        OpeningBook::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the first plies of the game. Summons are added by square, whichever unit was drawn, see
+    /// [Position::canonical].
+    pub fn add(&mut self, record: &GameRecord, plies: usize) -> () {
+        let positions = record.positions();
+        for (p, m) in positions.iter().zip(record.moves.iter()).take(plies) {
+            let weight = match record.result {
+                GameResult::Win(winner) if winner == p.to_move => 2,
+                GameResult::Win(_) => continue,
+                GameResult::Draw | GameResult::Unfinished => 1,
+            };
+            let m = p.canonical(*m);
+            let moves = self.entries.entry(p.zobrist()).or_default();
+            match moves.iter_mut().find(|(e, _)| *e == m) {
+                Some((_, w)) => *w += weight,
+                None => moves.push((m, weight)),
+            }
+        }
+    }
+
+    pub fn build(records: &[GameRecord], plies: usize) -> OpeningBook {
+        let mut result = OpeningBook::new();
+        for r in records {
+            result.add(r, plies);
+        }
+        result
+    }
+
+    /// The book's legal moves in the position, heaviest first. Summons are by square, see
+    /// [OpeningBook::add].
+    pub fn probe(&self, p: &Position) -> Vec<(Move, u32)> {
+        let mut result: Vec<(Move, u32)> = self
+            .entries
+            .get(&p.zobrist())
+            .map(|moves| moves.iter().filter(|(m, _)| p.is_legal(*m)).cloned().collect())
+            .unwrap_or_default();
+        result.sort_by_key(|(m, w)| (std::cmp::Reverse(*w), m.to_bits()));
+        result
+    }
+
+    /// The heaviest move, [None] if the position isn't in the book.
+    pub fn best(&self, p: &Position) -> Option<Move> {
+        self.probe(p).first().map(|(m, _)| *m)
+    }
+
+    /// A random move, with probability proportional to its weight.
+    pub fn choose<R: Rng>(&self, p: &Position, rng: &mut R) -> Option<Move> {
+        let moves = self.probe(p);
+        let distribution = WeightedIndex::new(moves.iter().map(|(_, w)| *w)).ok()?;
+        Some(moves[distribution.sample(rng)].0)
+    }
+
+    /// The format is a magic header and the number of positions, followed by each position's hash,
+    /// its number of moves, and each move's bits and weight, all little-endian. Positions are
+    /// sorted by hash, so equal books are written identically.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        fn count<T: TryFrom<usize>>(len: usize, what: &str) -> io::Result<T> {
+            T::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("Too many {}: {}", what, len)))
+        }
+        w.write_all(MAGIC)?;
+        w.write_all(&count::<u32>(self.entries.len(), "positions")?.to_le_bytes())?;
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort();
+        for key in keys {
+            let moves = &self.entries[key];
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&count::<u16>(moves.len(), "moves in a position")?.to_le_bytes())?;
+            for (m, weight) in moves {
+                w.write_all(&m.to_bits().to_le_bytes())?;
+                w.write_all(&weight.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(r: &mut R) -> io::Result<OpeningBook> {
+        fn bytes<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+            let mut result = [0u8; N];
+            r.read_exact(&mut result)?;
+            Ok(result)
+        }
+        if &bytes::<R, 8>(r)? != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Not an opening book file"));
+        }
+        let mut result = OpeningBook::new();
+        for _ in 0..u32::from_le_bytes(bytes(r)?) {
+            let key = u64::from_le_bytes(bytes(r)?);
+            let count = u16::from_le_bytes(bytes(r)?);
+            let mut moves = Vec::with_capacity(usize::from(count));
+            for _ in 0..count {
+                let m = Move::from_bits(u32::from_le_bytes(bytes(r)?));
+                moves.push((m, u32::from_le_bytes(bytes(r)?)));
+            }
+            result.entries.insert(key, moves);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::token::Owner;
+    use crate::game::units::UnitId;
+
+    use super::*;
+
+    fn record(moves: &[&str], result: GameResult) -> GameRecord {
+        let mut record = GameRecord::new(
+            [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Left, FootmenSetup::Sides)],
+            vec![UnitId::Footman, UnitId::Footman],
+        );
+        let mut p = record.initial_position();
+        for text in moves {
+            let m = p.parse_move(text).unwrap();
+            record.moves.push(m);
+            p = p.make(m);
+        }
+        record.result = result;
+        record
+    }
+
+    fn book() -> OpeningBook {
+        OpeningBook::build(&[
+            record(&["c1-c2", "b6-b5"], GameResult::Win(Owner::Player1)),
+            record(&["c1-c2", "d6-d5"], GameResult::Draw),
+            record(&["e1-e2", "b6-b5"], GameResult::Win(Owner::Player2)),
+            record(&["Footman@d2", "b6-b5", "c1-c2"], GameResult::Unfinished),
+        ], 2)
+    }
+
+    #[test]
+    fn probe_weighs_moves_by_result() {
+        let book = book();
+        let p = record(&[], GameResult::Unfinished).initial_position();
+        let moves: Vec<(String, u32)> = book.probe(&p).into_iter().map(|(m, w)| (m.to_string(), w)).collect();
+        assert_eq!(vec![("c1-c2".to_owned(), 3), ("Footman@d2".to_owned(), 1)], moves);
+        assert_eq!(p.parse_move("c1-c2"), book.best(&p));
+    }
+
+    #[test]
+    fn only_the_first_plies_are_added() {
+        let book = book();
+        let p = record(&["Footman@d2", "b6-b5"], GameResult::Unfinished).positions()[2];
+        assert!(book.probe(&p).is_empty());
+        assert_eq!(None, book.best(&p));
+    }
+
+    #[test]
+    fn choose_returns_book_moves() {
+        let book = book();
+        let p = record(&[], GameResult::Unfinished).initial_position();
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let m = book.choose(&p, &mut rng).unwrap();
+            assert!(book.probe(&p).iter().any(|(e, _)| *e == m));
+        }
+    }
+
+    #[test]
+    fn write_and_read_round_trip() {
+        let book = book();
+        let mut bytes = Vec::new();
+        book.write(&mut bytes).unwrap();
+        assert_eq!(book, OpeningBook::read(&mut bytes.as_slice()).unwrap());
+        let mut again = Vec::new();
+        book.write(&mut again).unwrap();
+        assert_eq!(bytes, again);
+        assert!(OpeningBook::read(&mut &bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn move_counts_which_do_not_fit_are_not_written() {
+        let mut book = OpeningBook::new();
+        book.entries.insert(1, vec![(Move::default(), 1); usize::from(u16::MAX) + 1]);
+        let error = book.write(&mut Vec::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::game::bitboard::squares;
+use crate::game::position::Position;
+use crate::game::token::{OwnedToken, Owner};
+use crate::game::units::UnitId;
+
+/// Scores are in centi-footmen, relative to the player to move.
+pub type Score = i32;
+
+/// Beyond any static evaluation; a mate in n plies scores MATE - n.
+pub const MATE: Score = 30_000;
+/// Scores at least this large (in absolute value) are mates.
+pub const MATE_BOUND: Score = MATE - 1_000;
+
+/// The value of a unit on the board. The Duke is priceless, but it's never captured, so it's 0.
+pub fn unit_value(unit: UnitId) -> Score {
+    match unit {
+        UnitId::Duke => 0,
+        UnitId::Footman => 100,
+    }
+}
+
+/// Custom tokens are valued like a Footman.
+pub fn token_value(t: &OwnedToken) -> Score {
+    t.token.unit().map_or(unit_value(UnitId::Footman), unit_value)
+}
+
+/// A token in the bag is worth less than one on the board, since it has to be summoned first.
+const BAG_FRACTION: Score = 2;
+const MOBILITY_WEIGHT: Score = 4;
+const GUARD_PENALTY: Score = 30;
+
+fn material(p: &Position, owner: Owner) -> Score {
+    UnitId::ALL
+        .iter()
+        .map(|u| {
+            let on_board = squares(p.board.unit(*u) & p.board.owned_by(owner)).count() as Score;
+            let in_bag = Score::from(p.bag(owner)[u.index()]);
+            unit_value(*u) * on_board + unit_value(*u) * in_bag / BAG_FRACTION
+        })
+        .sum()
+}
+
+/// The number of squares the player's tokens attack.
+fn mobility(p: &Position, owner: Owner) -> Score {
+    p.board.attacked_by(owner).count_ones() as Score
+}
+
+fn side(p: &Position, owner: Owner) -> Score {
+    let guard = if p.board.is_in_guard(owner) { GUARD_PENALTY } else { 0 };
+    material(p, owner) + MOBILITY_WEIGHT * mobility(p, owner) - guard
+}
+
+/// A static evaluation of the position, relative to the player to move.
+pub fn evaluate(p: &Position) -> Score {
+    side(p, p.to_move) - side(p, p.to_move.other())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coordinates::Coordinates;
+    use crate::game::bitboard::BitBoard;
+    use crate::game::token::CurrentSide;
+
+    use super::*;
+
+    fn position(to_move: Owner) -> Position {
+        let mut board = BitBoard::empty();
+        board.put(Coordinates { x: 0, y: 0 }, Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(Coordinates { x: 5, y: 5 }, Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        board.put(Coordinates { x: 2, y: 2 }, Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        Position::new(board, to_move, [[0; UnitId::COUNT]; 2])
+    }
+
+    #[test]
+    fn extra_material_is_good_for_its_owner() {
+        assert!(evaluate(&position(Owner::Player1)) > 0);
+        assert!(evaluate(&position(Owner::Player2)) < 0);
+    }
+
+    #[test]
+    fn evaluation_is_antisymmetric() {
+        assert_eq!(evaluate(&position(Owner::Player1)), -evaluate(&position(Owner::Player2)));
+    }
+
+    #[test]
+    fn bag_tokens_are_worth_less_than_board_tokens() {
+        let mut board = BitBoard::empty();
+        board.put(Coordinates { x: 0, y: 0 }, Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(Coordinates { x: 5, y: 5 }, Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        let mut bags = [[0; UnitId::COUNT]; 2];
+        bags[Owner::Player1.index()][UnitId::Footman.index()] = 1;
+        let in_bag = evaluate(&Position::new(board, Owner::Player1, bags));
+        assert!(in_bag > 0);
+        assert!(in_bag < evaluate(&position(Owner::Player1)));
+    }
+}
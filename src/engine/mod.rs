@@ -0,0 +1,7 @@
+pub mod book;
+pub mod eval;
+pub mod search;
+pub mod see;
+pub mod solver;
+pub mod tablebase;
+pub mod tt;
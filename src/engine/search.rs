@@ -0,0 +1,588 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+
+use crate::engine::book::OpeningBook;
+use crate::engine::eval::{evaluate, unit_value, Score, MATE, MATE_BOUND};
+use crate::engine::see::see_move;
+use crate::engine::tablebase::{Outcome, Tablebases};
+use crate::engine::tt::{Bound, Entry, TranspositionTable};
+use crate::game::bitboard::square_at;
+use crate::game::moves::{Move, MoveKind, MoveList};
+use crate::game::position::Position;
+use crate::game::units::UnitId;
+
+/// The deepest iteration helper threads will start.
+const MAX_DEPTH: u32 = 64;
+/// Quiescence stops extending here, in case guard evasions keep it going.
+const MAX_PLY: u32 = 128;
+const INFINITY: Score = MATE + 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SearchOptions {
+    pub depth: u32,
+    /// With a single thread, the search is deterministic. Additional threads search the same
+    /// position (Lazy SMP), and only share work through the transposition table.
+    pub threads: usize,
+    /// The number of transposition table slots.
+    pub table_size: usize,
+    /// Play the heaviest book move, if the position is in [Resources::book], without searching.
+    pub use_book: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions { depth: 6, threads: 1, table_size: 1 << 20, use_book: false }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SearchResult {
+    /// [None] if there are no legal moves.
+    pub best_move: Option<Move>,
+    /// Relative to the player to move.
+    pub score: Score,
+    /// The depth of the last completed iteration.
+    pub depth: u32,
+    /// Summed over all threads.
+    pub nodes: u64,
+    /// True if the move is from the opening book, in which case nothing was searched.
+    pub from_book: bool,
+}
+
+/// Lower is searched first: the table's move, then captures, then summons, then the rest.
+fn order_key(p: &Position, m: Move, table_move: Option<Move>) -> u8 {
+    if Some(m) == table_move {
+        0
+    } else if p.is_capture(m) {
+        1
+    } else if m.kind() == MoveKind::Summon {
+        2
+    } else {
+        3
+    }
+}
+
+fn victim_value(p: &Position, m: Move) -> Score {
+    p.board.get(square_at(m.to())).map_or(0, |(_, unit, _)| unit_value(unit))
+}
+
+/// The units the player to move can draw, with their counts.
+fn drawable(p: &Position) -> Vec<(UnitId, Score)> {
+    let bag = p.bag(p.to_move);
+    UnitId::ALL.iter().filter(|u| bag[u.index()] > 0).map(|u| (*u, Score::from(bag[u.index()]))).collect()
+}
+
+/// The expected score of a summon, given the score and count of each draw. Mates don't average with
+/// material: if every draw mates, or every draw is mated, it's the worst draw's mate. Otherwise mates
+/// count as the largest material score, so the expectation stays below [MATE_BOUND].
+fn expectation(draws: &[(Score, Score)]) -> Score {
+    let worst = draws.iter().map(|(score, _)| *score).min().unwrap_or(0);
+    if worst >= MATE_BOUND || draws.iter().all(|(score, _)| *score <= -MATE_BOUND) {
+        return worst;
+    }
+    let total: Score = draws.iter().map(|(_, count)| *count).sum();
+    let sum: Score = draws.iter().map(|(score, count)| count * (*score).clamp(1 - MATE_BOUND, MATE_BOUND - 1)).sum();
+    sum / total
+}
+
+/// Only one summon per square is searched, see [Position::canonical].
+fn is_canonical(p: &Position, m: Move) -> bool {
+    p.canonical(m) == m
+}
+
+/// Shared, read-only data the search consults, besides the position.
+#[derive(Clone, Copy)]
+pub struct Resources<'a> {
+    pub tt: &'a TranspositionTable,
+    /// Exact outcomes of small endings, which end the search wherever they apply.
+    pub tablebases: Option<&'a Tablebases>,
+    /// Only consulted at the root, and only with [SearchOptions::use_book].
+    pub book: Option<&'a OpeningBook>,
+}
+
+impl<'a> Resources<'a> {
+    pub fn new(tt: &'a TranspositionTable) -> Resources<'a> {
+        Resources { tt, tablebases: None, book: None }
+    }
+}
+
+struct Searcher<'a> {
+    tt: &'a TranspositionTable,
+    tablebases: Option<&'a Tablebases>,
+    /// Checked at every node; once set, results are discarded.
+    stop: &'a AtomicBool,
+    nodes: u64,
+    /// The best move of the last search of the root, which the table may have lost since.
+    root_move: Option<Move>,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(resources: &Resources<'a>, stop: &'a AtomicBool) -> Searcher<'a> {
+        Searcher { tt: resources.tt, tablebases: resources.tablebases, stop, nodes: 0, root_move: None }
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Tablebase distances are from p, so they're offset by ply to be relative to the root.
+    fn probe_tablebases(&self, p: &Position, ply: u32) -> Option<Score> {
+        self.tablebases?.probe(p).map(|outcome| match outcome {
+            Outcome::Win(d) => MATE - (ply + u32::from(d)) as Score,
+            Outcome::Loss(d) => -(MATE - (ply + u32::from(d)) as Score),
+            Outcome::Draw => 0,
+        })
+    }
+
+    /// Extends the search past its horizon with captures only (including strikes and commands), so
+    /// exchanges aren't cut off halfway. Captures losing material by [see_move] are skipped. While in
+    /// guard, all moves are searched, since standing pat isn't an option.
+    fn quiesce(&mut self, p: &Position, ply: u32, mut alpha: Score, beta: Score) -> Score {
+        self.nodes += 1;
+        if self.stopped() {
+            return 0;
+        }
+        if let Some(score) = self.probe_tablebases(p, ply) {
+            return score;
+        }
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        let in_guard = p.is_in_guard();
+        if moves.is_empty() {
+            return if in_guard { -(MATE - ply as Score) } else { 0 };
+        }
+        if ply >= MAX_PLY {
+            return evaluate(p);
+        }
+        let mut best_score = -INFINITY;
+        if !in_guard {
+            best_score = evaluate(p);
+            if best_score >= beta {
+                return best_score;
+            }
+            alpha = alpha.max(best_score);
+            moves.retain(|m| p.is_capture(m) && see_move(p, m) >= 0);
+            moves.as_mut_slice().sort_unstable_by_key(|m| -victim_value(p, *m));
+        }
+        for m in moves.iter() {
+            let score = -self.quiesce(&p.make(*m), ply + 1, -beta, -alpha);
+            if self.stopped() {
+                return 0;
+            }
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+
+    /// The score of m for the player to move. The summoned unit is drawn at random, so summons are
+    /// chance nodes: their score is the expectation over the bag, each draw searched with a full
+    /// window since the bounds don't carry over to an average.
+    fn child_score(&mut self, p: &Position, m: Move, depth: u32, ply: u32, alpha: Score, beta: Score) -> Score {
+        if m.kind() != MoveKind::Summon {
+            return -self.negamax(&p.make(m), depth - 1, ply + 1, -beta, -alpha);
+        }
+        let draws = drawable(p);
+        if draws.len() == 1 {
+            let summon = Move::summon(m.to(), draws[0].0);
+            return -self.negamax(&p.make(summon), depth - 1, ply + 1, -beta, -alpha);
+        }
+        let scores: Vec<(Score, Score)> = draws
+            .into_iter()
+            .map(|(unit, count)| {
+                (-self.negamax(&p.make(Move::summon(m.to(), unit)), depth - 1, ply + 1, -INFINITY, INFINITY), count)
+            })
+            .collect();
+        expectation(&scores)
+    }
+
+    fn negamax(&mut self, p: &Position, depth: u32, ply: u32, mut alpha: Score, beta: Score) -> Score {
+        if depth == 0 {
+            return self.quiesce(p, ply, alpha, beta);
+        }
+        self.nodes += 1;
+        if self.stopped() {
+            return 0;
+        }
+        if ply > 0 {
+            if let Some(score) = self.probe_tablebases(p, ply) {
+                return score;
+            }
+        }
+        let key = p.zobrist();
+        let entry = self.tt.probe(key, ply);
+        if let Some(e) = entry {
+            if ply > 0 && e.depth >= depth {
+                match e.bound {
+                    Bound::Exact => return e.score,
+                    Bound::Lower if e.score >= beta => return e.score,
+                    Bound::Upper if e.score <= alpha => return e.score,
+                    _ => (),
+                }
+            }
+        }
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        if moves.is_empty() {
+            // Without moves, a Duke in guard is checkmated, otherwise it's a stalemate.
+            return if p.is_in_guard() { -(MATE - ply as Score) } else { 0 };
+        }
+        let table_move = entry.and_then(|e| e.best_move);
+        moves.retain(|m| is_canonical(p, m));
+        moves.as_mut_slice().sort_unstable_by_key(|m| order_key(p, *m, table_move));
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for m in moves.iter() {
+            let score = self.child_score(p, *m, depth, ply, alpha, beta);
+            if self.stopped() {
+                return 0;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(*m);
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        if ply == 0 {
+            self.root_move = best_move;
+        }
+        self.tt.store(key, ply, Entry { best_move, score: best_score, depth, bound });
+        best_score
+    }
+
+    /// Iterative deepening from first to last, or until stopped. Returns the score, depth and best
+    /// move of the last completed iteration, if any.
+    fn deepen(&mut self, p: &Position, first: u32, last: u32) -> Option<(Score, u32, Option<Move>)> {
+        let mut result = None;
+        for depth in first..=last {
+            self.root_move = None;
+            let score = self.negamax(p, depth, 0, -INFINITY, INFINITY);
+            if self.stopped() {
+                break;
+            }
+            result = Some((score, depth, self.root_move));
+        }
+        result
+    }
+}
+
+/// Scores every legal move of the player to move with a full window of the given depth, in the order
+/// [Position::legal_moves] generates them. There's a single summon per square, scored by its
+/// expectation over the bag, see [summon_probabilities].
+pub fn move_scores(p: &Position, depth: u32, resources: &Resources) -> Vec<(Move, Score)> {
+    let stop = AtomicBool::new(false);
+    let mut searcher = Searcher::new(resources, &stop);
+    let mut moves = MoveList::new();
+    p.legal_moves(&mut moves);
+    moves.retain(|m| is_canonical(p, m));
+    moves.iter().map(|m| (*m, searcher.child_score(p, *m, depth.max(1), 0, -INFINITY, INFINITY))).collect()
+}
+
+/// The probability of drawing each unit, by [UnitId::index], from the bag of the player to move.
+/// All zeros if the bag is empty.
+pub fn summon_probabilities(p: &Position) -> [f64; UnitId::COUNT] {
+    let mut result = [0.0; UnitId::COUNT];
+    let total = p.bag_size(p.to_move);
+    if total > 0 {
+        for (unit, count) in drawable(p) {
+            result[unit.index()] = f64::from(count) / f64::from(total);
+        }
+    }
+    result
+}
+
+/// Searches with a fresh transposition table.
+pub fn search(p: &Position, options: &SearchOptions) -> SearchResult {
+    search_with(p, options, &Resources::new(&TranspositionTable::new(options.table_size)))
+}
+
+/// Iterative deepening alpha-beta up to the requested depth. The main thread's last iteration is the
+/// result; helper threads start at staggered depths and keep deepening until it's done, so they
+/// mostly populate the table ahead of it.
+pub fn search_with(p: &Position, options: &SearchOptions, resources: &Resources) -> SearchResult {
+    if let Some(m) = resources.book.filter(|_| options.use_book).and_then(|b| b.best(p)) {
+        return SearchResult { best_move: Some(m), score: 0, depth: 0, nodes: 0, from_book: true };
+    }
+    let depth = options.depth.max(1);
+    let stop = AtomicBool::new(false);
+    let helper_nodes = AtomicU64::new(0);
+    let mut main = Searcher::new(resources, &stop);
+    let (score, completed, best_move) = thread::scope(|scope| {
+        for i in 1..options.threads {
+            let (stop, helper_nodes) = (&stop, &helper_nodes);
+            scope.spawn(move || {
+                let mut helper = Searcher::new(resources, stop);
+                helper.deepen(p, 1 + (i as u32 % 2), MAX_DEPTH);
+                helper_nodes.fetch_add(helper.nodes, Ordering::Relaxed);
+            });
+        }
+        let result = main.deepen(p, 1, depth);
+        stop.store(true, Ordering::Relaxed);
+        result
+    })
+    .expect("The main thread is never stopped");
+    SearchResult {
+        best_move,
+        score,
+        depth: completed,
+        nodes: main.nodes + helper_nodes.load(Ordering::Relaxed),
+        from_book: false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coordinates::Coordinates;
+    use crate::game::bitboard::{square_index, BitBoard};
+    use crate::game::state::{DukeInitialLocation, FootmenSetup, GameState};
+    use crate::game::token::{CurrentSide, Owner, TokenBag};
+    use crate::game::units;
+
+    use super::*;
+
+    fn initial() -> Position {
+        let gs = GameState::new(
+            &TokenBag::new(vec![units::footman(Owner::Player1).token, units::footman(Owner::Player1).token]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Right, FootmenSetup::Sides),
+        );
+        Position::from_state(&gs).unwrap()
+    }
+
+    fn options(depth: u32, threads: usize) -> SearchOptions {
+        SearchOptions { depth, threads, table_size: 1 << 16, use_book: false }
+    }
+
+    fn c(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    fn is_mated(p: &Position) -> bool {
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        moves.is_empty() && p.is_in_guard()
+    }
+
+    /// Player 2's Duke is cornered, and can't take the defended footman next to it. Player 1's
+    /// flipped footman steps diagonally below it, flipping to a side which strikes up at the Duke.
+    fn mate_in_one() -> Position {
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(4, 5), Owner::Player1, UnitId::Footman, CurrentSide::Flipped);
+        board.put(c(3, 5), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(4, 3), Owner::Player1, UnitId::Footman, CurrentSide::Flipped);
+        Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2])
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let p = mate_in_one();
+        let result = search(&p, &options(3, 1));
+        assert_eq!(MATE - 1, result.score);
+        assert!(is_mated(&p.make(result.best_move.unwrap())));
+    }
+
+    #[test]
+    fn best_move_is_the_main_threads_rather_than_the_tables() {
+        let p = mate_in_one();
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        let other = *moves.iter().find(|m| !is_mated(&p.make(**m))).unwrap();
+        // As a helper thread's deeper iteration would have left it.
+        let tt = TranspositionTable::new(1 << 16);
+        tt.store(p.zobrist(), 0, Entry { best_move: Some(other), score: 0, depth: MAX_DEPTH, bound: Bound::Exact });
+        let result = search_with(&p, &options(3, 1), &Resources::new(&tt));
+        assert_eq!(MATE - 1, result.score);
+        assert!(is_mated(&p.make(result.best_move.unwrap())));
+    }
+
+    #[test]
+    fn summons_only_mate_if_every_draw_mates() {
+        assert_eq!(MATE - 5, expectation(&[(MATE - 3, 1), (MATE - 5, 2)]));
+        assert_eq!(-(MATE - 3), expectation(&[(-(MATE - 3), 1), (-(MATE - 5), 2)]));
+        let mixed = expectation(&[(MATE - 3, 1), (-50, 1)]);
+        assert!(mixed > 0 && mixed < MATE_BOUND, "{}", mixed);
+        assert!(expectation(&[(MATE - 3, 1), (-(MATE - 3), 1)]).abs() < MATE_BOUND);
+        assert_eq!(30, expectation(&[(50, 1), (20, 2)]));
+    }
+
+    #[test]
+    fn mated_position_has_no_best_move() {
+        let p = mate_in_one();
+        let mated = p.make(search(&p, &options(1, 1)).best_move.unwrap());
+        let result = search(&mated, &options(2, 1));
+        assert_eq!(None, result.best_move);
+        assert_eq!(-MATE, result.score);
+    }
+
+    /// Player 1's footman can take a footman defended by another.
+    fn defended_capture() -> Position {
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(2, 2), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(2, 3), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(2, 4), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2])
+    }
+
+    #[test]
+    fn quiescence_sees_the_recapture_past_the_horizon() {
+        let p = defended_capture();
+        let capture = p.make(Move::new(square_index(c(2, 2)), square_index(c(2, 3)), MoveKind::Move));
+        let result = search(&p, &options(1, 1));
+        // Statically, the capture would look like it wins a footman back.
+        assert!(-evaluate(&capture) >= result.score + 50, "{:?}", result);
+    }
+
+    #[test]
+    fn quiescence_takes_undefended_tokens() {
+        let mut p = defended_capture();
+        p.board.remove(c(2, 4));
+        assert!(search(&p, &options(1, 1)).score >= 80);
+    }
+
+    #[test]
+    fn single_thread_is_deterministic() {
+        let p = initial();
+        assert_eq!(search(&p, &options(4, 1)), search(&p, &options(4, 1)));
+    }
+
+    #[test]
+    fn best_move_is_legal() {
+        let p = initial();
+        let result = search(&p, &options(4, 1));
+        assert!(p.is_legal(result.best_move.unwrap()));
+        assert_eq!(4, result.depth);
+    }
+
+    #[test]
+    fn parallel_search_agrees_on_forced_mates() {
+        let p = mate_in_one();
+        let result = search(&p, &options(3, 4));
+        assert_eq!(MATE - 1, result.score);
+        assert!(is_mated(&p.make(result.best_move.unwrap())));
+    }
+
+    #[test]
+    fn parallel_search_returns_a_legal_move() {
+        let p = initial();
+        let result = search(&p, &options(4, 3));
+        assert!(p.is_legal(result.best_move.unwrap()));
+        assert_eq!(4, result.depth);
+        assert!(result.nodes >= search(&p, &options(4, 1)).nodes / 2);
+    }
+
+    #[test]
+    fn shared_table_speeds_up_a_repeated_search() {
+        let p = initial();
+        let tt = TranspositionTable::new(1 << 16);
+        let first = search_with(&p, &options(4, 1), &Resources::new(&tt));
+        let second = search_with(&p, &options(4, 1), &Resources::new(&tt));
+        assert!(second.nodes < first.nodes);
+        assert_eq!(first.score, second.score);
+    }
+
+    #[test]
+    fn summons_are_scored_once_per_square() {
+        let p = initial();
+        let tt = TranspositionTable::new(1 << 12);
+        let scores = move_scores(&p, 2, &Resources::new(&tt));
+        let summons: Vec<Move> = scores.iter().map(|(m, _)| *m).filter(|m| m.kind() == MoveKind::Summon).collect();
+        assert_eq!(vec![Move::summon(square_index(c(3, 1)), UnitId::Footman)], summons);
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        assert_eq!(moves.len(), scores.len());
+    }
+
+    #[test]
+    fn summon_is_the_expectation_over_the_bag() {
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        let mut bags = [[0; UnitId::COUNT]; 2];
+        bags[Owner::Player1.index()] = [1, 3];
+        let p = Position::new(board, Owner::Player1, bags);
+        assert_eq!([0.25, 0.75], summon_probabilities(&p));
+        let tt = TranspositionTable::new(1 << 12);
+        let summon = Move::summon(square_index(c(0, 1)), UnitId::Duke);
+        let (_, expected) = *move_scores(&p, 1, &Resources::new(&tt)).iter().find(|(m, _)| *m == summon).unwrap();
+        let draw = |u| {
+            let stop = AtomicBool::new(false);
+            let mut searcher = Searcher::new(&Resources::new(&tt), &stop);
+            -searcher.negamax(&p.make(Move::summon(square_index(c(0, 1)), u)), 0, 1, -INFINITY, INFINITY)
+        };
+        assert_eq!((draw(UnitId::Duke) + 3 * draw(UnitId::Footman)) / 4, expected);
+    }
+
+    #[test]
+    fn tablebase_outcomes_decide_the_score() {
+        use crate::engine::tablebase::{Outcome, Tablebases};
+        use crate::game::bitboard::SQUARES;
+
+        let mut tables = Tablebases::new();
+        tables.generate(&"D-D".parse().unwrap());
+        let tt = TranspositionTable::new(1 << 12);
+        let resources = Resources { tablebases: Some(&tables), ..Resources::new(&tt) };
+        for (a, b) in (0..SQUARES).flat_map(|a| (0..SQUARES).map(move |b| (a, b))).step_by(37) {
+            if a == b {
+                continue;
+            }
+            let mut board = BitBoard::empty();
+            board.put(square_at(a), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+            board.put(square_at(b), Owner::Player2, UnitId::Duke, CurrentSide::Flipped);
+            let p = Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2]);
+            if p.board.is_in_guard(Owner::Player2) {
+                continue;
+            }
+            let expected = match tables.probe(&p).unwrap() {
+                Outcome::Win(d) => MATE - Score::from(d),
+                Outcome::Loss(d) => -(MATE - Score::from(d)),
+                Outcome::Draw => 0,
+            };
+            tt.clear();
+            assert_eq!(expected, search_with(&p, &options(1, 1), &resources).score, "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn book_moves_are_played_without_searching_if_enabled() {
+        use crate::game::record::{GameRecord, GameResult};
+
+        let mut record = GameRecord::new(
+            [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Right, FootmenSetup::Sides)],
+            vec![UnitId::Footman, UnitId::Footman],
+        );
+        let p = initial();
+        let m = p.parse_move("e1-e2").unwrap();
+        record.moves.push(m);
+        record.result = GameResult::Win(Owner::Player1);
+        let book = OpeningBook::build(&[record], 10);
+        let tt = TranspositionTable::new(1 << 12);
+        let resources = Resources { book: Some(&book), ..Resources::new(&tt) };
+        let with_book = SearchOptions { use_book: true, ..options(3, 1) };
+        let result = search_with(&p, &with_book, &resources);
+        assert_eq!(SearchResult { best_move: Some(m), score: 0, depth: 0, nodes: 0, from_book: true }, result);
+        assert!(!search_with(&p, &options(3, 1), &resources).from_book);
+        let out_of_book = p.make(m);
+        assert!(!search_with(&out_of_book, &with_book, &resources).from_book);
+    }
+}
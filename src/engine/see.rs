@@ -0,0 +1,97 @@
+use crate::common::coordinates::Coordinates;
+use crate::engine::eval::{unit_value, Score};
+use crate::game::bitboard::{square_at, square_index};
+use crate::game::moves::{Move, MoveList};
+use crate::game::position::Position;
+
+/// The legal captures of the token on target by the player to move, including strikes and commands.
+fn captures_on(p: &Position, target: usize) -> MoveList {
+    let mut result = MoveList::new();
+    p.pseudo_legal_moves(&mut result);
+    result.retain(|m| m.to() == target && p.is_capture(m) && !p.make(m).board.is_in_guard(p.to_move));
+    result
+}
+
+/// The material gain of m, which is assumed to be a capture, given that the exchange on its target
+/// continues as well as possible for both sides.
+pub fn see_move(p: &Position, m: Move) -> Score {
+    let victim = p.board.get(square_at(m.to())).map_or(0, |(_, unit, _)| unit_value(unit));
+    victim - exchange(&p.make(m), m.to())
+}
+
+fn exchange(p: &Position, target: usize) -> Score {
+    captures_on(p, target).iter().map(|m| see_move(p, *m)).max().unwrap_or(0).max(0)
+}
+
+/// The net material the player to move gains by starting an exchange on target. Every capturer is
+/// considered, and either side may stop capturing at any point, so this is never negative. Strikes
+/// capture without moving the striker, so they end the exchange on the square. Unlike the classic
+/// least-valuable-attacker approximation, this plays the exchange out on the position, so tokens
+/// flipping after acting and lines opening behind moved tokens are accounted for.
+pub fn see(p: &Position, target: Coordinates) -> Score {
+    exchange(p, square_index(target))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::bitboard::BitBoard;
+    use crate::game::moves::MoveKind;
+    use crate::game::token::{CurrentSide, Owner};
+    use crate::game::units::UnitId;
+
+    use super::*;
+
+    fn c(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    fn base() -> BitBoard {
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Flipped);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Flipped);
+        board
+    }
+
+    fn position(board: BitBoard) -> Position {
+        Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2])
+    }
+
+    #[test]
+    fn undefended_token_is_won() {
+        let mut board = base();
+        board.put(c(2, 2), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(2, 3), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        assert_eq!(100, see(&position(board), c(2, 3)));
+    }
+
+    #[test]
+    fn defended_token_is_an_even_trade_at_best() {
+        let mut board = base();
+        board.put(c(2, 2), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(2, 3), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(2, 4), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        let p = position(board);
+        assert_eq!(0, see(&p, c(2, 3)));
+        assert_eq!(0, see_move(&p, Move::new(square_index(c(2, 2)), square_index(c(2, 3)), MoveKind::Move)));
+    }
+
+    #[test]
+    fn empty_or_unattacked_squares_are_worth_nothing() {
+        let mut board = base();
+        board.put(c(2, 3), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        let p = position(board);
+        assert_eq!(0, see(&p, c(2, 3)));
+        assert_eq!(0, see(&p, c(3, 3)));
+    }
+
+    #[test]
+    fn the_duke_cannot_recapture_into_guard() {
+        let mut board = base();
+        // The Duke's flipped side slides vertically, defending the footman below it.
+        board.put(c(5, 3), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(5, 2), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        assert_eq!(0, see(&position(board), c(5, 3)));
+        board.put(c(4, 3), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        assert_eq!(100, see(&position(board), c(5, 3)));
+    }
+}
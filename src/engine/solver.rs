@@ -0,0 +1,177 @@
+use crate::game::moves::{Move, MoveKind, MoveList};
+use crate::game::position::Position;
+use crate::game::puzzle::Puzzle;
+use crate::game::units::UnitId;
+
+fn legal_moves(p: &Position) -> MoveList {
+    let mut moves = MoveList::new();
+    p.legal_moves(&mut moves);
+    moves
+}
+
+/// True if the player to move has lost: no legal moves, and its Duke in guard.
+pub fn is_mated(p: &Position) -> bool {
+    p.is_in_guard() && legal_moves(p).is_empty()
+}
+
+/// Every unit m could summon, since the player doesn't choose which one is drawn. Other moves are
+/// returned as is.
+fn outcomes(p: &Position, m: Move) -> Vec<Move> {
+    match m.kind() {
+        MoveKind::Summon => UnitId::ALL
+            .iter()
+            .filter(|u| p.bag(p.to_move)[u.index()] > 0)
+            .map(|u| Move::summon(m.to(), *u))
+            .collect(),
+        _ => vec![m],
+    }
+}
+
+/// True if the opponent, to move in p, loses within the given number of the winner's moves.
+fn loses_within(p: &Position, moves: u32) -> bool {
+    let replies = legal_moves(p);
+    if replies.is_empty() {
+        return p.is_in_guard();
+    }
+    moves > 0 && replies.iter().all(|r| wins_within(&p.make(*r), moves))
+}
+
+/// True if m, by the player to move, forces a win within the given number of its moves, m
+/// included, whichever unit is drawn if it's a summon.
+pub fn move_wins(p: &Position, m: Move, moves: u32) -> bool {
+    moves > 0 && outcomes(p, m).iter().all(|o| loses_within(&p.make(*o), moves - 1))
+}
+
+/// True if the player to move can force a win within the given number of its own moves, whatever
+/// the opponent plays. Unlike [crate::engine::search::search], nothing is pruned, so the answer is
+/// exact, but only practical for a few moves.
+pub fn wins_within(p: &Position, moves: u32) -> bool {
+    moves > 0 && legal_moves(p).iter().any(|m| p.canonical(*m) == *m && move_wins(p, *m, moves))
+}
+
+/// The moves forcing a win within the given number of moves. Summons are [Position::canonical].
+pub fn winning_moves(p: &Position, moves: u32) -> Vec<Move> {
+    legal_moves(p).iter().copied().filter(|m| p.canonical(*m) == *m && move_wins(p, *m, moves)).collect()
+}
+
+/// The least number of moves the player to move needs to force a win, if at most max.
+pub fn win_distance(p: &Position, max: u32) -> Option<u32> {
+    (1..=max).find(|n| wins_within(p, *n))
+}
+
+/// True if the puzzle's first move forces a win in the puzzle's number of moves, and there's no
+/// faster win.
+pub fn verify(puzzle: &Puzzle) -> bool {
+    let p = &puzzle.position;
+    let first = p.canonical(puzzle.solution[0]);
+    win_distance(p, puzzle.moves()) == Some(puzzle.moves()) && move_wins(p, first, puzzle.moves())
+}
+
+/// The reply which holds out longest against a win within the given number of moves: one which
+/// escapes it if there is any, otherwise one the winner needs the most moves to punish. [None] if
+/// there are no legal moves.
+pub fn best_defence(p: &Position, moves: u32) -> Option<Move> {
+    legal_moves(p)
+        .iter()
+        .copied()
+        .max_by_key(|r| win_distance(&p.make(*r), moves).map_or(u32::MAX, |n| n))
+}
+
+/// A line to mate within the given number of moves: the first winning move at each turn, and the
+/// [best_defence] to it. Empty if there's no such win.
+pub fn solution_line(p: &Position, moves: u32) -> Vec<Move> {
+    let mut result = Vec::new();
+    let mut p = *p;
+    for remaining in (1..=moves).rev() {
+        let m = match winning_moves(&p, remaining).first() {
+            Some(m) => *m,
+            None => break,
+        };
+        result.push(m);
+        p = p.make(m);
+        let r = match best_defence(&p, remaining - 1) {
+            Some(r) => r,
+            None => break,
+        };
+        result.push(r);
+        p = p.make(r);
+    }
+    if is_mated(&p) { result } else { Vec::new() }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coordinates::Coordinates;
+    use crate::game::bitboard::BitBoard;
+    use crate::game::puzzle::PINNED;
+    use crate::game::token::{CurrentSide, Owner};
+
+    use super::*;
+
+    fn c(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    /// Player 2's Duke is cornered by three of Player 1's footmen.
+    fn corner() -> Position {
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(4, 5), Owner::Player1, UnitId::Footman, CurrentSide::Flipped);
+        board.put(c(3, 5), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        board.put(c(4, 3), Owner::Player1, UnitId::Footman, CurrentSide::Flipped);
+        Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2])
+    }
+
+    #[test]
+    fn mates_in_one_are_found() {
+        let p = corner();
+        let wins = winning_moves(&p, 1);
+        assert!(!wins.is_empty());
+        for m in wins {
+            assert!(is_mated(&p.make(m)));
+        }
+        assert_eq!(Some(1), win_distance(&p, 3));
+    }
+
+    #[test]
+    fn lone_dukes_cant_win() {
+        let mut board = BitBoard::empty();
+        board.put(c(0, 5), Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(5, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        let p = Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2]);
+        assert!(!wins_within(&p, 2));
+        assert_eq!(None, win_distance(&p, 2));
+    }
+
+    #[test]
+    fn verify_proves_puzzles() {
+        let puzzle: Puzzle = PINNED.parse().unwrap();
+        assert!(verify(&puzzle));
+        assert_eq!(vec![puzzle.solution[0]], winning_moves(&puzzle.position, 2));
+        let mut slow = puzzle.clone();
+        slow.position = puzzle.position.make(puzzle.solution[0]).make(puzzle.solution[1]);
+        slow.solution = vec![puzzle.solution[2]];
+        assert!(verify(&slow));
+        assert!(!verify(&Puzzle { solution: vec![puzzle.solution[0]; 5], ..puzzle }));
+    }
+
+    #[test]
+    fn solution_lines_end_in_mate() {
+        let p = corner();
+        let line = solution_line(&p, 2);
+        assert_eq!(1, line.len());
+        assert!(is_mated(&p.make(line[0])));
+        assert!(solution_line(&p.make(p.parse_move("e6-f5").unwrap()), 1).is_empty());
+    }
+
+    #[test]
+    fn best_defence_escapes_if_possible() {
+        let p = corner();
+        let m = winning_moves(&p, 1)[0];
+        assert_eq!(None, best_defence(&p.make(m), 1));
+        let released = p.make(p.parse_move("e6-f5").unwrap());
+        let r = best_defence(&released, 2).unwrap();
+        assert_eq!(None, win_distance(&released.make(r), 2));
+    }
+}
@@ -0,0 +1,551 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::game::bitboard::{square_at, BitBoard, SQUARES};
+use crate::game::moves::MoveList;
+use crate::game::position::Position;
+use crate::game::token::{CurrentSide, Owner};
+use crate::game::units::UnitId;
+
+/// Tables grow by a factor of 72 per token, so larger materials aren't practical.
+pub const MAX_TOKENS: usize = 4;
+/// Every token can be on any square, on either side.
+const DIGITS: usize = SQUARES * 2;
+
+const DRAW: u8 = 0;
+const INVALID: u8 = 255;
+const UNRESOLVED: u8 = 254;
+/// The longest distance to mate, in plies, which can be stored; longer mates are stored as draws.
+pub const MAX_DISTANCE: u8 = UNRESOLVED - 2;
+
+const MAGIC: &[u8; 8] = b"DUKETB1\0";
+
+/// The result of perfect play, relative to the player to move, with the distance to mate in plies.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Outcome {
+    Win(u8),
+    /// Loss(0) means the player to move is mated.
+    Loss(u8),
+    Draw,
+}
+
+impl Outcome {
+    /// Stored as the distance plus one, so wins are even and losses odd, and 0 is a draw.
+    fn from_byte(b: u8) -> Option<Outcome> {
+        match b {
+            DRAW => Some(Outcome::Draw),
+            INVALID | UNRESOLVED => None,
+            _ if (b - 1) % 2 == 1 => Some(Outcome::Win(b - 1)),
+            _ => Some(Outcome::Loss(b - 1)),
+        }
+    }
+    fn to_byte(self) -> u8 {
+        match self {
+            Outcome::Draw => DRAW,
+            Outcome::Win(d) | Outcome::Loss(d) => d + 1,
+        }
+    }
+}
+
+/// The tokens on the board, with empty bags. Every player has exactly one Duke.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Material {
+    /// Sorted by owner, then unit.
+    tokens: Vec<(Owner, UnitId)>,
+}
+
+impl Material {
+    /// [None] if a player doesn't have exactly one Duke, or there are more than [MAX_TOKENS].
+    pub fn new(mut tokens: Vec<(Owner, UnitId)>) -> Option<Material> {
+        let dukes = |owner| tokens.iter().filter(|t| **t == (owner, UnitId::Duke)).count();
+        if dukes(Owner::Player1) != 1 || dukes(Owner::Player2) != 1 || tokens.len() > MAX_TOKENS {
+            return None;
+        }
+        tokens.sort_by_key(|(owner, unit)| (owner.index(), *unit));
+        Some(Material { tokens })
+    }
+
+    /// [None] if the position has tokens in the bags or isn't a valid material.
+    pub fn of(p: &Position) -> Option<Material> {
+        let mut tokens = Vec::new();
+        for owner in Owner::ALL.iter() {
+            if p.bag_size(*owner) > 0 {
+                return None;
+            }
+            for unit in UnitId::ALL.iter() {
+                let count = (p.board.unit(*unit) & p.board.owned_by(*owner)).count_ones();
+                tokens.extend((0..count).map(|_| (*owner, *unit)));
+            }
+        }
+        Material::new(tokens)
+    }
+
+    pub fn tokens(&self) -> &[(Owner, UnitId)] {
+        &self.tokens
+    }
+
+    /// The number of indices, including invalid positions.
+    pub fn size(&self) -> usize {
+        DIGITS.pow(self.tokens.len() as u32) * 2
+    }
+
+    /// The material after the token at i is captured.
+    fn without(&self, i: usize) -> Material {
+        let mut tokens = self.tokens.clone();
+        tokens.remove(i);
+        Material { tokens }
+    }
+
+    /// Assumes the position has this material. Identical tokens are indexed by ascending squares.
+    fn index(&self, p: &Position) -> usize {
+        let mut result = 0;
+        let mut rest = [0u64; 2 * UnitId::COUNT];
+        for (owner, unit) in self.tokens.iter() {
+            rest[owner.index() * UnitId::COUNT + unit.index()] = p.board.unit(*unit) & p.board.owned_by(*owner);
+        }
+        // The first token is the least significant digit, so this iterates from the last.
+        for (owner, unit) in self.tokens.iter().rev() {
+            let bits = &mut rest[owner.index() * UnitId::COUNT + unit.index()];
+            let square = 63 - bits.leading_zeros() as usize;
+            *bits &= !(1 << square);
+            let side = if p.board.unit_side(*unit, CurrentSide::Initial) & (1 << square) != 0 {
+                CurrentSide::Initial
+            } else {
+                CurrentSide::Flipped
+            };
+            result = result * DIGITS + square * 2 + side.index();
+        }
+        result * 2 + p.to_move.index()
+    }
+
+    /// [None] if tokens overlap.
+    fn position(&self, index: usize) -> Option<Position> {
+        let to_move = Owner::ALL[index % 2];
+        let mut rest = index / 2;
+        let mut board = BitBoard::empty();
+        for (owner, unit) in self.tokens.iter() {
+            let digit = rest % DIGITS;
+            rest /= DIGITS;
+            let c = square_at(digit / 2);
+            if board.get(c).is_some() {
+                return None;
+            }
+            board.put(c, *owner, *unit, CurrentSide::ALL[digit % 2]);
+        }
+        Some(Position::new(board, to_move, [[0; UnitId::COUNT]; 2]))
+    }
+}
+
+/// Formatted as each player's units' initials, e.g., "DF-D".
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let initials = |owner: Owner| -> String {
+            self.tokens.iter().filter(|t| t.0 == owner).map(|t| t.1.name().chars().next().unwrap()).collect()
+        };
+        write!(f, "{}-{}", initials(Owner::Player1), initials(Owner::Player2))
+    }
+}
+
+impl std::str::FromStr for Material {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Material, String> {
+        let (p1, p2) = s.split_once('-').ok_or_else(|| format!("Expected '-' in {}", s))?;
+        let mut tokens = Vec::new();
+        for (owner, initials) in [(Owner::Player1, p1), (Owner::Player2, p2)].iter() {
+            for c in initials.chars() {
+                let unit = UnitId::ALL
+                    .iter()
+                    .find(|u| u.name().starts_with(c.to_ascii_uppercase()))
+                    .ok_or_else(|| format!("Unknown unit '{}'", c))?;
+                tokens.push((*owner, *unit));
+            }
+        }
+        Material::new(tokens).ok_or_else(|| format!("Invalid material {}", s))
+    }
+}
+
+/// The outcomes of all positions with a single material.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Tablebase {
+    material: Material,
+    /// Indexed by [Material::index], see [Outcome::to_byte].
+    values: Vec<u8>,
+}
+
+impl Tablebase {
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// [None] if the position doesn't have this table's material, or if it's illegal.
+    pub fn probe(&self, p: &Position) -> Option<Outcome> {
+        if Material::of(p).as_ref() != Some(&self.material) {
+            return None;
+        }
+        self.probe_index(self.material.index(p))
+    }
+
+    /// The outcomes of all legal positions.
+    pub fn outcomes(&self) -> impl Iterator<Item=Outcome> + '_ {
+        self.values.iter().filter_map(|v| Outcome::from_byte(*v))
+    }
+
+    fn probe_index(&self, index: usize) -> Option<Outcome> {
+        Outcome::from_byte(self.values[index])
+    }
+
+    /// Retrograde analysis: mates are found first, and outcomes are then propagated backwards to
+    /// predecessors in order of distance, so each position is resolved at its shortest win (or
+    /// longest loss). Captures lead to smaller materials, which must already be in tables.
+    fn generate(material: &Material, tables: &Tablebases) -> Tablebase {
+        let size = material.size();
+        let mut values = vec![UNRESOLVED; size];
+        let mut remaining = vec![0u32; size];
+        // The longest win among the resolved children, which the loss distance is based on.
+        let mut longest = vec![0u8; size];
+        let mut edges: Vec<(u32, u32)> = Vec::new();
+        let mut buckets: Vec<Vec<(u32, Outcome)>> = vec![Vec::new(); usize::from(MAX_DISTANCE) + 1];
+        let mut moves = MoveList::new();
+        for index in 0..size {
+            let p = match material.position(index) {
+                // The player who just moved can't be in guard.
+                Some(p) if !p.board.is_in_guard(p.to_move.other()) => p,
+                _ => {
+                    values[index] = INVALID;
+                    continue;
+                }
+            };
+            moves.clear();
+            p.legal_moves(&mut moves);
+            if moves.is_empty() {
+                if p.is_in_guard() {
+                    buckets[0].push((index as u32, Outcome::Loss(0)));
+                } else {
+                    values[index] = DRAW;
+                }
+                continue;
+            }
+            let mut shortest_win = None;
+            let mut has_draw = false;
+            for m in moves.iter() {
+                let next = p.make(*m);
+                if !p.is_capture(*m) {
+                    edges.push((material.index(&next) as u32, index as u32));
+                    remaining[index] += 1;
+                    continue;
+                }
+                match tables.probe(&next) {
+                    Some(Outcome::Loss(d)) => shortest_win = Some(shortest_win.map_or(d + 1, |w: u8| w.min(d + 1))),
+                    Some(Outcome::Win(d)) => longest[index] = longest[index].max(d),
+                    _ => has_draw = true,
+                }
+            }
+            if has_draw || shortest_win.is_some() {
+                // Never reaches zero, so it's never lost, even when the win is too long to store.
+                remaining[index] += 1;
+            }
+            if let Some(d) = shortest_win.filter(|d| *d <= MAX_DISTANCE) {
+                buckets[usize::from(d)].push((index as u32, Outcome::Win(d)));
+            } else if remaining[index] == 0 && longest[index] < MAX_DISTANCE {
+                let d = longest[index] + 1;
+                buckets[usize::from(d)].push((index as u32, Outcome::Loss(d)));
+            }
+        }
+
+        // Predecessors, grouped by child.
+        edges.sort_unstable();
+        let mut starts = vec![0usize; size + 1];
+        for (child, _) in edges.iter() {
+            starts[*child as usize + 1] += 1;
+        }
+        for i in 0..size {
+            starts[i + 1] += starts[i];
+        }
+
+        for d in 0..buckets.len() {
+            for (index, outcome) in std::mem::take(&mut buckets[d]) {
+                let index = index as usize;
+                if values[index] != UNRESOLVED {
+                    continue;
+                }
+                values[index] = outcome.to_byte();
+                for (_, parent) in edges[starts[index]..starts[index + 1]].iter() {
+                    let parent = *parent as usize;
+                    if values[parent] != UNRESOLVED {
+                        continue;
+                    }
+                    let next = d + 1;
+                    if next > usize::from(MAX_DISTANCE) {
+                        continue;
+                    }
+                    match outcome {
+                        Outcome::Loss(_) => buckets[next].push((parent as u32, Outcome::Win(next as u8))),
+                        _ => {
+                            remaining[parent] -= 1;
+                            longest[parent] = longest[parent].max(d as u8);
+                            let loss = usize::from(longest[parent]) + 1;
+                            if remaining[parent] == 0 && loss <= usize::from(MAX_DISTANCE) {
+                                buckets[loss].push((parent as u32, Outcome::Loss(loss as u8)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for v in values.iter_mut().filter(|v| **v == UNRESOLVED) {
+            *v = DRAW;
+        }
+        Tablebase { material: material.clone(), values }
+    }
+
+    /// The format is a magic header, the number of tokens, an (owner, unit) byte pair per token,
+    /// and then a byte per index.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&[self.material.tokens.len() as u8])?;
+        for (owner, unit) in self.material.tokens.iter() {
+            w.write_all(&[owner.index() as u8, unit.index() as u8])?;
+        }
+        w.write_all(&self.values)
+    }
+
+    pub fn read<R: Read>(r: &mut R) -> io::Result<Tablebase> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_owned());
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("Not a tablebase file"));
+        }
+        let mut count = [0u8; 1];
+        r.read_exact(&mut count)?;
+        let mut tokens = Vec::new();
+        for _ in 0..count[0] {
+            let mut pair = [0u8; 2];
+            r.read_exact(&mut pair)?;
+            let owner = *Owner::ALL.get(usize::from(pair[0])).ok_or_else(|| invalid("Invalid owner"))?;
+            let unit = *UnitId::ALL.get(usize::from(pair[1])).ok_or_else(|| invalid("Invalid unit"))?;
+            tokens.push((owner, unit));
+        }
+        let material = Material::new(tokens).ok_or_else(|| invalid("Invalid material"))?;
+        let mut values = vec![0u8; material.size()];
+        r.read_exact(&mut values)?;
+        Ok(Tablebase { material, values })
+    }
+}
+
+/// Tables of several materials, probed by the position's material.
+#[derive(Debug, Clone, Default)]
+pub struct Tablebases {
+    tables: HashMap<Material, Tablebase>,
+}
+
+impl Tablebases {
+    pub fn new() -> Tablebases {
+        Tablebases::default()
+    }
+
+    pub fn insert(&mut self, table: Tablebase) -> () {
+        self.tables.insert(table.material.clone(), table);
+    }
+
+    pub fn get(&self, material: &Material) -> Option<&Tablebase> {
+        self.tables.get(material)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Generates the material's table, and first, those of every material reachable by captures.
+    pub fn generate(&mut self, material: &Material) -> () {
+        if self.tables.contains_key(material) {
+            return;
+        }
+        for (i, (_, unit)) in material.tokens.iter().enumerate() {
+            if *unit != UnitId::Duke {
+                self.generate(&material.without(i));
+            }
+        }
+        let table = Tablebase::generate(material, self);
+        self.insert(table);
+    }
+
+    /// The file name of a material's table, e.g., "DF-D.tb".
+    pub fn file_name(material: &Material) -> String {
+        format!("{}.tb", material)
+    }
+
+    /// Writes every table to its own file in dir, see [Tablebases::file_name].
+    pub fn write_dir(&self, dir: &Path) -> io::Result<()> {
+        for table in self.tables.values() {
+            let mut file = BufWriter::new(File::create(dir.join(Tablebases::file_name(&table.material)))?);
+            table.write(&mut file)?;
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Reads every ".tb" file in dir.
+    pub fn read_dir(dir: &Path) -> io::Result<Tablebases> {
+        let mut result = Tablebases::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "tb") {
+                result.insert(Tablebase::read(&mut BufReader::new(File::open(path)?))?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// [None] if there's no table for the position's material.
+    pub fn probe(&self, p: &Position) -> Option<Outcome> {
+        if p.board.occupied().count_ones() as usize > MAX_TOKENS {
+            return None;
+        }
+        let material = Material::of(p)?;
+        let table = self.tables.get(&material)?;
+        table.probe_index(material.index(p))
+    }
+}
+
+/// All the valid positions of a material, for verifying tables.
+#[cfg(test)]
+fn positions(material: &Material) -> impl Iterator<Item=Position> + '_ {
+    (0..material.size())
+        .filter_map(move |i| material.position(i))
+        .filter(|p| !p.board.is_in_guard(p.to_move.other()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::coordinates::Coordinates;
+
+    use super::*;
+
+    fn dukes() -> Material {
+        "D-D".parse().unwrap()
+    }
+
+    fn c(x: u16, y: u16) -> Coordinates {
+        Coordinates { x, y }
+    }
+
+    /// Every outcome, of every step-th position, is justified by the outcomes of its children.
+    fn verify(tables: &Tablebases, material: &Material, step: usize) -> () {
+        for p in positions(material).step_by(step) {
+            let outcome = tables.probe(&p).unwrap();
+            let mut moves = MoveList::new();
+            p.legal_moves(&mut moves);
+            let children: Vec<Outcome> = moves.iter().map(|m| tables.probe(&p.make(*m)).unwrap()).collect();
+            match outcome {
+                Outcome::Loss(0) => assert!(children.is_empty() && p.is_in_guard()),
+                Outcome::Win(d) => {
+                    assert!(children.contains(&Outcome::Loss(d - 1)));
+                    assert!(children.iter().all(|c| !matches!(c, Outcome::Loss(e) if *e < d - 1)));
+                }
+                Outcome::Loss(d) => {
+                    assert!(children.contains(&Outcome::Win(d - 1)));
+                    assert!(children.iter().all(|c| matches!(c, Outcome::Win(e) if *e < d)));
+                }
+                Outcome::Draw => assert!(children.iter().all(|c| !matches!(c, Outcome::Loss(_)))),
+            }
+        }
+    }
+
+    #[test]
+    fn material_is_sorted_and_validated() {
+        let material: Material = "FD-D".parse().unwrap();
+        assert_eq!(
+            &[(Owner::Player1, UnitId::Duke), (Owner::Player1, UnitId::Footman), (Owner::Player2, UnitId::Duke)],
+            material.tokens(),
+        );
+        assert_eq!("DF-D", material.to_string());
+        assert!("F-D".parse::<Material>().is_err());
+        assert!("DD-D".parse::<Material>().is_err());
+        assert!("DFFF-D".parse::<Material>().is_err());
+    }
+
+    #[test]
+    fn index_round_trips() {
+        let material: Material = "DF-DF".parse().unwrap();
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(3, 2), Owner::Player1, UnitId::Footman, CurrentSide::Flipped);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Flipped);
+        board.put(c(1, 4), Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        let p = Position::new(board, Owner::Player2, [[0; UnitId::COUNT]; 2]);
+        assert_eq!(Some(p), material.position(material.index(&p)));
+        assert_eq!(Some(material), Material::of(&p));
+    }
+
+    #[test]
+    fn dukes_table_is_consistent() {
+        let mut tables = Tablebases::new();
+        tables.generate(&dukes());
+        assert_eq!(1, tables.len());
+        verify(&tables, &dukes(), 1);
+    }
+
+    #[test]
+    fn footman_table_includes_captured_materials_and_finds_mates() {
+        let material: Material = "D-DF".parse().unwrap();
+        let mut tables = Tablebases::new();
+        tables.generate(&material);
+        assert_eq!(2, tables.len());
+        assert!(tables.get(&dukes()).is_some());
+        verify(&tables, &material, 101);
+        let wins = positions(&material).filter(|p| matches!(tables.probe(p), Some(Outcome::Win(_)))).count();
+        assert!(wins > 0);
+    }
+
+    #[test]
+    fn probe_requires_a_matching_material_and_empty_bags() {
+        let mut tables = Tablebases::new();
+        tables.generate(&dukes());
+        let mut board = BitBoard::empty();
+        board.put(c(0, 0), Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(c(5, 5), Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        let p = Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2]);
+        assert!(tables.probe(&p).is_some());
+        let mut bags = [[0; UnitId::COUNT]; 2];
+        bags[0][UnitId::Footman.index()] = 1;
+        assert_eq!(None, tables.probe(&Position::new(board, Owner::Player1, bags)));
+        board.put(c(2, 2), Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        assert_eq!(None, tables.probe(&Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2])));
+    }
+
+    #[test]
+    fn write_and_read_round_trip() {
+        let mut tables = Tablebases::new();
+        tables.generate(&dukes());
+        let table = tables.get(&dukes()).unwrap();
+        let mut bytes = Vec::new();
+        table.write(&mut bytes).unwrap();
+        assert_eq!(8 + 1 + 4 + dukes().size(), bytes.len());
+        assert_eq!(*table, Tablebase::read(&mut bytes.as_slice()).unwrap());
+        bytes[0] = b'X';
+        assert!(Tablebase::read(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn write_and_read_dir_round_trip() {
+        let dir = std::env::temp_dir().join(format!("duke_tablebases_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut tables = Tablebases::new();
+        tables.generate(&dukes());
+        tables.write_dir(&dir).unwrap();
+        assert!(dir.join("D-D.tb").exists());
+        let read = Tablebases::read_dir(&dir).unwrap();
+        assert_eq!(tables.get(&dukes()), read.get(&dukes()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
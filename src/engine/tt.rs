@@ -0,0 +1,188 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::engine::eval::{Score, MATE_BOUND};
+use crate::game::moves::Move;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Bound {
+    Exact,
+    /// The score is at least this large (a beta cutoff).
+    Lower,
+    /// The score is at most this large (no move raised alpha).
+    Upper,
+}
+
+impl Bound {
+    fn from_bits(bits: u64) -> Bound {
+        match bits {
+            0 => Bound::Exact,
+            1 => Bound::Lower,
+            _ => Bound::Upper,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Entry {
+    pub best_move: Option<Move>,
+    /// Mate scores are relative to the probing node, see [TranspositionTable::probe].
+    pub score: Score,
+    pub depth: u32,
+    pub bound: Bound,
+}
+
+/// Data layout:
+/// * bits 0-31: the best move's bits.
+/// * bit 32: set if there is a best move.
+/// * bits 33-48: the score, as an i16.
+/// * bits 49-56: the depth.
+/// * bits 57-58: the bound.
+impl Entry {
+    fn pack(&self) -> u64 {
+        let (has_move, move_bits) = match self.best_move {
+            Some(m) => (1, u64::from(m.to_bits())),
+            None => (0, 0),
+        };
+        move_bits
+            | has_move << 32
+            | u64::from(self.score as i16 as u16) << 33
+            | u64::from(self.depth.min(0xFF)) << 49
+            | (self.bound as u64) << 57
+    }
+
+    fn unpack(data: u64) -> Entry {
+        Entry {
+            best_move: if data >> 32 & 1 == 1 { Some(Move::from_bits(data as u32)) } else { None },
+            score: Score::from((data >> 33) as u16 as i16),
+            depth: (data >> 49 & 0xFF) as u32,
+            bound: Bound::from_bits(data >> 57 & 0b11),
+        }
+    }
+}
+
+struct Slot {
+    /// The key XORed with the data, so a torn write (one word from another store) fails to match.
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A lock-free hash table shared between search threads. Each slot is two atomic words, and the
+/// key is stored XORed with the data, so racing writes are detected on probe rather than prevented.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    /// Rounds the size up to a power of two.
+    pub fn new(size: usize) -> TranspositionTable {
+        let size = size.max(1).next_power_of_two();
+        TranspositionTable {
+            slots: (0..size).map(|_| Slot { check: AtomicU64::new(0), data: AtomicU64::new(0) }).collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+
+    /// Mate scores are stored relative to the node, so they're converted from and to the distance
+    /// from the root by ply.
+    pub fn probe(&self, key: u64, ply: u32) -> Option<Entry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if slot.check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        let mut result = Entry::unpack(data);
+        result.score = from_table(result.score, ply);
+        Some(result)
+    }
+
+    /// Keeps an existing entry of the same position if it was searched deeper.
+    pub fn store(&self, key: u64, ply: u32, entry: Entry) -> () {
+        let slot = self.slot(key);
+        let old = slot.data.load(Ordering::Relaxed);
+        if slot.check.load(Ordering::Relaxed) ^ old == key && Entry::unpack(old).depth > entry.depth {
+            return;
+        }
+        let data = Entry { score: to_table(entry.score, ply), ..entry }.pack();
+        slot.check.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) -> () {
+        for slot in self.slots.iter() {
+            slot.check.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+fn to_table(score: Score, ply: u32) -> Score {
+    if score >= MATE_BOUND {
+        score + ply as Score
+    } else if score <= -MATE_BOUND {
+        score - ply as Score
+    } else {
+        score
+    }
+}
+
+fn from_table(score: Score, ply: u32) -> Score {
+    if score >= MATE_BOUND {
+        score - ply as Score
+    } else if score <= -MATE_BOUND {
+        score + ply as Score
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::engine::eval::MATE;
+    use crate::game::moves::MoveKind;
+
+    use super::*;
+
+    fn entry(score: Score, depth: u32) -> Entry {
+        Entry { best_move: Some(Move::new(3, 9, MoveKind::Slide)), score, depth, bound: Bound::Lower }
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        for e in [entry(-123, 7), entry(MATE - 3, 0), Entry { best_move: None, score: 5, depth: 1, bound: Bound::Upper }].iter() {
+            assert_eq!(*e, Entry::unpack(e.pack()));
+        }
+    }
+
+    #[test]
+    fn probe_misses_other_keys() {
+        let tt = TranspositionTable::new(16);
+        tt.store(42, 0, entry(10, 3));
+        assert_eq!(Some(entry(10, 3)), tt.probe(42, 0));
+        assert_eq!(None, tt.probe(42 + 16, 0));
+    }
+
+    #[test]
+    fn deeper_entries_are_kept() {
+        let tt = TranspositionTable::new(16);
+        tt.store(42, 0, entry(10, 5));
+        tt.store(42, 0, entry(20, 2));
+        assert_eq!(Some(entry(10, 5)), tt.probe(42, 0));
+        tt.store(42, 0, entry(30, 6));
+        assert_eq!(Some(entry(30, 6)), tt.probe(42, 0));
+    }
+
+    #[test]
+    fn mate_scores_are_relative_to_the_node() {
+        let tt = TranspositionTable::new(16);
+        // Mate in 5 plies from the root, found at ply 2, is a mate in 3 from the node.
+        tt.store(42, 2, entry(MATE - 5, 1));
+        assert_eq!(MATE - 4, tt.probe(42, 1).unwrap().score);
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::OnceLock;
+
+use crate::common::coordinates::Vector;
+use crate::game::token::{CurrentSide, GameToken, Owner, TokenAction, TokenSide};
+use crate::game::units::UnitId;
+
+/// The actions of a single token side, oriented by its owner and grouped by [TokenAction], so they
+/// don't have to be rediscovered by scanning the side's board.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ActionTable {
+    /// Linear moves, which can be blocked by tokens in their path.
+    pub moves: Vec<Vector>,
+    pub jumps: Vec<Vector>,
+    /// The directions of the slides.
+    pub slides: Vec<Vector>,
+    /// The first square of each jump slide, which then continues in the same direction.
+    pub jump_slides: Vec<Vector>,
+    pub strikes: Vec<Vector>,
+    pub commands: Vec<Vector>,
+}
+
+impl ActionTable {
+    pub fn new(side: &TokenSide, owner: Owner) -> ActionTable {
+        let mut result = ActionTable::default();
+        for (offset, action) in side.actions() {
+            let v = Vector::from(owner.orient(offset));
+            match action {
+                TokenAction::Move => result.moves.push(v),
+                TokenAction::Jump => result.jumps.push(v),
+                TokenAction::Slide => result.slides.push(v),
+                TokenAction::JumpSlide => result.jump_slides.push(v),
+                TokenAction::Strike => result.strikes.push(v),
+                TokenAction::Command => result.commands.push(v),
+            }
+        }
+        result
+    }
+
+    /// All the actions, ungrouped.
+    pub fn iter(&self) -> impl Iterator<Item=(Vector, TokenAction)> + '_ {
+        fn tagged(vs: &[Vector], a: TokenAction) -> impl Iterator<Item=(Vector, TokenAction)> + '_ {
+            vs.iter().map(move |v| (*v, a))
+        }
+        tagged(&self.moves, TokenAction::Move)
+            .chain(tagged(&self.jumps, TokenAction::Jump))
+            .chain(tagged(&self.slides, TokenAction::Slide))
+            .chain(tagged(&self.jump_slides, TokenAction::JumpSlide))
+            .chain(tagged(&self.strikes, TokenAction::Strike))
+            .chain(tagged(&self.commands, TokenAction::Command))
+    }
+}
+
+fn registry_index(unit: UnitId, side: CurrentSide, owner: Owner) -> usize {
+    (unit.index() * 2 + side.index()) * 2 + owner.index()
+}
+
+fn registry() -> &'static Vec<ActionTable> {
+    static REGISTRY: OnceLock<Vec<ActionTable>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut result = vec![ActionTable::default(); UnitId::COUNT * 2 * 2];
+        for unit in UnitId::ALL.iter() {
+            for owner in Owner::ALL.iter() {
+                let token = unit.token(*owner).token;
+                result[registry_index(*unit, CurrentSide::Initial, *owner)] = ActionTable::new(&token.side_a, *owner);
+                result[registry_index(*unit, CurrentSide::Flipped, *owner)] = ActionTable::new(&token.side_b, *owner);
+            }
+        }
+        result
+    })
+}
+
+fn standard_tokens() -> &'static Vec<GameToken> {
+    static TOKENS: OnceLock<Vec<GameToken>> = OnceLock::new();
+    TOKENS.get_or_init(|| UnitId::ALL.iter().map(|u| u.token(Owner::Player1).token).collect())
+}
+
+/// Whether the token has both sides of the unit, rather than just its name.
+pub fn is_standard(unit: UnitId, token: &GameToken) -> bool {
+    let standard = &standard_tokens()[unit.index()];
+    token.side_a == standard.side_a && token.side_b == standard.side_b
+}
+
+/// The precompiled table of a standard unit's side, oriented by owner.
+pub fn lookup(unit: UnitId, side: CurrentSide, owner: Owner) -> &'static ActionTable {
+    &registry()[registry_index(unit, side, owner)]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::offset::{Offsets, VerticalOffset};
+    use crate::game::token::OwnedToken;
+    use crate::game::units::FOOTMAN;
+    use crate::hashmap;
+
+    use super::*;
+
+    #[test]
+    fn footman_flipped_side_is_grouped_and_oriented() {
+        let p1 = lookup(UnitId::Footman, CurrentSide::Flipped, Owner::Player1);
+        assert_eq!(5, p1.moves.len());
+        assert!(p1.moves.contains(&Vector::new(0, 2)));
+        assert!(p1.jumps.is_empty() && p1.slides.is_empty());
+        let p2 = lookup(UnitId::Footman, CurrentSide::Flipped, Owner::Player2);
+        assert!(p2.moves.contains(&Vector::new(0, -2)));
+    }
+
+    #[test]
+    fn duke_sides_are_slides() {
+        let initial = lookup(UnitId::Duke, CurrentSide::Initial, Owner::Player1);
+        assert_eq!(vec![Vector::LEFT, Vector::RIGHT], initial.slides);
+        let flipped = lookup(UnitId::Duke, CurrentSide::Flipped, Owner::Player1);
+        assert_eq!(vec![Vector::UP, Vector::DOWN], flipped.slides);
+    }
+
+    #[test]
+    fn tokens_named_after_a_unit_keep_their_own_sides() {
+        let footman = UnitId::Footman.token(Owner::Player1);
+        assert!(is_standard(UnitId::Footman, &footman.token));
+
+        let strike = TokenSide::new(hashmap![Offsets::centered(VerticalOffset::Top) => TokenAction::Strike]);
+        let impostor = OwnedToken {
+            token: GameToken::new(strike.clone(), strike, FOOTMAN.to_owned()),
+            owner: Owner::Player1,
+        };
+        assert!(!is_standard(UnitId::Footman, &impostor.token));
+        assert_eq!(None, impostor.token.unit());
+        let table = impostor.action_table();
+        assert!(table.moves.is_empty());
+        assert_eq!(vec![Vector::UP], table.strikes);
+    }
+
+    #[test]
+    fn registry_matches_scanning_the_token_sides() {
+        for unit in UnitId::ALL.iter() {
+            for owner in Owner::ALL.iter() {
+                for side in CurrentSide::ALL.iter() {
+                    let mut token = unit.token(*owner);
+                    token.token.current_side = *side;
+                    let mut expected: Vec<(Vector, TokenAction)> = token
+                        .current_actions()
+                        .into_iter()
+                        .map(|(o, a)| (Vector::from(o), a))
+                        .collect();
+                    let mut actual: Vec<(Vector, TokenAction)> = lookup(*unit, *side, *owner).iter().collect();
+                    let key = |e: &(Vector, TokenAction)| (e.0.x, e.0.y);
+                    expected.sort_by_key(key);
+                    actual.sort_by_key(key);
+                    assert_eq!(expected, actual);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use std::sync::OnceLock;
+
+use crate::common::coordinates::{Coordinates, Vector};
+use crate::game::board::GameBoard;
+use crate::game::moves::{GameMove, Move, MoveKind, MoveList};
+use crate::game::actions;
+use crate::game::actions::ActionTable;
+use crate::game::token::{CurrentSide, Owner};
+use crate::game::units::UnitId;
+
+/// A set of squares on the 6x6 board, where square {x, y} is bit y * 6 + x.
+pub type Bits = u64;
+
+const SIZE: u16 = GameBoard::BOARD_SIZE;
+pub const SQUARES: usize = (SIZE * SIZE) as usize;
+
+pub fn square_index(c: Coordinates) -> usize {
+    usize::from(c.y * SIZE + c.x)
+}
+pub fn square_at(i: usize) -> Coordinates {
+    Coordinates { x: i as u16 % SIZE, y: i as u16 / SIZE }
+}
+pub fn bit(c: Coordinates) -> Bits {
+    1 << square_index(c)
+}
+
+/// Iterates over the set squares' indices, from the lowest.
+pub struct Squares(Bits);
+
+impl Iterator for Squares {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            None
+        } else {
+            let i = self.0.trailing_zeros() as usize;
+            self.0 &= self.0 - 1;
+            Some(i)
+        }
+    }
+}
+
+pub fn squares(bits: Bits) -> Squares {
+    Squares(bits)
+}
+
+
+/// The precomputed squares a single unit side, oriented by its owner, can act on from a single
+/// source square.
+#[derive(Debug, Default, Clone)]
+pub struct SquareAttacks {
+    /// Move destinations adjacent to the source, which therefore can't be obstructed.
+    pub steps: Bits,
+    /// Move destinations further away, paired with the squares in between which must be empty.
+    pub far_steps: Vec<(usize, Bits)>,
+    pub jumps: Bits,
+    pub strikes: Bits,
+    /// The first square of each slide, paired with its index in [Vector::DIRECTIONS].
+    pub slides: Vec<(usize, usize)>,
+    pub jump_slides: Vec<(usize, usize)>,
+    pub commands: Bits,
+}
+
+struct Tables {
+    /// Indexed by direction, then square; the squares along the ray, excluding the square itself.
+    rays: [[Bits; SQUARES]; 8],
+    /// Indexed by square; the orthogonally adjacent squares.
+    neighbours: [Bits; SQUARES],
+    /// Indexed by [attacks_index].
+    attacks: Vec<SquareAttacks>,
+}
+
+fn attacks_index(unit: UnitId, side: CurrentSide, owner: Owner, square: usize) -> usize {
+    ((unit.index() * 2 + side.index()) * 2 + owner.index()) * SQUARES + square
+}
+
+fn direction_index(v: Vector) -> usize {
+    let direction = v.direction();
+    Vector::DIRECTIONS.iter().position(|d| *d == direction).unwrap()
+}
+
+/// True if moving in the direction increases the square index.
+fn is_ascending(direction: usize) -> bool {
+    let d = Vector::DIRECTIONS[direction];
+    d.y > 0 || (d.y == 0 && d.x > 0)
+}
+
+impl Tables {
+    fn new() -> Tables {
+        let mut rays = [[0; SQUARES]; 8];
+        for (d, direction) in Vector::DIRECTIONS.iter().enumerate() {
+            for (i, ray) in rays[d].iter_mut().enumerate() {
+                *ray = square_at(i).ray(*direction, SIZE, SIZE).fold(0, |acc, c| acc | bit(c));
+            }
+        }
+        let mut neighbours = [0; SQUARES];
+        for (i, n) in neighbours.iter_mut().enumerate() {
+            *n = [Vector::UP, Vector::DOWN, Vector::LEFT, Vector::RIGHT]
+                .iter()
+                .filter_map(|d| square_at(i).ray(*d, SIZE, SIZE).next())
+                .fold(0, |acc, c| acc | bit(c));
+        }
+        let mut attacks = vec![SquareAttacks::default(); UnitId::COUNT * 2 * 2 * SQUARES];
+        for unit in UnitId::ALL.iter() {
+            for side in CurrentSide::ALL.iter() {
+                for owner in Owner::ALL.iter() {
+                    let table = actions::lookup(*unit, *side, *owner);
+                    for (i, src) in (0..SQUARES).map(square_at).enumerate() {
+                        attacks[attacks_index(*unit, *side, *owner, i)] = Tables::square_attacks(table, src);
+                    }
+                }
+            }
+        }
+        Tables { rays, neighbours, attacks }
+    }
+
+    fn square_attacks(table: &ActionTable, src: Coordinates) -> SquareAttacks {
+        let on_board = |v: &Vector| (src + *v).filter(|c| c.x < SIZE && c.y < SIZE).map(|c| (*v, c));
+        let mask = |vs: &[Vector]| vs.iter().filter_map(on_board).fold(0, |acc, (_, c)| acc | bit(c));
+        let rays = |vs: &[Vector]| vs.iter()
+            .filter_map(on_board)
+            .map(|(v, c)| (square_index(c), direction_index(v)))
+            .collect();
+        let mut result = SquareAttacks {
+            jumps: mask(&table.jumps),
+            strikes: mask(&table.strikes),
+            commands: mask(&table.commands),
+            slides: rays(&table.slides),
+            jump_slides: rays(&table.jump_slides),
+            ..SquareAttacks::default()
+        };
+        for (v, dst) in table.moves.iter().filter_map(on_board) {
+            if v.length() == 1 {
+                result.steps |= bit(dst)
+            } else {
+                let path = src.linear_path_to(dst).iter().fold(0, |acc, c| acc | bit(*c));
+                result.far_steps.push((square_index(dst), path))
+            }
+        }
+        result
+    }
+
+    /// The squares from first (inclusive) in the direction, up to and including the first
+    /// occupied square.
+    fn slide(&self, first: usize, direction: usize, occupied: Bits) -> Bits {
+        let first_bit = 1 << first;
+        if occupied & first_bit != 0 {
+            return first_bit;
+        }
+        let ray = self.rays[direction][first];
+        let blockers = ray & occupied;
+        if blockers == 0 {
+            return first_bit | ray;
+        }
+        let blocker = if is_ascending(direction) {
+            blockers.trailing_zeros()
+        } else {
+            63 - blockers.leading_zeros()
+        } as usize;
+        first_bit | (ray & !self.rays[direction][blocker])
+    }
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(Tables::new)
+}
+
+/// The precomputed attacks of a unit side, oriented by owner, from square.
+pub fn square_attacks(unit: UnitId, side: CurrentSide, owner: Owner, square: usize) -> &'static SquareAttacks {
+    &tables().attacks[attacks_index(unit, side, owner, square)]
+}
+
+/// A compact representation of the 6x6 [GameBoard], with occupancy masks per owner and per unit
+/// side.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct BitBoard {
+    owners: [Bits; 2],
+    /// Indexed by unit, then side.
+    units: [[Bits; 2]; UnitId::COUNT],
+}
+
+impl BitBoard {
+    pub fn empty() -> BitBoard {
+        BitBoard::default()
+    }
+
+    /// [None] if the board isn't 6x6, or if it has tokens which aren't standard units.
+    pub fn from_game_board(board: &GameBoard) -> Option<BitBoard> {
+        if board.width() != SIZE || board.height() != SIZE {
+            return None;
+        }
+        let mut result = BitBoard::empty();
+        for (c, t) in board.active_coordinates() {
+            result.put(c, t.owner, t.token.unit()?, t.token.current_side);
+        }
+        Some(result)
+    }
+
+    pub fn to_game_board(self) -> GameBoard {
+        let mut result = GameBoard::empty();
+        for i in squares(self.occupied()) {
+            let c = square_at(i);
+            let (owner, unit, side) = self.get(c).unwrap();
+            let mut token = unit.token(owner);
+            token.token.current_side = side;
+            result.place(c, token);
+        }
+        result
+    }
+
+    /// Overwrites whatever was at c.
+    pub fn put(&mut self, c: Coordinates, owner: Owner, unit: UnitId, side: CurrentSide) -> () {
+        self.remove(c);
+        let b = bit(c);
+        self.owners[owner.index()] |= b;
+        self.units[unit.index()][side.index()] |= b;
+    }
+
+    pub fn remove(&mut self, c: Coordinates) -> () {
+        let mask = !bit(c);
+        self.owners.iter_mut().for_each(|o| *o &= mask);
+        self.units.iter_mut().flat_map(|u| u.iter_mut()).for_each(|s| *s &= mask);
+    }
+
+    pub fn get(&self, c: Coordinates) -> Option<(Owner, UnitId, CurrentSide)> {
+        let b = bit(c);
+        let owner = Owner::ALL.iter().find(|o| self.owners[o.index()] & b != 0)?;
+        for unit in UnitId::ALL.iter() {
+            for side in CurrentSide::ALL.iter() {
+                if self.units[unit.index()][side.index()] & b != 0 {
+                    return Some((*owner, *unit, *side));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn occupied(&self) -> Bits {
+        self.owners[0] | self.owners[1]
+    }
+    pub fn owned_by(&self, owner: Owner) -> Bits {
+        self.owners[owner.index()]
+    }
+    pub fn unit_side(&self, unit: UnitId, side: CurrentSide) -> Bits {
+        self.units[unit.index()][side.index()]
+    }
+    pub fn unit(&self, unit: UnitId) -> Bits {
+        self.units[unit.index()][0] | self.units[unit.index()][1]
+    }
+
+    pub fn duke_coordinates(&self, owner: Owner) -> Option<Coordinates> {
+        squares(self.unit(UnitId::Duke) & self.owned_by(owner)).next().map(square_at)
+    }
+
+    fn attacks_of(&self, c: Coordinates) -> Option<(Owner, &'static SquareAttacks)> {
+        let (owner, unit, side) = self.get(c)?;
+        Some((owner, square_attacks(unit, side, owner, square_index(c))))
+    }
+
+    /// See [GameBoard::attacks_from].
+    pub fn attacks_from(&self, c: Coordinates) -> Bits {
+        let (owner, attacks) = match self.attacks_of(c) {
+            Some(e) => e,
+            None => return 0,
+        };
+        let tables = tables();
+        let occupied = self.occupied();
+        let mut result = attacks.steps | attacks.jumps | attacks.strikes;
+        for (dst, path) in &attacks.far_steps {
+            if path & occupied == 0 {
+                result |= 1 << dst;
+            }
+        }
+        for (first, direction) in attacks.slides.iter().chain(attacks.jump_slides.iter()) {
+            result |= tables.slide(*first, *direction, occupied);
+        }
+        let commanded = attacks.commands & self.owned_by(owner);
+        result |= match commanded.count_ones() {
+            0 => 0,
+            1 => attacks.commands & !commanded,
+            _ => attacks.commands,
+        };
+        result
+    }
+
+    pub fn attacked_by(&self, owner: Owner) -> Bits {
+        squares(self.owned_by(owner)).fold(0, |acc, i| acc | self.attacks_from(square_at(i)))
+    }
+
+    /// See [GameBoard::is_in_guard].
+    pub fn is_in_guard(&self, owner: Owner) -> bool {
+        let duke = self.unit(UnitId::Duke) & self.owned_by(owner);
+        duke != 0 && self.attacked_by(owner.other()) & duke != 0
+    }
+
+    /// Pushes all the moves of the token at src into moves, without allocating. Like
+    /// [GameBoard::moves_from], this doesn't verify that the move doesn't leave the Duke in guard.
+    pub fn generate_from(&self, src: Coordinates, moves: &mut MoveList) -> () {
+        let (owner, attacks) = match self.attacks_of(src) {
+            Some(e) => e,
+            None => return,
+        };
+        let from = square_index(src);
+        let tables = tables();
+        let occupied = self.occupied();
+        let own = self.owned_by(owner);
+        let enemy = self.owned_by(owner.other());
+        let mut push_moves = |dsts: Bits, kind: MoveKind| {
+            for dst in squares(dsts & !own) {
+                moves.push(Move::new(from, dst, kind));
+            }
+        };
+        push_moves(attacks.steps, MoveKind::Move);
+        for (dst, path) in &attacks.far_steps {
+            if path & occupied == 0 {
+                push_moves(1 << dst, MoveKind::Move);
+            }
+        }
+        push_moves(attacks.jumps, MoveKind::Jump);
+        for (first, direction) in &attacks.slides {
+            push_moves(tables.slide(*first, *direction, occupied), MoveKind::Slide);
+        }
+        for (first, direction) in &attacks.jump_slides {
+            push_moves(tables.slide(*first, *direction, occupied), MoveKind::JumpSlide);
+        }
+        for dst in squares(attacks.strikes & enemy) {
+            moves.push(Move::new(from, dst, MoveKind::Strike));
+        }
+        for commanded in squares(attacks.commands & own) {
+            for dst in squares(attacks.commands & !own) {
+                moves.push(Move::command(from, commanded, dst));
+            }
+        }
+    }
+
+    /// Pushes all the moves of owner's tokens into moves, see [BitBoard::generate_from].
+    pub fn generate(&self, owner: Owner, moves: &mut MoveList) -> () {
+        for i in squares(self.owned_by(owner)) {
+            self.generate_from(square_at(i), moves);
+        }
+    }
+
+    /// See [GameBoard::moves_from].
+    pub fn moves_from(&self, src: Coordinates) -> Vec<GameMove> {
+        let mut moves = MoveList::new();
+        self.generate_from(src, &mut moves);
+        moves.iter().filter_map(|m| m.to_game_move()).collect()
+    }
+
+    /// See [GameBoard::moves].
+    pub fn moves(&self, owner: Owner) -> Vec<GameMove> {
+        let mut moves = MoveList::new();
+        self.generate(owner, &mut moves);
+        moves.iter().filter_map(|m| m.to_game_move()).collect()
+    }
+
+    /// The empty squares orthogonally adjacent to owner's Duke, where new tokens can be summoned.
+    pub fn summoning_squares(&self, owner: Owner) -> Bits {
+        squares(self.unit(UnitId::Duke) & self.owned_by(owner))
+            .fold(0, |acc, i| acc | tables().neighbours[i])
+            & !self.occupied()
+    }
+
+    /// Panics if c is empty.
+    pub fn flip(&mut self, c: Coordinates) -> () {
+        let (owner, unit, side) = self.get(c).unwrap_or_else(|| panic!("No token to flip at {}", c));
+        self.put(c, owner, unit, side.flip());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::game::state::{DukeInitialLocation, FootmenSetup, GameState};
+    use crate::game::token::TokenBag;
+
+    use super::*;
+
+    fn place_randomly(rng: &mut StdRng, board: &mut GameBoard, owner: Owner, unit: UnitId) {
+        loop {
+            let c = Coordinates { x: rng.gen_range(0..SIZE), y: rng.gen_range(0..SIZE) };
+            let mut token = unit.token(owner);
+            if rng.gen_bool(0.5) {
+                token.token.flip();
+            }
+            if board.try_place(c, token).is_ok() {
+                return;
+            }
+        }
+    }
+
+    fn random_board(rng: &mut StdRng) -> GameBoard {
+        let mut board = GameBoard::empty();
+        for owner in Owner::ALL.iter() {
+            place_randomly(rng, &mut board, *owner, UnitId::Duke);
+        }
+        for _ in 0..rng.gen_range(0..12) {
+            let owner = if rng.gen_bool(0.5) { Owner::Player1 } else { Owner::Player2 };
+            place_randomly(rng, &mut board, owner, UnitId::Footman);
+        }
+        board
+    }
+
+    fn to_set(bits: Bits) -> HashSet<Coordinates> {
+        squares(bits).map(square_at).collect()
+    }
+
+    fn assert_same_as_game_board(board: &GameBoard) {
+        let bb = BitBoard::from_game_board(board).unwrap();
+        for c in (0..SQUARES).map(square_at) {
+            assert_eq!(
+                board.moves_from(c).into_iter().collect::<HashSet<_>>(),
+                bb.moves_from(c).into_iter().collect::<HashSet<_>>(),
+                "moves from {}", c,
+            );
+            assert_eq!(
+                board.attacks_from(c).into_iter().collect::<HashSet<_>>(),
+                to_set(bb.attacks_from(c)),
+                "attacks from {}", c,
+            );
+        }
+        for owner in Owner::ALL.iter() {
+            assert_eq!(board.is_in_guard(*owner), bb.is_in_guard(*owner));
+            assert_eq!(board.duke_coordinates(*owner), bb.duke_coordinates(*owner));
+        }
+    }
+
+    #[test]
+    fn square_index_round_trips() {
+        for i in 0..SQUARES {
+            assert_eq!(i, square_index(square_at(i)));
+        }
+    }
+
+    #[test]
+    fn put_get_and_remove() {
+        let mut bb = BitBoard::empty();
+        let c = Coordinates { x: 4, y: 3 };
+        bb.put(c, Owner::Player2, UnitId::Footman, CurrentSide::Flipped);
+        assert_eq!(Some((Owner::Player2, UnitId::Footman, CurrentSide::Flipped)), bb.get(c));
+        assert_eq!(bit(c), bb.unit_side(UnitId::Footman, CurrentSide::Flipped));
+        bb.put(c, Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        assert_eq!(Some((Owner::Player1, UnitId::Duke, CurrentSide::Initial)), bb.get(c));
+        assert_eq!(0, bb.unit(UnitId::Footman));
+        bb.remove(c);
+        assert_eq!(BitBoard::empty(), bb);
+    }
+
+    #[test]
+    fn summoning_squares_are_empty_orthogonal_neighbours_of_the_duke() {
+        let mut bb = BitBoard::empty();
+        bb.put(Coordinates { x: 0, y: 1 }, Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        bb.put(Coordinates { x: 1, y: 1 }, Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        assert_eq!(
+            bit(Coordinates { x: 0, y: 0 }) | bit(Coordinates { x: 0, y: 2 }),
+            bb.summoning_squares(Owner::Player1),
+        );
+        assert_eq!(0, bb.summoning_squares(Owner::Player2));
+    }
+
+    #[test]
+    fn round_trips_through_game_board() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let bb = BitBoard::from_game_board(&random_board(&mut rng)).unwrap();
+            assert_eq!(Some(bb), BitBoard::from_game_board(&bb.to_game_board()));
+        }
+    }
+
+    #[test]
+    fn same_as_game_board_in_initial_positions() {
+        let setups = || vec![
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Right, FootmenSetup::Left),
+            (DukeInitialLocation::Left, FootmenSetup::Right),
+        ];
+        for p1 in setups() {
+            for p2 in setups() {
+                assert_same_as_game_board(&GameState::new(&TokenBag::new(vec![]), p1, p2).board);
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_game_board_in_random_positions() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..200 {
+            assert_same_as_game_board(&random_board(&mut rng));
+        }
+    }
+}
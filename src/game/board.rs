@@ -1,17 +1,15 @@
-use std::convert::TryFrom;
-
-use crate::common::board::Board;
-use crate::common::coordinates::Coordinates;
-use crate::game::offset;
-use crate::game::offset::{HorizontalOffset, VerticalOffset};
-use crate::game::token::{OwnedToken, Ownership, TokenAction};
+use crate::common::board::{Board, BoardError};
+use crate::common::coordinates::{Coordinates, Vector};
+use crate::game::moves::GameMove;
+use crate::game::token::{OwnedToken, Owner, TokenAction};
 
+#[derive(Clone)]
 pub struct GameBoard {
     board: Board<OwnedToken>,
 }
 
 impl GameBoard {
-    const BOARD_SIZE: u16 = 6;
+    pub const BOARD_SIZE: u16 = 6;
     pub fn height(&self) -> u16 {
         self.board.height
     }
@@ -22,54 +20,499 @@ impl GameBoard {
         GameBoard { board: Board::square(GameBoard::BOARD_SIZE) }
     }
     pub fn place(&mut self, c: Coordinates, t: OwnedToken) -> () {
-        assert!(self.board.is_empty(c), "Cannot insert token into occupied space {:?}", c);
+        if let Err(e) = self.try_place(c, t) {
+            panic!("Cannot insert token: {}", e)
+        }
+    }
+    pub fn try_place(&mut self, c: Coordinates, t: OwnedToken) -> Result<(), BoardError> {
+        if self.board.try_get(c)?.is_some() {
+            return Err(BoardError::Occupied(c));
+        }
         self.board.put(c, t);
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=(Coordinates, Option<&OwnedToken>)> {
+        self.board.iter()
     }
 
-    pub fn rows(&self) -> &Vec<Vec<Option<OwnedToken>>> {
-        self.board.rows()
+    pub fn active_coordinates(&self) -> Vec<(Coordinates, &OwnedToken)> {
+        self.board.active_coordinates()
     }
 
     pub fn get(&self, c: Coordinates) -> Option<&OwnedToken> {
         self.board.get(c)
     }
+    pub fn try_get(&self, c: Coordinates) -> Result<Option<&OwnedToken>, BoardError> {
+        self.board.try_get(c)
+    }
 
-    fn to_absolute_coordinate(
-        &self, src: Coordinates, offset: offset::Offsets) -> Option<Coordinates> {
-        let x: i32 = (src.x as i32) + match offset.x {
-            HorizontalOffset::FarLeft => -2,
-            HorizontalOffset::Left => -1,
-            HorizontalOffset::Center => 0,
-            HorizontalOffset::Right => 1,
-            HorizontalOffset::FarRight => 2,
-        };
-        let y: i32 = (src.x as i32) + match offset.y {
-            VerticalOffset::FarTop => -2,
-            VerticalOffset::Top => -1,
-            VerticalOffset::Center => 0,
-            VerticalOffset::Bottom => 1,
-            VerticalOffset::FarBottom => 2,
-        };
-        u16::try_from(x).and_then(|x| u16::try_from(y).map(|y| Coordinates { x, y }))
-            .ok()
-            .filter(|e| self.board.is_in_bounds(*e))
+    fn to_absolute_coordinate(&self, src: Coordinates, v: Vector) -> Option<Coordinates> {
+        (src + v).filter(|e| self.board.is_in_bounds(*e))
     }
 
     fn unobstructed(&self, src: Coordinates, dst: Coordinates) -> bool {
         src.linear_path_to(dst).iter().all(|c| self.board.is_empty(*c))
     }
-    fn can_apply(&self, src_token: &OwnedToken, src: Coordinates, c: offset::Offsets, a: &TokenAction) -> Option<Coordinates> {
-        self.to_absolute_coordinate(src, c)
-            .filter(|dst| {
-                let can_move_to = self.board.get(*dst).map_or(false, |c| src_token.different_team(c));
-                match a {
-                    TokenAction::Move => self.unobstructed(src, *dst) && can_move_to,
-                    TokenAction::Jump => can_move_to,
-                    TokenAction::Slide => unimplemented!(),
-                    TokenAction::Command => unimplemented!(),
-                    TokenAction::JumpSlide => unimplemented!(),
-                    TokenAction::Strike => unimplemented!(),
+
+    /// True if a token of owner can end its action on c, i.e., c is either empty or an enemy's.
+    fn can_land_on(&self, owner: Owner, c: Coordinates) -> bool {
+        match self.board.get(c) {
+            None => true,
+            Some(t) => t.owner != owner,
+        }
+    }
+
+    fn is_enemy(&self, owner: Owner, c: Coordinates) -> bool {
+        self.board.get(c).is_some_and(|t| t.owner != owner)
+    }
+
+    /// The squares from first (inclusive) along direction, up to and including the first enemy
+    /// token, and excluding the first friendly token.
+    fn slide(&self, owner: Owner, first: Coordinates, direction: Vector) -> Vec<Coordinates> {
+        let mut result = Vec::new();
+        for c in std::iter::once(first).chain(self.board.ray(first, direction)) {
+            match self.board.get(c) {
+                None => result.push(c),
+                Some(t) => {
+                    if t.owner != owner {
+                        result.push(c);
+                    }
+                    break;
                 }
+            }
+        }
+        result
+    }
+
+    /// All the moves of the token at src, or nothing if src is empty. The token's actions are
+    /// oriented by its owner, so Top is always towards the enemy. This doesn't verify that the
+    /// move doesn't leave the Duke in guard.
+    pub fn moves_from(&self, src: Coordinates) -> Vec<GameMove> {
+        let token = match self.board.get(src) {
+            Some(t) => t,
+            None => return Vec::new(),
+        };
+        let owner = token.owner;
+        let table = token.action_table();
+        let absolute = |v: &Vector| self.to_absolute_coordinate(src, *v);
+        let mut result = Vec::new();
+        for dst in table.moves.iter().filter_map(absolute) {
+            if self.unobstructed(src, dst) && self.can_land_on(owner, dst) {
+                result.push(GameMove::Move { src, dst, action: TokenAction::Move });
+            }
+        }
+        for dst in table.jumps.iter().filter_map(absolute) {
+            if self.can_land_on(owner, dst) {
+                result.push(GameMove::Move { src, dst, action: TokenAction::Jump });
+            }
+        }
+        // A jump slide ignores anything between src and its first square; a slide's first square is
+        // adjacent to src anyway.
+        for (vs, action) in &[(&table.slides, TokenAction::Slide), (&table.jump_slides, TokenAction::JumpSlide)] {
+            for v in vs.iter() {
+                if let Some(first) = absolute(v) {
+                    for dst in self.slide(owner, first, v.direction()) {
+                        result.push(GameMove::Move { src, dst, action: *action });
+                    }
+                }
+            }
+        }
+        for dst in table.strikes.iter().filter_map(absolute) {
+            if self.is_enemy(owner, dst) {
+                result.push(GameMove::Strike { src, dst });
+            }
+        }
+        let command_squares: Vec<Coordinates> = table.commands.iter().filter_map(absolute).collect();
+        for from in &command_squares {
+            if !self.board.get(*from).is_some_and(|t| t.owner == owner) {
+                continue;
+            }
+            for to in &command_squares {
+                if from != to && self.can_land_on(owner, *to) {
+                    result.push(GameMove::Command { src, from: *from, to: *to });
+                }
+            }
+        }
+        result
+    }
+
+    /// The squares from first (inclusive) along direction, up to and including the first token.
+    fn slide_reach(&self, first: Coordinates, direction: Vector) -> Vec<Coordinates> {
+        let mut result = Vec::new();
+        for c in std::iter::once(first).chain(self.board.ray(first, direction)) {
+            result.push(c);
+            if self.board.is_occupied(c) {
+                break;
+            }
+        }
+        result
+    }
+
+    /// The squares threatened by the token at src, i.e., where it would capture an enemy token or
+    /// defend a friendly one. Unlike [GameBoard::moves_from], occupants of the target squares are
+    /// ignored, though tokens can still block paths. Command squares are only threatened if there
+    /// is a friendly token on another command square which can be commanded there.
+    pub fn attacks_from(&self, src: Coordinates) -> Vec<Coordinates> {
+        let token = match self.board.get(src) {
+            Some(t) => t,
+            None => return Vec::new(),
+        };
+        let table = token.action_table();
+        let absolute = |v: &Vector| self.to_absolute_coordinate(src, *v);
+        let mut result: Vec<Coordinates> = table.moves
+            .iter()
+            .filter_map(absolute)
+            .filter(|dst| self.unobstructed(src, *dst))
+            .collect();
+        result.extend(table.jumps.iter().chain(table.strikes.iter()).filter_map(absolute));
+        for v in table.slides.iter().chain(table.jump_slides.iter()) {
+            if let Some(first) = absolute(v) {
+                result.extend(self.slide_reach(first, v.direction()));
+            }
+        }
+        let command_squares: Vec<Coordinates> = table.commands.iter().filter_map(absolute).collect();
+        for to in &command_squares {
+            let commandable = command_squares
+                .iter()
+                .any(|from| from != to && self.board.get(*from).is_some_and(|t| t.owner == token.owner));
+            if commandable {
+                result.push(*to);
+            }
+        }
+        let mut deduped = Vec::with_capacity(result.len());
+        for c in result {
+            if !deduped.contains(&c) {
+                deduped.push(c);
+            }
+        }
+        deduped
+    }
+
+    pub fn is_attacked_by(&self, c: Coordinates, attacker: Owner) -> bool {
+        self.board
+            .active_coordinates()
+            .into_iter()
+            .filter(|(_, t)| t.owner == attacker)
+            .any(|(src, _)| self.attacks_from(src).contains(&c))
+    }
+
+    pub fn duke_coordinates(&self, owner: Owner) -> Option<Coordinates> {
+        self.board
+            .active_coordinates()
+            .into_iter()
+            .find(|(_, t)| t.owner == owner && t.token.is_duke())
+            .map(|(c, _)| c)
+    }
+
+    /// True if owner's Duke is threatened by the other player.
+    pub fn is_in_guard(&self, owner: Owner) -> bool {
+        self.duke_coordinates(owner).is_some_and(|c| self.is_attacked_by(c, owner.other()))
+    }
+
+    /// All the moves of owner's tokens, see [GameBoard::moves_from].
+    pub fn moves(&self, owner: Owner) -> Vec<GameMove> {
+        self.board
+            .active_coordinates()
+            .into_iter()
+            .filter(|(_, t)| t.owner == owner)
+            .flat_map(|(c, _)| self.moves_from(c))
+            .collect()
+    }
+
+    /// owner's moves which don't leave its Duke in guard.
+    pub fn legal_moves(&self, owner: Owner) -> Vec<GameMove> {
+        self.moves(owner)
+            .into_iter()
+            .filter(|m| {
+                let mut next = self.clone();
+                next.make_move(m);
+                !next.is_in_guard(owner)
             })
+            .collect()
+    }
+
+    /// Applies m, which is assumed to be legal, and flips the acting token. Returns the captured
+    /// token, if any.
+    pub fn make_move(&mut self, m: &GameMove) -> Option<OwnedToken> {
+        match *m {
+            GameMove::Move { src, dst, .. } => {
+                let mut token = self.board.remove(src).expect("No token to move");
+                token.token.flip();
+                self.board.put(dst, token)
+            }
+            GameMove::Strike { src, dst } => {
+                let captured = self.board.remove(dst);
+                self.board.get_mut(src).expect("No striking token").token.flip();
+                captured
+            }
+            GameMove::Command { src, from, to } => {
+                let token = self.board.remove(from).expect("No token to command");
+                let captured = self.board.put(to, token);
+                self.board.get_mut(src).expect("No commanding token").token.flip();
+                captured
+            }
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::hashmap;
+    use crate::game::offset::{HorizontalOffset, Offsets, VerticalOffset};
+    use crate::game::token::{CurrentSide, GameToken, Owner, TokenSide};
+    use crate::game::units;
+
+    use super::*;
+
+    fn flipped(mut t: OwnedToken) -> OwnedToken {
+        t.token.flip();
+        t
+    }
+
+    fn custom(owner: Owner, actions: HashMap<Offsets, TokenAction>) -> OwnedToken {
+        let side = TokenSide::new(actions);
+        OwnedToken { token: GameToken::new(side.clone(), side, "Custom".to_owned()), owner }
+    }
+
+    fn mirrored(c: Coordinates) -> Coordinates {
+        Coordinates { x: c.x, y: GameBoard::BOARD_SIZE - 1 - c.y }
+    }
+
+    fn targets(board: &GameBoard, src: Coordinates) -> HashSet<Coordinates> {
+        board.moves_from(src).iter().map(|m| m.target()).collect()
+    }
+
+    fn coordinates(cs: &[(u16, u16)]) -> HashSet<Coordinates> {
+        cs.iter().map(|(x, y)| Coordinates { x: *x, y: *y }).collect()
+    }
+
+    fn far_top() -> Offsets {
+        Offsets::centered(VerticalOffset::FarTop)
+    }
+
+    #[test]
+    fn try_place_on_empty_square() {
+        let mut board = GameBoard::empty();
+        assert_eq!(Ok(()), board.try_place(Coordinates { x: 1, y: 1 }, units::footman(Owner::Player1)));
+        assert!(board.get(Coordinates { x: 1, y: 1 }).is_some());
+    }
+
+    #[test]
+    fn try_place_on_occupied_square() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 1, y: 1 }, units::footman(Owner::Player1));
+        assert_eq!(
+            Err(BoardError::Occupied(Coordinates { x: 1, y: 1 })),
+            board.try_place(Coordinates { x: 1, y: 1 }, units::duke(Owner::Player2)),
+        );
+        assert_eq!(Owner::Player1, board.get(Coordinates { x: 1, y: 1 }).unwrap().owner);
+    }
+
+    #[test]
+    fn try_place_out_of_bounds() {
+        let mut board = GameBoard::empty();
+        assert_eq!(
+            Err(BoardError::OutOfBounds(Coordinates { x: 6, y: 0 })),
+            board.try_place(Coordinates { x: 6, y: 0 }, units::footman(Owner::Player1)),
+        );
+    }
+
+    #[test]
+    fn footman_forward_is_towards_the_enemy_for_both_players() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 1 }, flipped(units::footman(Owner::Player1)));
+        board.place(Coordinates { x: 2, y: 4 }, flipped(units::footman(Owner::Player2)));
+        assert!(targets(&board, Coordinates { x: 2, y: 1 }).contains(&Coordinates { x: 2, y: 3 }));
+        assert!(targets(&board, Coordinates { x: 2, y: 4 }).contains(&Coordinates { x: 2, y: 2 }));
+    }
+
+    #[test]
+    fn mirrored_boards_have_mirrored_moves() {
+        let jumper = || hashmap![
+            Offsets::new(HorizontalOffset::Left, VerticalOffset::FarTop) => TokenAction::Jump,
+            Offsets::new(HorizontalOffset::FarRight, VerticalOffset::Bottom) => TokenAction::Jump,
+            Offsets::new(HorizontalOffset::Right, VerticalOffset::Top) => TokenAction::Slide
+        ];
+        let mut board = GameBoard::empty();
+        let mut mirror = GameBoard::empty();
+        let place = |board: &mut GameBoard, mirror: &mut GameBoard, c: Coordinates, t: fn(Owner) -> OwnedToken| {
+            board.place(c, t(Owner::Player1));
+            mirror.place(mirrored(c), t(Owner::Player2));
+        };
+        place(&mut board, &mut mirror, Coordinates { x: 2, y: 0 }, units::duke);
+        place(&mut board, &mut mirror, Coordinates { x: 3, y: 1 }, |o| flipped(units::footman(o)));
+        place(&mut board, &mut mirror, Coordinates { x: 1, y: 0 }, units::footman);
+        board.place(Coordinates { x: 1, y: 2 }, custom(Owner::Player1, jumper()));
+        mirror.place(mirrored(Coordinates { x: 1, y: 2 }), custom(Owner::Player2, jumper()));
+
+        let moves: HashSet<(Coordinates, Coordinates)> =
+            board.moves(Owner::Player1).iter().map(|m| (mirrored(m.src()), mirrored(m.target()))).collect();
+        let mirrored_moves: HashSet<(Coordinates, Coordinates)> =
+            mirror.moves(Owner::Player2).iter().map(|m| (m.src(), m.target())).collect();
+        assert!(!moves.is_empty());
+        assert_eq!(moves, mirrored_moves);
+    }
+
+    #[test]
+    fn move_is_blocked_by_a_token_in_its_path() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 1 }, custom(Owner::Player1, hashmap![far_top() => TokenAction::Move]));
+        board.place(Coordinates { x: 2, y: 2 }, units::footman(Owner::Player2));
+        assert!(targets(&board, Coordinates { x: 2, y: 1 }).is_empty());
+    }
+
+    #[test]
+    fn jump_ignores_its_path() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 1 }, custom(Owner::Player1, hashmap![far_top() => TokenAction::Jump]));
+        board.place(Coordinates { x: 2, y: 2 }, units::footman(Owner::Player2));
+        assert_eq!(coordinates(&[(2, 3)]), targets(&board, Coordinates { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn cannot_land_on_a_friendly_token_but_can_capture_an_enemy() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 2 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 2, y: 3 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 1, y: 2 }, units::footman(Owner::Player2));
+        assert_eq!(coordinates(&[(2, 1), (1, 2), (3, 2)]), targets(&board, Coordinates { x: 2, y: 2 }));
+    }
+
+    #[test]
+    fn slide_stops_before_friendly_and_on_enemy_tokens() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 0 }, units::duke(Owner::Player1));
+        board.place(Coordinates { x: 0, y: 0 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 4, y: 0 }, units::footman(Owner::Player2));
+        assert_eq!(coordinates(&[(1, 0), (3, 0), (4, 0)]), targets(&board, Coordinates { x: 2, y: 0 }));
+    }
+
+    #[test]
+    fn jump_slide_ignores_its_first_square_path_then_slides() {
+        let mut board = GameBoard::empty();
+        let top = Offsets::centered(VerticalOffset::Top);
+        board.place(Coordinates { x: 2, y: 0 }, custom(Owner::Player1, hashmap![top => TokenAction::JumpSlide]));
+        board.place(Coordinates { x: 2, y: 3 }, units::footman(Owner::Player2));
+        assert_eq!(coordinates(&[(2, 1), (2, 2), (2, 3)]), targets(&board, Coordinates { x: 2, y: 0 }));
+    }
+
+    #[test]
+    fn strike_only_targets_enemies() {
+        let mut board = GameBoard::empty();
+        let strike = custom(Owner::Player1, hashmap![
+            far_top() => TokenAction::Strike,
+            Offsets::centered(HorizontalOffset::Left) => TokenAction::Strike,
+            Offsets::centered(HorizontalOffset::Right) => TokenAction::Strike
+        ]);
+        board.place(Coordinates { x: 2, y: 1 }, strike);
+        board.place(Coordinates { x: 2, y: 3 }, units::footman(Owner::Player2));
+        board.place(Coordinates { x: 1, y: 1 }, units::footman(Owner::Player1));
+        assert_eq!(
+            vec![GameMove::Strike { src: Coordinates { x: 2, y: 1 }, dst: Coordinates { x: 2, y: 3 } }],
+            board.moves_from(Coordinates { x: 2, y: 1 }),
+        );
+    }
+
+    #[test]
+    fn command_moves_friendly_tokens_between_command_squares() {
+        let mut board = GameBoard::empty();
+        let commander = custom(Owner::Player1, hashmap![
+            Offsets::centered(HorizontalOffset::Left) => TokenAction::Command,
+            Offsets::centered(HorizontalOffset::Right) => TokenAction::Command,
+            Offsets::centered(VerticalOffset::Top) => TokenAction::Command
+        ]);
+        let src = Coordinates { x: 2, y: 1 };
+        board.place(src, commander);
+        board.place(Coordinates { x: 1, y: 1 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 3, y: 1 }, units::footman(Owner::Player2));
+        let commands: HashSet<GameMove> = board.moves_from(src).into_iter().collect();
+        let expected: HashSet<GameMove> = vec![
+            GameMove::Command { src, from: Coordinates { x: 1, y: 1 }, to: Coordinates { x: 3, y: 1 } },
+            GameMove::Command { src, from: Coordinates { x: 1, y: 1 }, to: Coordinates { x: 2, y: 2 } },
+        ].into_iter().collect();
+        assert_eq!(expected, commands);
+    }
+
+    #[test]
+    fn attacks_include_friendly_squares_and_stop_slides_on_any_token() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 0 }, units::duke(Owner::Player1));
+        board.place(Coordinates { x: 0, y: 0 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 4, y: 0 }, units::footman(Owner::Player2));
+        assert_eq!(
+            coordinates(&[(0, 0), (1, 0), (3, 0), (4, 0)]),
+            board.attacks_from(Coordinates { x: 2, y: 0 }).into_iter().collect(),
+        );
+    }
+
+    #[test]
+    fn strike_attacks_empty_squares() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 1 }, custom(Owner::Player1, hashmap![far_top() => TokenAction::Strike]));
+        assert_eq!(vec![Coordinates { x: 2, y: 3 }], board.attacks_from(Coordinates { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn is_in_guard() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 0 }, units::duke(Owner::Player1));
+        board.place(Coordinates { x: 5, y: 5 }, units::duke(Owner::Player2));
+        assert!(!board.is_in_guard(Owner::Player1));
+        board.place(Coordinates { x: 3, y: 1 }, flipped(units::footman(Owner::Player2)));
+        assert!(board.is_in_guard(Owner::Player1));
+        assert!(!board.is_in_guard(Owner::Player2));
+    }
+
+    #[test]
+    fn make_move_flips_the_mover_and_returns_the_captured_token() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 2, y: 2 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 2, y: 3 }, units::footman(Owner::Player2));
+        let captured = board.make_move(&GameMove::Move {
+            src: Coordinates { x: 2, y: 2 },
+            dst: Coordinates { x: 2, y: 3 },
+            action: TokenAction::Move,
+        });
+        assert_eq!(Some(Owner::Player2), captured.map(|t| t.owner));
+        assert!(board.get(Coordinates { x: 2, y: 2 }).is_none());
+        let moved = board.get(Coordinates { x: 2, y: 3 }).unwrap();
+        assert_eq!(Owner::Player1, moved.owner);
+        assert_eq!(CurrentSide::Flipped, moved.token.current_side);
+    }
+
+    #[test]
+    fn make_move_strike_and_command_flip_the_actor_only() {
+        let mut board = GameBoard::empty();
+        let src = Coordinates { x: 2, y: 1 };
+        board.place(src, custom(Owner::Player1, hashmap![
+            far_top() => TokenAction::Strike,
+            Offsets::centered(HorizontalOffset::Left) => TokenAction::Command,
+            Offsets::centered(HorizontalOffset::Right) => TokenAction::Command
+        ]));
+        board.place(Coordinates { x: 2, y: 3 }, units::footman(Owner::Player2));
+        board.place(Coordinates { x: 1, y: 1 }, units::footman(Owner::Player1));
+        assert!(board.make_move(&GameMove::Strike { src, dst: Coordinates { x: 2, y: 3 } }).is_some());
+        assert!(board.get(Coordinates { x: 2, y: 3 }).is_none());
+        assert_eq!(CurrentSide::Flipped, board.get(src).unwrap().token.current_side);
+        let command = GameMove::Command { src, from: Coordinates { x: 1, y: 1 }, to: Coordinates { x: 3, y: 1 } };
+        assert!(board.make_move(&command).is_none());
+        assert_eq!(CurrentSide::Initial, board.get(src).unwrap().token.current_side);
+        let commanded = board.get(Coordinates { x: 3, y: 1 }).unwrap();
+        assert_eq!(CurrentSide::Initial, commanded.token.current_side);
+    }
+
+    #[test]
+    fn legal_moves_dont_leave_the_duke_in_guard() {
+        let mut board = GameBoard::empty();
+        board.place(Coordinates { x: 0, y: 0 }, units::duke(Owner::Player1));
+        board.place(Coordinates { x: 1, y: 0 }, units::footman(Owner::Player1));
+        board.place(Coordinates { x: 5, y: 0 }, units::duke(Owner::Player2));
+        let legal = board.legal_moves(Owner::Player1);
+        assert!(!legal.is_empty());
+        assert!(legal.iter().all(|m| m.src() != Coordinates { x: 1, y: 0 } || m.target().y == 0));
+        assert!(legal.len() < board.moves(Owner::Player1).len());
+    }
+}
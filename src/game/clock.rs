@@ -0,0 +1,398 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::game::token::Owner;
+
+/// Where clocks read the time from. Only differences between readings matter.
+pub trait TimeSource: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+/// The system's monotonic clock.
+pub struct RealTime {
+    start: Instant,
+}
+
+impl RealTime {
+    pub fn new() -> RealTime {
+        RealTime { start: Instant::now() }
+    }
+}
+
+impl Default for RealTime {
+    fn default() -> RealTime {
+        RealTime::new()
+    }
+}
+
+impl TimeSource for RealTime {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// Time which only passes when told to, for deterministic tests. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualTime {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualTime {
+    pub fn new() -> ManualTime {
+        ManualTime::default()
+    }
+
+    pub fn advance(&self, d: Duration) -> () {
+        self.nanos.fetch_add(d.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TimeControl {
+    /// A fixed time for the whole game.
+    SuddenDeath(Duration),
+    /// The increment is added after every move.
+    Fischer { time: Duration, increment: Duration },
+    /// Once the main time is spent, every move must be made within a period, and each overrun
+    /// uses up one of the periods.
+    ByoYomi { time: Duration, period: Duration, periods: u32 },
+}
+
+/// E.g., "90s", "5m", or "1h".
+fn print_duration(d: Duration) -> String {
+    let s = d.as_secs();
+    if s >= 3600 && s.is_multiple_of(3600) {
+        format!("{}h", s / 3600)
+    } else if s >= 60 && s.is_multiple_of(60) {
+        format!("{}m", s / 60)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+fn parse_duration(s: &str) -> Option<Duration> {
+    let number = |n: &str, seconds: u64| n.parse::<u64>().ok()?.checked_mul(seconds).map(Duration::from_secs);
+    if let Some(n) = s.strip_suffix('h') {
+        number(n, 3600)
+    } else if let Some(n) = s.strip_suffix('m') {
+        number(n, 60)
+    } else {
+        number(s.strip_suffix('s')?, 1)
+    }
+}
+
+/// "5m" for sudden death, "5m+3s" for Fischer, and "10m/30sx5" for byo-yomi with 5 periods.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeControl::SuddenDeath(time) => write!(f, "{}", print_duration(*time)),
+            TimeControl::Fischer { time, increment } => {
+                write!(f, "{}+{}", print_duration(*time), print_duration(*increment))
+            }
+            TimeControl::ByoYomi { time, period, periods } => {
+                write!(f, "{}/{}x{}", print_duration(*time), print_duration(*period), periods)
+            }
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TimeControl, String> {
+        let invalid = || format!("Invalid time control '{}'", s);
+        let result = if let Some((time, increment)) = s.split_once('+') {
+            TimeControl::Fischer {
+                time: parse_duration(time).ok_or_else(invalid)?,
+                increment: parse_duration(increment).ok_or_else(invalid)?,
+            }
+        } else if let Some((time, byo_yomi)) = s.split_once('/') {
+            let (period, periods) = byo_yomi.split_once('x').ok_or_else(invalid)?;
+            TimeControl::ByoYomi {
+                time: parse_duration(time).ok_or_else(invalid)?,
+                period: parse_duration(period).ok_or_else(invalid)?,
+                periods: periods.parse().ok().filter(|p| *p > 0).ok_or_else(invalid)?,
+            }
+        } else {
+            TimeControl::SuddenDeath(parse_duration(s).ok_or_else(invalid)?)
+        };
+        Ok(result)
+    }
+}
+
+/// A player's time left.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct PlayerTime {
+    pub main: Duration,
+    /// Byo-yomi only: the periods left, including the current one, and the time left in it.
+    pub periods: u32,
+    pub period: Duration,
+}
+
+impl PlayerTime {
+    fn new(control: TimeControl) -> PlayerTime {
+        match control {
+            TimeControl::SuddenDeath(time) | TimeControl::Fischer { time, .. } => {
+                PlayerTime { main: time, periods: 0, period: Duration::ZERO }
+            }
+            TimeControl::ByoYomi { time, period, periods } => PlayerTime { main: time, periods, period },
+        }
+    }
+
+    /// The time left after spending elapsed in a single turn, [None] if it ran out.
+    fn spend(self, elapsed: Duration, control: TimeControl) -> Option<PlayerTime> {
+        let mut result = self;
+        if elapsed <= result.main {
+            result.main -= elapsed;
+            return Some(result);
+        }
+        let mut over = elapsed - result.main;
+        result.main = Duration::ZERO;
+        let full_period = match control {
+            TimeControl::ByoYomi { period, .. } => period,
+            _ => return None,
+        };
+        while over > result.period {
+            over -= result.period;
+            result.periods -= 1;
+            if result.periods == 0 {
+                return None;
+            }
+            result.period = full_period;
+        }
+        result.period -= over;
+        Some(result)
+    }
+
+    /// Applies the control's bonus at the end of a turn.
+    fn end_turn(self, control: TimeControl) -> PlayerTime {
+        let mut result = self;
+        match control {
+            TimeControl::Fischer { increment, .. } => result.main += increment,
+            TimeControl::ByoYomi { period, .. } if result.main.is_zero() => result.period = period,
+            _ => (),
+        }
+        result
+    }
+}
+
+/// E.g., "4:05.3", or "1:02:05.3" from an hour.
+pub fn print_time(d: Duration) -> String {
+    let tenths = d.as_millis() / 100;
+    let (h, m, s, t) = (tenths / 36000, tenths / 600 % 60, tenths / 10 % 60, tenths % 10);
+    if h > 0 {
+        format!("{}:{:02}:{:02}.{}", h, m, s, t)
+    } else {
+        format!("{}:{:02}.{}", m, s, t)
+    }
+}
+
+/// The main time, or the time left in the period and the periods left once in byo-yomi.
+impl fmt::Display for PlayerTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.main.is_zero() && self.periods > 0 {
+            write!(f, "{} ({}x)", print_time(self.period), self.periods)
+        } else {
+            write!(f, "{}", print_time(self.main))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Flagged(pub Owner);
+
+impl fmt::Display for Flagged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} lost on time, {:?} wins", self.0, self.0.other())
+    }
+}
+
+impl std::error::Error for Flagged {}
+
+/// Both players' clocks in a game, at most one of them running.
+pub struct GameClock {
+    control: TimeControl,
+    /// Indexed by [Owner::index], as of the start of the running player's turn.
+    times: [PlayerTime; 2],
+    /// The player whose clock runs, and when it started.
+    running: Option<(Owner, Duration)>,
+    flagged: Option<Owner>,
+    source: Box<dyn TimeSource>,
+}
+
+impl GameClock {
+    pub fn new(control: TimeControl, source: Box<dyn TimeSource>) -> GameClock {
+        let time = PlayerTime::new(control);
+        GameClock { control, times: [time, time], running: None, flagged: None, source }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    pub fn running(&self) -> Option<Owner> {
+        self.running.map(|(owner, _)| owner)
+    }
+
+    /// Stops the running clock, if any, without ending its player's turn.
+    pub fn stop(&mut self) -> () {
+        if let Some((owner, _)) = self.running {
+            match self.time(owner) {
+                Some(t) => self.times[owner.index()] = t,
+                None => self.flagged = Some(owner),
+            }
+            self.running = None;
+        }
+    }
+
+    /// Starts the owner's clock, stopping the other's.
+    pub fn start(&mut self, owner: Owner) -> () {
+        self.stop();
+        if self.flagged.is_none() {
+            self.running = Some((owner, self.source.now()));
+        }
+    }
+
+    /// Ends the running player's turn, and starts the other's clock. Fails if the running player
+    /// ran out of time, which stops the clocks for good.
+    pub fn press(&mut self) -> Result<(), Flagged> {
+        let owner = match self.running {
+            Some((owner, _)) => owner,
+            None => return self.flagged.map_or(Ok(()), |o| Err(Flagged(o))),
+        };
+        self.stop();
+        if let Some(o) = self.flagged {
+            return Err(Flagged(o));
+        }
+        self.times[owner.index()] = self.times[owner.index()].end_turn(self.control);
+        self.start(owner.other());
+        Ok(())
+    }
+
+    /// The owner's time left now, [None] if it ran out.
+    pub fn time(&self, owner: Owner) -> Option<PlayerTime> {
+        if self.flagged == Some(owner) {
+            return None;
+        }
+        let time = self.times[owner.index()];
+        match self.running {
+            Some((o, start)) if o == owner => time.spend(self.source.now() - start, self.control),
+            _ => Some(time),
+        }
+    }
+
+    /// The player who ran out of time, if any, even if its clock is still running.
+    pub fn flagged(&self) -> Option<Owner> {
+        self.flagged.or_else(|| Owner::ALL.iter().copied().find(|o| self.time(*o).is_none()))
+    }
+}
+
+/// E.g., "Player1 4:05.3* | Player2 5:00.0", where the running clock is starred.
+impl fmt::Display for GameClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clocks: Vec<String> = Owner::ALL
+            .iter()
+            .map(|o| {
+                let time = self.time(*o).map_or_else(|| "flagged".to_owned(), |t| t.to_string());
+                let star = if self.running() == Some(*o) { "*" } else { "" };
+                format!("{:?} {}{}", o, time, star)
+            })
+            .collect();
+        write!(f, "{}", clocks.join(" | "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn clock(control: &str) -> (GameClock, ManualTime) {
+        let time = ManualTime::new();
+        let mut clock = GameClock::new(control.parse().unwrap(), Box::new(time.clone()));
+        clock.start(Owner::Player1);
+        (clock, time)
+    }
+
+    #[test]
+    fn time_controls_round_trip() {
+        for text in ["5m", "90s", "1h+5s", "10m/30sx5"].iter() {
+            assert_eq!(*text, text.parse::<TimeControl>().unwrap().to_string());
+        }
+        assert_eq!(Ok(TimeControl::Fischer { time: secs(300), increment: secs(3) }), "5m+3s".parse());
+        assert!("5".parse::<TimeControl>().is_err());
+        assert!("5m/30sx0".parse::<TimeControl>().is_err());
+        assert!("5é".parse::<TimeControl>().is_err());
+        assert!("99999999999999999h".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn sudden_death_flags_when_time_runs_out() {
+        let (mut clock, time) = clock("1m");
+        time.advance(secs(20));
+        assert_eq!(Ok(()), clock.press());
+        time.advance(secs(5));
+        assert_eq!("Player1 0:40.0 | Player2 0:55.0*", clock.to_string());
+        assert_eq!(Ok(()), clock.press());
+        time.advance(secs(41));
+        assert_eq!(Some(Owner::Player1), clock.flagged());
+        assert_eq!(Err(Flagged(Owner::Player1)), clock.press());
+        assert_eq!(None, clock.running());
+        assert_eq!("Player1 flagged | Player2 0:55.0", clock.to_string());
+    }
+
+    #[test]
+    fn fischer_adds_the_increment_after_moves() {
+        let (mut clock, time) = clock("1m+10s");
+        time.advance(secs(30));
+        clock.press().unwrap();
+        assert_eq!(secs(40), clock.time(Owner::Player1).unwrap().main);
+        time.advance(secs(100));
+        assert_eq!(Err(Flagged(Owner::Player2)), clock.press());
+    }
+
+    #[test]
+    fn byo_yomi_uses_up_periods() {
+        let (mut clock, time) = clock("1m/10sx3");
+        // The main time, and one period and a half.
+        time.advance(secs(75));
+        assert_eq!("0:05.0 (2x)", clock.time(Owner::Player1).unwrap().to_string());
+        clock.press().unwrap();
+        assert_eq!("0:10.0 (2x)", clock.time(Owner::Player1).unwrap().to_string());
+        clock.press().unwrap();
+        time.advance(secs(10));
+        clock.press().unwrap();
+        assert_eq!(2, clock.time(Owner::Player1).unwrap().periods);
+        clock.press().unwrap();
+        time.advance(secs(21));
+        assert_eq!(Err(Flagged(Owner::Player1)), clock.press());
+    }
+
+    #[test]
+    fn stopped_clocks_dont_run() {
+        let (mut clock, time) = clock("1m");
+        clock.stop();
+        time.advance(secs(100));
+        assert_eq!(None, clock.flagged());
+        assert_eq!(Ok(()), clock.press());
+        assert_eq!(None, clock.running());
+    }
+
+    #[test]
+    fn print_time_formats_tenths() {
+        assert_eq!("4:05.3", print_time(Duration::from_millis(245_300)));
+        assert_eq!("1:02:05.0", print_time(secs(3725)));
+    }
+}
@@ -0,0 +1,345 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::common::coordinates::{Coordinates, Vector};
+use crate::game::board::GameBoard;
+use crate::game::moves::GameMove;
+use crate::game::state::GameState;
+use crate::game::token::{OwnedToken, Owner};
+
+/// A move as a player states it, which may be illegal, see [check].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Attempt {
+    /// Any action which relocates the token, i.e., a Move, Jump, Slide or JumpSlide.
+    Move { from: Coordinates, to: Coordinates },
+    Strike { from: Coordinates, to: Coordinates },
+    Command { commander: Coordinates, from: Coordinates, to: Coordinates },
+    Summon(Coordinates),
+}
+
+/// [crate::game::moves::Move] notation, where summons may leave out the unit, e.g., "@d2", since
+/// it's drawn anyway.
+impl FromStr for Attempt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Attempt, String> {
+        let invalid = || format!("Expected a move like 'c1-c2', 'c1xc3', 'c1:b1-b2' or '@d2', not '{}'", s);
+        let square = |t: &str| t.parse::<Coordinates>().map_err(|_| invalid());
+        if let Some((_, to)) = s.split_once('@') {
+            return Ok(Attempt::Summon(square(to)?));
+        }
+        if let Some((from, to)) = s.split_once('x') {
+            return Ok(Attempt::Strike { from: square(from)?, to: square(to)? });
+        }
+        let (from, to) = s.split_once('-').ok_or_else(invalid)?;
+        match from.split_once(':') {
+            Some((commander, from)) => {
+                Ok(Attempt::Command { commander: square(commander)?, from: square(from)?, to: square(to)? })
+            }
+            None => Ok(Attempt::Move { from: square(from)?, to: square(to)? }),
+        }
+    }
+}
+
+/// Why an [Attempt] is illegal.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IllegalMove {
+    NoToken(Coordinates),
+    NotYourToken(Coordinates),
+    /// The token's current [crate::game::token::TokenSide] has no such action.
+    NotOnTokenSide { from: Coordinates, to: Coordinates },
+    /// The first token in the way.
+    Obstructed { blocking: Coordinates },
+    FriendlyAtDestination(Coordinates),
+    LeavesDukeInGuard,
+    BagEmpty,
+    NotAdjacentToDuke(Coordinates),
+    /// Summons need an empty square.
+    Occupied(Coordinates),
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllegalMove::NoToken(c) => write!(f, "There's no token on {}", c),
+            IllegalMove::NotYourToken(c) => write!(f, "The token on {} isn't yours", c),
+            IllegalMove::NotOnTokenSide { from, to } => {
+                write!(f, "The token on {} has no action to {} on its current side", from, to)
+            }
+            IllegalMove::Obstructed { blocking } => write!(f, "The path is blocked by the token on {}", blocking),
+            IllegalMove::FriendlyAtDestination(c) => write!(f, "{} is taken by your own token", c),
+            IllegalMove::LeavesDukeInGuard => write!(f, "It would leave your Duke in guard"),
+            IllegalMove::BagEmpty => write!(f, "Your bag is empty"),
+            IllegalMove::NotAdjacentToDuke(c) => write!(f, "{} isn't next to your Duke", c),
+            IllegalMove::Occupied(c) => write!(f, "{} isn't empty", c),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+impl IllegalMove {
+    /// The variant's name, and the squares it names, for reports read by programs.
+    pub fn parts(&self) -> (&'static str, Vec<(&'static str, Coordinates)>) {
+        match *self {
+            IllegalMove::NoToken(c) => ("NoToken", vec![("square", c)]),
+            IllegalMove::NotYourToken(c) => ("NotYourToken", vec![("square", c)]),
+            IllegalMove::NotOnTokenSide { from, to } => ("NotOnTokenSide", vec![("from", from), ("to", to)]),
+            IllegalMove::Obstructed { blocking } => ("Obstructed", vec![("blocking", blocking)]),
+            IllegalMove::FriendlyAtDestination(c) => ("FriendlyAtDestination", vec![("square", c)]),
+            IllegalMove::LeavesDukeInGuard => ("LeavesDukeInGuard", Vec::new()),
+            IllegalMove::BagEmpty => ("BagEmpty", Vec::new()),
+            IllegalMove::NotAdjacentToDuke(c) => ("NotAdjacentToDuke", vec![("square", c)]),
+            IllegalMove::Occupied(c) => ("Occupied", vec![("square", c)]),
+        }
+    }
+}
+
+/// The token on c, which must belong to the player to move.
+fn own_token(gs: &GameState, c: Coordinates) -> Result<&OwnedToken, IllegalMove> {
+    let token = gs.board.try_get(c).ok().flatten().ok_or(IllegalMove::NoToken(c))?;
+    if token.owner == gs.current_player_turn {
+        Ok(token)
+    } else {
+        Err(IllegalMove::NotYourToken(c))
+    }
+}
+
+fn owner_at(gs: &GameState, c: Coordinates) -> Option<Owner> {
+    gs.board.try_get(c).ok().flatten().map(|t| t.owner)
+}
+
+fn first_occupied(gs: &GameState, path: impl IntoIterator<Item=Coordinates>) -> Option<Coordinates> {
+    path.into_iter().find(|c| gs.board.try_get(*c).ok().flatten().is_some())
+}
+
+fn guard_check(gs: &GameState, m: &GameMove) -> Result<(), IllegalMove> {
+    let mut next = gs.board.clone();
+    next.make_move(m);
+    if next.is_in_guard(gs.current_player_turn) {
+        Err(IllegalMove::LeavesDukeInGuard)
+    } else {
+        Ok(())
+    }
+}
+
+/// The squares a relocating action from `from` passes over to reach `to`, for every action of the
+/// token's current side which could; [None] if there's no such action.
+fn paths(token: &OwnedToken, from: Coordinates, to: Coordinates) -> Option<Vec<Vec<Coordinates>>> {
+    let table = token.action_table();
+    let v = from.vector_to(to);
+    let along = |first: &Vector| v.is_linear() && v.direction() == first.direction() && v.length() >= first.length();
+    let mut result = Vec::new();
+    if table.moves.contains(&v) {
+        result.push(from.linear_path_to(to));
+    }
+    if table.jumps.contains(&v) {
+        result.push(Vec::new());
+    }
+    if table.slides.iter().any(along) {
+        result.push(from.linear_path_to(to));
+    }
+    // A jump slide passes over nothing until its first square.
+    for start in table.jump_slides.iter().filter(|j| along(j)).filter_map(|j| from + *j) {
+        let mut path = Vec::new();
+        if start != to {
+            path.push(start);
+            path.extend(start.linear_path_to(to));
+        }
+        result.push(path);
+    }
+    Some(result).filter(|r| !r.is_empty())
+}
+
+fn check_move(gs: &GameState, from: Coordinates, to: Coordinates) -> Result<(), IllegalMove> {
+    let token = own_token(gs, from)?;
+    if gs.board.try_get(to).is_err() {
+        return Err(IllegalMove::NotOnTokenSide { from, to });
+    }
+    if let Some(m) = gs.board.moves_from(from).iter().find(|m| matches!(m, GameMove::Move { dst, .. } if *dst == to)) {
+        return guard_check(gs, m);
+    }
+    let paths = paths(token, from, to).ok_or(IllegalMove::NotOnTokenSide { from, to })?;
+    if owner_at(gs, to) == Some(token.owner) {
+        return Err(IllegalMove::FriendlyAtDestination(to));
+    }
+    let blocking = paths.into_iter().filter_map(|p| first_occupied(gs, p)).next();
+    Err(blocking.map_or(IllegalMove::NotOnTokenSide { from, to }, |blocking| IllegalMove::Obstructed { blocking }))
+}
+
+fn check_strike(gs: &GameState, from: Coordinates, to: Coordinates) -> Result<(), IllegalMove> {
+    let token = own_token(gs, from)?;
+    if !token.action_table().strikes.contains(&from.vector_to(to)) {
+        return Err(IllegalMove::NotOnTokenSide { from, to });
+    }
+    match owner_at(gs, to) {
+        None => Err(IllegalMove::NoToken(to)),
+        Some(owner) if owner == token.owner => Err(IllegalMove::FriendlyAtDestination(to)),
+        Some(_) => guard_check(gs, &GameMove::Strike { src: from, dst: to }),
+    }
+}
+
+fn check_command(gs: &GameState, commander: Coordinates, from: Coordinates, to: Coordinates) -> Result<(), IllegalMove> {
+    let token = own_token(gs, commander)?;
+    let commands = &token.action_table().commands;
+    for c in [from, to].iter() {
+        if !commands.contains(&commander.vector_to(*c)) || gs.board.try_get(*c).is_err() {
+            return Err(IllegalMove::NotOnTokenSide { from: commander, to: *c });
+        }
+    }
+    own_token(gs, from)?;
+    if owner_at(gs, to) == Some(token.owner) {
+        return Err(IllegalMove::FriendlyAtDestination(to));
+    }
+    guard_check(gs, &GameMove::Command { src: commander, from, to })
+}
+
+fn check_summon(gs: &GameState, c: Coordinates) -> Result<(), IllegalMove> {
+    let owner = gs.current_player_turn;
+    let token = gs.bag(owner).remaining().first().ok_or(IllegalMove::BagEmpty)?;
+    let adjacent = gs.board.duke_coordinates(owner).is_some_and(|duke| {
+        let v = duke.vector_to(c);
+        v.x.abs() + v.y.abs() == 1
+    });
+    if !adjacent || gs.board.try_get(c).is_err() {
+        return Err(IllegalMove::NotAdjacentToDuke(c));
+    }
+    if gs.board.get(c).is_some() {
+        return Err(IllegalMove::Occupied(c));
+    }
+    let mut next: GameBoard = gs.board.clone();
+    next.place(c, OwnedToken { token: token.clone(), owner });
+    if next.is_in_guard(owner) {
+        Err(IllegalMove::LeavesDukeInGuard)
+    } else {
+        Ok(())
+    }
+}
+
+/// Ok if the player to move may make the attempt, otherwise the first reason it's illegal.
+pub fn check(gs: &GameState, attempt: &Attempt) -> Result<(), IllegalMove> {
+    match *attempt {
+        Attempt::Move { from, to } => check_move(gs, from, to),
+        Attempt::Strike { from, to } => check_strike(gs, from, to),
+        Attempt::Command { commander, from, to } => check_command(gs, commander, from, to),
+        Attempt::Summon(c) => check_summon(gs, c),
+    }
+}
+
+/// Player 1's Duke on a1 and Player 2's on f6, with a flipped Player 2 Footman on a3, which
+/// threatens a1 unless a2 is taken. Player 1 has a Footman left in the bag. For tests.
+#[cfg(test)]
+pub(crate) fn guarded() -> GameState {
+    use crate::game::units;
+
+    let mut gs = crate::game::state::initial(1);
+    gs.board = GameBoard::empty();
+    gs.board.place(Coordinates { x: 0, y: 0 }, units::duke(Owner::Player1));
+    gs.board.place(Coordinates { x: 5, y: 5 }, units::duke(Owner::Player2));
+    let mut footman = units::footman(Owner::Player2);
+    footman.token.flip();
+    gs.board.place(Coordinates { x: 0, y: 2 }, footman);
+    gs
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::state::initial;
+    use crate::game::units;
+
+    use super::*;
+
+    fn square(s: &str) -> Coordinates {
+        s.parse().unwrap()
+    }
+
+    fn check_text(gs: &GameState, text: &str) -> Result<(), IllegalMove> {
+        check(gs, &text.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_attempts() {
+        assert_eq!(Ok(Attempt::Move { from: square("c1"), to: square("c2") }), "c1-c2".parse());
+        assert_eq!(Ok(Attempt::Strike { from: square("c1"), to: square("c3") }), "c1xc3".parse());
+        assert_eq!(
+            Ok(Attempt::Command { commander: square("c1"), from: square("b1"), to: square("b2") }),
+            "c1:b1-b2".parse(),
+        );
+        assert_eq!(Ok(Attempt::Summon(square("d2"))), "@d2".parse());
+        assert_eq!(Ok(Attempt::Summon(square("d2"))), "Footman@d2".parse());
+        assert!("c1c2".parse::<Attempt>().is_err());
+        assert!("c1-".parse::<Attempt>().is_err());
+    }
+
+    #[test]
+    fn legal_attempts_pass() {
+        let gs = initial(1);
+        assert_eq!(Ok(()), check_text(&gs, "c1-c2"));
+        assert_eq!(Ok(()), check_text(&gs, "@d2"));
+    }
+
+    #[test]
+    fn explains_token_errors() {
+        let gs = initial(1);
+        assert_eq!(Err(IllegalMove::NoToken(square("c3"))), check_text(&gs, "c3-c4"));
+        assert_eq!(Err(IllegalMove::NoToken(square("g1"))), check_text(&gs, "g1-g2"));
+        assert_eq!(Err(IllegalMove::NotYourToken(square("c6"))), check_text(&gs, "c6-c5"));
+        assert_eq!(
+            Err(IllegalMove::NotOnTokenSide { from: square("c1"), to: square("c3") }),
+            check_text(&gs, "c1-c3"),
+        );
+        assert_eq!(
+            Err(IllegalMove::NotOnTokenSide { from: square("c1"), to: square("c2") }),
+            check_text(&gs, "c1xc2"),
+        );
+        assert_eq!(Err(IllegalMove::FriendlyAtDestination(square("d1"))), check_text(&gs, "c1-d1"));
+    }
+
+    #[test]
+    fn explains_obstructions() {
+        let mut gs = guarded();
+        let mut footman = units::footman(Owner::Player1);
+        footman.token.flip();
+        gs.board.place(square("a2"), units::footman(Owner::Player1));
+        gs.board.place(square("c1"), footman);
+        gs.board.place(square("c2"), units::footman(Owner::Player2));
+        assert_eq!(Err(IllegalMove::Obstructed { blocking: square("c2") }), check_text(&gs, "c1-c3"));
+        assert_eq!(Ok(()), check_text(&gs, "c1-b2"));
+    }
+
+    #[test]
+    fn refuses_targets_off_the_board() {
+        let gs = guarded();
+        assert_eq!(
+            Err(IllegalMove::NotOnTokenSide { from: square("a1"), to: square("z1") }),
+            check_text(&gs, "a1-z1"),
+        );
+    }
+
+    #[test]
+    fn explains_guard_and_summon_errors() {
+        let mut gs = guarded();
+        gs.board.place(square("f1"), units::footman(Owner::Player1));
+        assert_eq!(Err(IllegalMove::LeavesDukeInGuard), check_text(&gs, "f1-f2"));
+        assert_eq!(Ok(()), check_text(&gs, "@a2"));
+        assert_eq!(Err(IllegalMove::LeavesDukeInGuard), check_text(&gs, "@b1"));
+        assert_eq!(Err(IllegalMove::NotAdjacentToDuke(square("b2"))), check_text(&gs, "@b2"));
+        let gs = initial(1);
+        assert_eq!(Err(IllegalMove::Occupied(square("c1"))), check_text(&gs, "@c1"));
+        let gs = initial(0);
+        assert_eq!(Err(IllegalMove::BagEmpty), check_text(&gs, "@d2"));
+    }
+
+    #[test]
+    fn agrees_with_legal_moves() {
+        let gs = initial(1);
+        for m in gs.board.legal_moves(Owner::Player1) {
+            let attempt = match m {
+                GameMove::Move { src, dst, .. } => Attempt::Move { from: src, to: dst },
+                GameMove::Strike { src, dst } => Attempt::Strike { from: src, to: dst },
+                GameMove::Command { src, from, to } => Attempt::Command { commander: src, from, to },
+            };
+            assert_eq!(Ok(()), check(&gs, &attempt));
+        }
+    }
+}
@@ -1,5 +1,14 @@
+pub mod actions;
+pub mod bitboard;
 pub mod board;
+pub mod clock;
+pub mod legality;
+pub mod moves;
 pub mod offset;
+pub mod position;
+pub mod puzzle;
+pub mod record;
 pub mod state;
 pub mod token;
+pub mod tree;
 pub mod units;
@@ -0,0 +1,356 @@
+use std::fmt;
+
+use crate::common::coordinates::Coordinates;
+use crate::game::bitboard::{square_at, square_index};
+use crate::game::token::TokenAction;
+use crate::game::units::UnitId;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GameMove {
+    /// The token at src relocates to dst, capturing the enemy token there, if any. The action is
+    /// one of Move, Jump, Slide or JumpSlide.
+    Move { src: Coordinates, dst: Coordinates, action: TokenAction },
+    /// The token at src captures the enemy token at dst without moving.
+    Strike { src: Coordinates, dst: Coordinates },
+    /// The token at src moves the friendly token at from to to, capturing the enemy token there,
+    /// if any.
+    Command { src: Coordinates, from: Coordinates, to: Coordinates },
+}
+
+impl GameMove {
+    /// The token performing the action.
+    pub fn src(&self) -> Coordinates {
+        match self {
+            GameMove::Move { src, .. } => *src,
+            GameMove::Strike { src, .. } => *src,
+            GameMove::Command { src, .. } => *src,
+        }
+    }
+    /// The square being moved to, struck, or commanded to.
+    pub fn target(&self) -> Coordinates {
+        match self {
+            GameMove::Move { dst, .. } => *dst,
+            GameMove::Strike { dst, .. } => *dst,
+            GameMove::Command { to, .. } => *to,
+        }
+    }
+    pub fn action(&self) -> TokenAction {
+        match self {
+            GameMove::Move { action, .. } => *action,
+            GameMove::Strike { .. } => TokenAction::Strike,
+            GameMove::Command { .. } => TokenAction::Command,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MoveKind {
+    Move,
+    Jump,
+    Slide,
+    JumpSlide,
+    Strike,
+    Command,
+    /// Places a new token from the bag next to the Duke.
+    Summon,
+}
+
+impl MoveKind {
+    const ALL: [MoveKind; 7] = [
+        MoveKind::Move,
+        MoveKind::Jump,
+        MoveKind::Slide,
+        MoveKind::JumpSlide,
+        MoveKind::Strike,
+        MoveKind::Command,
+        MoveKind::Summon,
+    ];
+
+    /// [None] for Summon, which isn't a token's action.
+    pub fn action(&self) -> Option<TokenAction> {
+        match self {
+            MoveKind::Move => Some(TokenAction::Move),
+            MoveKind::Jump => Some(TokenAction::Jump),
+            MoveKind::Slide => Some(TokenAction::Slide),
+            MoveKind::JumpSlide => Some(TokenAction::JumpSlide),
+            MoveKind::Strike => Some(TokenAction::Strike),
+            MoveKind::Command => Some(TokenAction::Command),
+            MoveKind::Summon => None,
+        }
+    }
+}
+
+impl From<TokenAction> for MoveKind {
+    fn from(a: TokenAction) -> MoveKind {
+        match a {
+            TokenAction::Move => MoveKind::Move,
+            TokenAction::Jump => MoveKind::Jump,
+            TokenAction::Slide => MoveKind::Slide,
+            TokenAction::JumpSlide => MoveKind::JumpSlide,
+            TokenAction::Strike => MoveKind::Strike,
+            TokenAction::Command => MoveKind::Command,
+        }
+    }
+}
+
+/// A move packed into 32 bits, with squares indexed as in [crate::game::bitboard]:
+/// * bits 0-5: the acting token's square, or the commanded token's square for Command.
+/// * bits 6-11: the destination, struck square, or summoning square.
+/// * bits 12-14: the [MoveKind].
+/// * bits 15-20: the commanding token's square, for Command.
+/// * bits 21-24: the summoned unit's [UnitId::index], for Summon.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Move(u32);
+
+impl Move {
+    const SQUARE_MASK: u32 = 0x3F;
+
+    fn pack(from: usize, to: usize, kind: MoveKind, commander: usize, unit: usize) -> Move {
+        Move(from as u32
+            | (to as u32) << 6
+            | (kind as u32) << 12
+            | (commander as u32) << 15
+            | (unit as u32) << 21)
+    }
+    /// Panics if kind is Command or Summon, use [Move::command] or [Move::summon] instead.
+    pub fn new(from: usize, to: usize, kind: MoveKind) -> Move {
+        assert!(kind != MoveKind::Command && kind != MoveKind::Summon, "Use dedicated constructors for {:?}", kind);
+        Move::pack(from, to, kind, 0, 0)
+    }
+    pub fn command(commander: usize, from: usize, to: usize) -> Move {
+        Move::pack(from, to, MoveKind::Command, commander, 0)
+    }
+    pub fn summon(to: usize, unit: UnitId) -> Move {
+        Move::pack(0, to, MoveKind::Summon, 0, unit.index())
+    }
+
+    pub fn from(&self) -> usize {
+        (self.0 & Move::SQUARE_MASK) as usize
+    }
+    pub fn to(&self) -> usize {
+        (self.0 >> 6 & Move::SQUARE_MASK) as usize
+    }
+    pub fn kind(&self) -> MoveKind {
+        MoveKind::ALL[(self.0 >> 12 & 0x7) as usize]
+    }
+    /// The token performing the action, or [None] for Summon.
+    pub fn actor(&self) -> Option<usize> {
+        match self.kind() {
+            MoveKind::Summon => None,
+            MoveKind::Command => Some((self.0 >> 15 & Move::SQUARE_MASK) as usize),
+            _ => Some(self.from()),
+        }
+    }
+    pub fn unit(&self) -> Option<UnitId> {
+        if self.kind() == MoveKind::Summon {
+            Some(UnitId::ALL[(self.0 >> 21 & 0xF) as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The packed representation, e.g., for storing in tables.
+    pub fn to_bits(self) -> u32 {
+        self.0
+    }
+    /// The inverse of [Move::to_bits]; the bits aren't validated.
+    pub fn from_bits(bits: u32) -> Move {
+        Move(bits)
+    }
+
+    /// [None] for Summon.
+    pub fn to_game_move(self) -> Option<GameMove> {
+        let from = square_at(self.from());
+        let to = square_at(self.to());
+        match self.kind() {
+            MoveKind::Summon => None,
+            MoveKind::Strike => Some(GameMove::Strike { src: from, dst: to }),
+            MoveKind::Command =>
+                Some(GameMove::Command { src: square_at(self.actor().unwrap()), from, to }),
+            kind => Some(GameMove::Move { src: from, dst: to, action: kind.action().unwrap() }),
+        }
+    }
+}
+
+impl From<GameMove> for Move {
+    fn from(m: GameMove) -> Move {
+        match m {
+            GameMove::Move { src, dst, action } =>
+                Move::new(square_index(src), square_index(dst), action.into()),
+            GameMove::Strike { src, dst } =>
+                Move::new(square_index(src), square_index(dst), MoveKind::Strike),
+            GameMove::Command { src, from, to } =>
+                Move::command(square_index(src), square_index(from), square_index(to)),
+        }
+    }
+}
+
+/// Notation: "b1-b2" for moving tokens, "b1xb3" for strikes, "c1:b1-b2" for commands, and
+/// "Footman@c2" for summons.
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let from = square_at(self.from());
+        let to = square_at(self.to());
+        match self.kind() {
+            MoveKind::Summon => write!(f, "{}@{}", self.unit().unwrap().name(), to),
+            MoveKind::Strike => write!(f, "{}x{}", from, to),
+            MoveKind::Command => write!(f, "{}:{}-{}", square_at(self.actor().unwrap()), from, to),
+            _ => write!(f, "{}-{}", from, to),
+        }
+    }
+}
+
+/// An upper bound on the number of moves in a single position.
+pub const MAX_MOVES: usize = 256;
+
+/// A fixed capacity buffer of moves, so move generation doesn't allocate on the heap.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList { moves: [Move::default(); MAX_MOVES], len: 0 }
+    }
+    /// Panics if the list is already at [MAX_MOVES].
+    pub fn push(&mut self, m: Move) -> () {
+        debug_assert!(self.len < MAX_MOVES, "No position has more than MAX_MOVES ({}) moves", MAX_MOVES);
+        self.moves[self.len] = m;
+        self.len += 1;
+    }
+    pub fn clear(&mut self) -> () {
+        self.len = 0;
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Move> {
+        self.as_slice().iter()
+    }
+    /// Keeps only the moves satisfying p, preserving their order.
+    pub fn retain<P: FnMut(Move) -> bool>(&mut self, mut p: P) -> () {
+        let mut kept = 0;
+        for i in 0..self.len {
+            let m = self.moves[i];
+            if p(m) {
+                self.moves[kept] = m;
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn move_fits_in_32_bits() {
+        assert_eq!(4, std::mem::size_of::<Move>());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "No position has more than MAX_MOVES")]
+    fn move_list_is_bounded() {
+        let mut list = MoveList::new();
+        for _ in 0..=MAX_MOVES {
+            list.push(Move::default());
+        }
+    }
+
+    #[test]
+    fn move_round_trip() {
+        let m = Move::new(7, 35, MoveKind::JumpSlide);
+        assert_eq!(7, m.from());
+        assert_eq!(35, m.to());
+        assert_eq!(MoveKind::JumpSlide, m.kind());
+        assert_eq!(Some(7), m.actor());
+        assert_eq!(None, m.unit());
+    }
+
+    #[test]
+    fn command_round_trip() {
+        let m = Move::command(14, 13, 20);
+        assert_eq!(13, m.from());
+        assert_eq!(20, m.to());
+        assert_eq!(MoveKind::Command, m.kind());
+        assert_eq!(Some(14), m.actor());
+    }
+
+    #[test]
+    fn summon_round_trip() {
+        let m = Move::summon(8, UnitId::Footman);
+        assert_eq!(8, m.to());
+        assert_eq!(MoveKind::Summon, m.kind());
+        assert_eq!(Some(UnitId::Footman), m.unit());
+        assert_eq!(None, m.actor());
+        assert_eq!(None, m.to_game_move());
+    }
+
+    #[test]
+    fn game_move_round_trip() {
+        let moves = vec![
+            GameMove::Move {
+                src: Coordinates { x: 1, y: 2 },
+                dst: Coordinates { x: 5, y: 2 },
+                action: TokenAction::Slide,
+            },
+            GameMove::Strike { src: Coordinates { x: 0, y: 0 }, dst: Coordinates { x: 5, y: 5 } },
+            GameMove::Command {
+                src: Coordinates { x: 2, y: 2 },
+                from: Coordinates { x: 1, y: 2 },
+                to: Coordinates { x: 3, y: 2 },
+            },
+        ];
+        for m in moves {
+            let packed: Move = m.into();
+            assert_eq!(Some(m), packed.to_game_move());
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("b1-b3", Move::new(1, 13, MoveKind::Move).to_string());
+        assert_eq!("a1xf6", Move::new(0, 35, MoveKind::Strike).to_string());
+        assert_eq!("c1:b1-b2", Move::command(2, 1, 7).to_string());
+        assert_eq!("Footman@c2", Move::summon(8, UnitId::Footman).to_string());
+    }
+
+    #[test]
+    fn move_list_push_and_retain() {
+        let mut list = MoveList::new();
+        assert!(list.is_empty());
+        for i in 0..10 {
+            list.push(Move::new(i, i + 1, MoveKind::Move));
+        }
+        list.retain(|m| m.from() % 2 == 0);
+        assert_eq!(vec![0, 2, 4, 6, 8], list.iter().map(|m| m.from()).collect::<Vec<_>>());
+        list.clear();
+        assert_eq!(0, list.len());
+    }
+}
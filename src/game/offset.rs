@@ -102,6 +102,16 @@ impl From<Offsets> for coordinates::Coordinates {
     }
 }
 
+/// The displacement from the center of the token, e.g., FarLeft, Top => (-2, 1).
+impl From<Offsets> for coordinates::Vector {
+    fn from(other: Offsets) -> coordinates::Vector {
+        coordinates::Vector {
+            x: other.x.to_index() as i16 - 2,
+            y: 2 - other.y.to_index() as i16,
+        }
+    }
+}
+
 pub trait Centerable {
     fn center(&self) -> Offsets;
     fn distance_from_center(&self) -> u16;
@@ -113,7 +123,7 @@ pub trait Centerable {
 impl Centerable for HorizontalOffset {
     fn center(&self) -> Offsets {
         Offsets {
-            x: self.clone(),
+            x: *self,
             y: VerticalOffset::Center,
         }
     }
@@ -133,7 +143,7 @@ impl Centerable for VerticalOffset {
     fn center(&self) -> Offsets {
         Offsets {
             x: HorizontalOffset::Center,
-            y: self.clone(),
+            y: *self,
         }
     }
 
@@ -200,6 +210,7 @@ impl Indexable for VerticalOffset {
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -212,6 +223,14 @@ mod test {
         )
     }
 
+    #[test]
+    fn offsets_to_vector() {
+        assert_eq!(
+            coordinates::Vector::new(-2, -1),
+            Offsets::new(HorizontalOffset::FarLeft, VerticalOffset::Bottom).into(),
+        )
+    }
+
     #[test]
     fn offsets_to_coordinates() {
         let os = Offsets::new(HorizontalOffset::FarLeft, VerticalOffset::Center);
@@ -0,0 +1,456 @@
+use std::sync::OnceLock;
+
+use crate::common::coordinates::Coordinates;
+use crate::game::bitboard::{square_at, squares, BitBoard, SQUARES};
+use crate::game::board::GameBoard;
+use crate::game::moves::{Move, MoveKind, MoveList};
+use crate::game::state::GameState;
+use crate::game::token::{CurrentSide, DiscardBag, Owner, TokenBag};
+use crate::game::units::UnitId;
+
+/// A compact, copyable snapshot of a [GameState] for search. Bags are tracked by unit counts,
+/// since their order is random anyway, and discard piles don't affect play so they're dropped.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Position {
+    pub board: BitBoard,
+    pub to_move: Owner,
+    /// Indexed by owner, then unit.
+    bags: [[u8; UnitId::COUNT]; 2],
+}
+
+/// A deterministic PRNG (splitmix64), so hashes are stable across runs and can be persisted.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// The maximum number of a single unit in a bag which is hashed distinctly.
+const MAX_HASHED_BAG_COUNT: usize = 16;
+
+struct ZobristKeys {
+    /// Indexed by owner, unit, side, then square.
+    tokens: [[[[u64; SQUARES]; 2]; UnitId::COUNT]; 2],
+    /// Indexed by owner, unit, then count.
+    bags: [[[u64; MAX_HASHED_BAG_COUNT]; UnitId::COUNT]; 2],
+    player_2_to_move: u64,
+}
+
+fn zobrist_keys() -> &'static ZobristKeys {
+    static KEYS: OnceLock<ZobristKeys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut state = 0x0D0C_E5EE_D000_0001;
+        let mut keys = ZobristKeys {
+            tokens: [[[[0; SQUARES]; 2]; UnitId::COUNT]; 2],
+            bags: [[[0; MAX_HASHED_BAG_COUNT]; UnitId::COUNT]; 2],
+            player_2_to_move: 0,
+        };
+        keys.tokens.iter_mut().flatten().flatten().flatten().for_each(|k| *k = splitmix64(&mut state));
+        keys.bags.iter_mut().flatten().flatten().for_each(|k| *k = splitmix64(&mut state));
+        keys.player_2_to_move = splitmix64(&mut state);
+        keys
+    })
+}
+
+fn bag_counts(bag: &TokenBag) -> Option<[u8; UnitId::COUNT]> {
+    let mut result = [0u8; UnitId::COUNT];
+    for t in bag.remaining() {
+        let count = &mut result[t.unit()?.index()];
+        *count = count.checked_add(1)?;
+    }
+    Some(result)
+}
+
+impl Position {
+    pub fn new(board: BitBoard, to_move: Owner, bags: [[u8; UnitId::COUNT]; 2]) -> Position {
+        Position { board, to_move, bags }
+    }
+
+    /// [None] if the state has tokens, on the board or in the bags, which aren't standard units, or
+    /// more than 255 of a unit in a bag.
+    pub fn from_state(gs: &GameState) -> Option<Position> {
+        Some(Position {
+            board: BitBoard::from_game_board(&gs.board)?,
+            to_move: gs.current_player_turn,
+            bags: [bag_counts(&gs.player_1_bag)?, bag_counts(&gs.player_2_bag)?],
+        })
+    }
+
+    /// The inverse of [Position::from_state]; discard piles are empty.
+    pub fn to_state(&self) -> GameState {
+        let bag = |owner: Owner| {
+            let counts = self.bag(owner);
+            TokenBag::new(
+                UnitId::ALL
+                    .iter()
+                    .flat_map(|u| (0..counts[u.index()]).map(move |_| u.token(owner).token))
+                    .collect(),
+            )
+        };
+        GameState {
+            board: self.board.to_game_board(),
+            current_player_turn: self.to_move,
+            player_1_bag: bag(Owner::Player1),
+            player_1_discard: DiscardBag::empty(),
+            player_2_bag: bag(Owner::Player2),
+            player_2_discard: DiscardBag::empty(),
+        }
+    }
+
+    /// The number of each unit, by [UnitId::index], left in owner's bag.
+    pub fn bag(&self, owner: Owner) -> &[u8; UnitId::COUNT] {
+        &self.bags[owner.index()]
+    }
+
+    pub fn bag_size(&self, owner: Owner) -> u32 {
+        self.bag(owner).iter().map(|c| u32::from(*c)).sum()
+    }
+
+    /// All the moves of the player to move, including summons, without checking for guard.
+    pub fn pseudo_legal_moves(&self, moves: &mut MoveList) -> () {
+        self.board.generate(self.to_move, moves);
+        let summoning_squares = self.board.summoning_squares(self.to_move);
+        for unit in UnitId::ALL.iter() {
+            if self.bag(self.to_move)[unit.index()] > 0 {
+                for to in squares(summoning_squares) {
+                    moves.push(Move::summon(to, *unit));
+                }
+            }
+        }
+    }
+
+    /// All the moves of the player to move which don't leave its Duke in guard.
+    pub fn legal_moves(&self, moves: &mut MoveList) -> () {
+        self.pseudo_legal_moves(moves);
+        moves.retain(|m| !self.make(m).board.is_in_guard(self.to_move));
+    }
+
+    pub fn is_legal(&self, m: Move) -> bool {
+        let mut moves = MoveList::new();
+        self.legal_moves(&mut moves);
+        moves.iter().any(|e| *e == m)
+    }
+
+    /// The legal move written as text, see [Move]'s Display.
+    pub fn parse_move(&self, text: &str) -> Option<Move> {
+        let mut moves = MoveList::new();
+        self.legal_moves(&mut moves);
+        moves.iter().find(|m| m.to_string() == text).copied()
+    }
+
+    /// Summons differing only by the unit are the same choice, since the unit is drawn at random.
+    /// Summons are mapped to the first unit the player to move can draw, other moves are unchanged.
+    pub fn canonical(&self, m: Move) -> Move {
+        let first = UnitId::ALL.iter().find(|u| self.bag(self.to_move)[u.index()] > 0);
+        match (m.kind(), first) {
+            (MoveKind::Summon, Some(unit)) => Move::summon(m.to(), *unit),
+            _ => m,
+        }
+    }
+
+    /// Returns the position after m, which is assumed to be (pseudo) legal. The acting token is
+    /// flipped, except for the Duke when summoning.
+    pub fn make(&self, m: Move) -> Position {
+        let mut next = *self;
+        let board = &mut next.board;
+        let to = square_at(m.to());
+        match m.kind() {
+            MoveKind::Summon => {
+                let unit = m.unit().unwrap();
+                board.put(to, self.to_move, unit, CurrentSide::Initial);
+                next.bags[self.to_move.index()][unit.index()] -= 1;
+            }
+            MoveKind::Strike => {
+                board.remove(to);
+                board.flip(square_at(m.from()));
+            }
+            MoveKind::Command => {
+                let from = square_at(m.from());
+                let (owner, unit, side) = board.get(from).unwrap();
+                board.remove(from);
+                board.put(to, owner, unit, side);
+                board.flip(square_at(m.actor().unwrap()));
+            }
+            _ => {
+                let from = square_at(m.from());
+                let (owner, unit, side) = board.get(from).unwrap();
+                board.remove(from);
+                board.put(to, owner, unit, side.flip());
+            }
+        }
+        next.to_move = self.to_move.other();
+        next
+    }
+
+    /// True if the move captures an enemy token.
+    pub fn is_capture(&self, m: Move) -> bool {
+        m.kind() != MoveKind::Summon && self.board.owned_by(self.to_move.other()) & (1 << m.to()) != 0
+    }
+
+    pub fn is_in_guard(&self) -> bool {
+        self.board.is_in_guard(self.to_move)
+    }
+
+    /// A Zobrist hash of the position, stable across runs.
+    pub fn zobrist(&self) -> u64 {
+        let keys = zobrist_keys();
+        let mut result = match self.to_move {
+            Owner::Player1 => 0,
+            Owner::Player2 => keys.player_2_to_move,
+        };
+        for owner in Owner::ALL.iter() {
+            for unit in UnitId::ALL.iter() {
+                for side in CurrentSide::ALL.iter() {
+                    let bits = self.board.unit_side(*unit, *side) & self.board.owned_by(*owner);
+                    for i in squares(bits) {
+                        result ^= keys.tokens[owner.index()][unit.index()][side.index()][i];
+                    }
+                }
+                let count = usize::from(self.bag(*owner)[unit.index()]);
+                result ^= keys.bags[owner.index()][unit.index()][count.min(MAX_HASHED_BAG_COUNT - 1)];
+            }
+        }
+        result
+    }
+
+    /// The number of leaf nodes in the legal move tree of the given depth.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut moves = MoveList::new();
+        self.legal_moves(&mut moves);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves.iter().map(|m| self.make(*m).perft(depth - 1)).sum()
+    }
+
+    /// A one line summary in the spirit of chess's FEN: the ranks from the sixth down, separated by
+    /// '/', then the player to move, then each player's bag, e.g.,
+    /// "1fdf2/6/6/6/6/2FDF1 1 FF ff" for the initial position with two Footmen in each bag.
+    /// Tokens are their unit's initial, in upper case for Player 1 and followed by "'" once
+    /// flipped, runs of empty squares are counted, and empty bags are "-".
+    pub fn fen(&self) -> String {
+        let size = GameBoard::BOARD_SIZE;
+        let ranks: Vec<String> = (0..size)
+            .rev()
+            .map(|y| {
+                let mut rank = String::new();
+                let mut empty = 0;
+                for x in 0..size {
+                    match self.board.get(Coordinates { x, y }) {
+                        None => empty += 1,
+                        Some((owner, unit, side)) => {
+                            if empty > 0 {
+                                rank.push_str(&empty.to_string());
+                                empty = 0;
+                            }
+                            rank.push_str(&fen_token(owner, unit, side));
+                        }
+                    }
+                }
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                }
+                rank
+            })
+            .collect();
+        let bags: Vec<String> = Owner::ALL
+            .iter()
+            .map(|&owner| {
+                let counts = self.bag(owner);
+                let bag: String = UnitId::ALL
+                    .iter()
+                    .flat_map(|u| (0..counts[u.index()]).map(move |_| fen_token(owner, *u, CurrentSide::Initial)))
+                    .collect();
+                if bag.is_empty() { "-".to_owned() } else { bag }
+            })
+            .collect();
+        let to_move = match self.to_move {
+            Owner::Player1 => 1,
+            Owner::Player2 => 2,
+        };
+        format!("{} {} {}", ranks.join("/"), to_move, bags.join(" "))
+    }
+}
+
+/// A token as [Position::fen] writes it, e.g., "F'" for a flipped Footman of Player 1.
+pub(crate) fn fen_token(owner: Owner, unit: UnitId, side: CurrentSide) -> String {
+    let c = unit.name().chars().next().unwrap();
+    let c = match owner {
+        Owner::Player1 => c.to_ascii_uppercase(),
+        Owner::Player2 => c.to_ascii_lowercase(),
+    };
+    match side {
+        CurrentSide::Initial => c.to_string(),
+        CurrentSide::Flipped => format!("{}'", c),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::bitboard::square_index;
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::units;
+
+    use super::*;
+
+    fn initial() -> Position {
+        let gs = GameState::new(
+            &TokenBag::new(vec![units::footman(Owner::Player1).token, units::footman(Owner::Player1).token]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        Position::from_state(&gs).unwrap()
+    }
+
+    fn legal(p: &Position) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        moves.as_slice().to_vec()
+    }
+
+    fn sq(x: u16, y: u16) -> usize {
+        square_index(Coordinates { x, y })
+    }
+
+    #[test]
+    fn fen_summarizes_the_position() {
+        let p = initial();
+        assert_eq!("1fdf2/6/6/6/6/2FDF1 1 FF ff", p.fen());
+        let p = p.make(p.parse_move("c1-c2").unwrap());
+        assert_eq!("1fdf2/6/6/6/2F'3/3DF1 2 FF ff", p.fen());
+    }
+
+    #[test]
+    fn from_state_counts_bags() {
+        let p = initial();
+        assert_eq!(2, p.bag(Owner::Player1)[UnitId::Footman.index()]);
+        assert_eq!(2, p.bag_size(Owner::Player2));
+        assert_eq!(Owner::Player1, p.to_move);
+
+        let gs = GameState::new(
+            &TokenBag::new((0..256).map(|_| units::footman(Owner::Player1).token).collect()),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        assert!(Position::from_state(&gs).is_none());
+    }
+
+    #[test]
+    fn to_state_is_inverse_of_from_state() {
+        let p = initial().make(Move::summon(sq(3, 1), UnitId::Footman));
+        assert_eq!(Some(p), Position::from_state(&p.to_state()));
+    }
+
+    #[test]
+    fn initial_moves_include_summons() {
+        let p = initial();
+        let moves = legal(&p);
+        assert_eq!(5, moves.len());
+        assert_eq!(
+            vec![Move::summon(sq(3, 1), UnitId::Footman)],
+            moves.iter().cloned().filter(|m| m.kind() == MoveKind::Summon).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn summon_takes_from_the_bag_and_doesnt_flip_the_duke() {
+        let p = initial().make(Move::summon(sq(3, 1), UnitId::Footman));
+        assert_eq!(1, p.bag(Owner::Player1)[UnitId::Footman.index()]);
+        assert_eq!(
+            Some((Owner::Player1, UnitId::Footman, CurrentSide::Initial)),
+            p.board.get(Coordinates { x: 3, y: 1 }),
+        );
+        assert_eq!(
+            Some((Owner::Player1, UnitId::Duke, CurrentSide::Initial)),
+            p.board.get(Coordinates { x: 3, y: 0 }),
+        );
+        assert_eq!(Owner::Player2, p.to_move);
+    }
+
+    #[test]
+    fn moving_flips_the_token() {
+        let p = initial().make(Move::new(sq(2, 0), sq(2, 1), MoveKind::Move));
+        assert_eq!(None, p.board.get(Coordinates { x: 2, y: 0 }));
+        assert_eq!(
+            Some((Owner::Player1, UnitId::Footman, CurrentSide::Flipped)),
+            p.board.get(Coordinates { x: 2, y: 1 }),
+        );
+    }
+
+    #[test]
+    fn legal_moves_dont_leave_the_duke_in_guard() {
+        let mut board = BitBoard::empty();
+        board.put(Coordinates { x: 0, y: 0 }, Owner::Player1, UnitId::Duke, CurrentSide::Initial);
+        board.put(Coordinates { x: 1, y: 0 }, Owner::Player1, UnitId::Footman, CurrentSide::Initial);
+        board.put(Coordinates { x: 5, y: 0 }, Owner::Player2, UnitId::Duke, CurrentSide::Initial);
+        board.put(Coordinates { x: 5, y: 5 }, Owner::Player2, UnitId::Footman, CurrentSide::Initial);
+        let p = Position::new(board, Owner::Player1, [[0; UnitId::COUNT]; 2]);
+        // The footman is pinned, so only its capture-free horizontal move along the row is legal.
+        let moves = legal(&p);
+        assert!(moves.iter().all(|m| m.from() != sq(1, 0) || m.to() == sq(2, 0)));
+        assert!(moves.iter().all(|m| !p.make(*m).board.is_in_guard(Owner::Player1)));
+    }
+
+    #[test]
+    fn perft_1_is_the_number_of_legal_moves() {
+        let p = initial();
+        assert_eq!(legal(&p).len() as u64, p.perft(1));
+    }
+
+    #[test]
+    fn perft_sums_over_children() {
+        let p = initial();
+        let expected: u64 = legal(&p).iter().map(|m| p.make(*m).perft(2)).sum();
+        assert_eq!(expected, p.perft(3));
+    }
+
+    #[test]
+    fn zobrist_depends_on_the_position_not_the_path() {
+        let p = initial();
+        let a = p
+            .make(Move::new(sq(2, 0), sq(2, 1), MoveKind::Move))
+            .make(Move::new(sq(2, 5), sq(2, 4), MoveKind::Move))
+            .make(Move::new(sq(4, 0), sq(4, 1), MoveKind::Move));
+        let b = p
+            .make(Move::new(sq(4, 0), sq(4, 1), MoveKind::Move))
+            .make(Move::new(sq(2, 5), sq(2, 4), MoveKind::Move))
+            .make(Move::new(sq(2, 0), sq(2, 1), MoveKind::Move));
+        assert_eq!(a, b);
+        assert_eq!(a.zobrist(), b.zobrist());
+        assert_ne!(p.zobrist(), a.zobrist());
+    }
+
+    #[test]
+    fn zobrist_depends_on_bags_and_side_to_move() {
+        let p = initial();
+        let mut other_bags = p;
+        other_bags.bags[0][UnitId::Footman.index()] = 1;
+        assert_ne!(p.zobrist(), other_bags.zobrist());
+        let mut other_side = p;
+        other_side.to_move = Owner::Player2;
+        assert_ne!(p.zobrist(), other_side.zobrist());
+    }
+
+    #[test]
+    fn parse_move_finds_legal_moves_only() {
+        let p = initial();
+        assert_eq!(Some(Move::new(sq(2, 0), sq(2, 1), MoveKind::Move)), p.parse_move("c1-c2"));
+        assert_eq!(Some(Move::summon(sq(3, 1), UnitId::Footman)), p.parse_move("Footman@d2"));
+        assert_eq!(None, p.parse_move("c1-c4"));
+        assert_eq!(None, p.parse_move("Duke@d2"));
+    }
+
+    #[test]
+    fn canonical_maps_summons_to_the_first_drawable_unit() {
+        let mut p = initial();
+        p.bags[0] = [1, 1];
+        assert_eq!(Move::summon(sq(3, 1), UnitId::Duke), p.canonical(Move::summon(sq(3, 1), UnitId::Footman)));
+        let m = Move::new(sq(2, 0), sq(2, 1), MoveKind::Move);
+        assert_eq!(m, p.canonical(m));
+    }
+}
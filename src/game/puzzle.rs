@@ -0,0 +1,262 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::common::coordinates::Coordinates;
+use crate::game::bitboard::{square_at, squares, BitBoard};
+use crate::game::board::GameBoard;
+use crate::game::moves::{Move, MoveList};
+use crate::game::position::Position;
+use crate::game::record::{parse_tag, split_tagged};
+use crate::game::token::{CurrentSide, Owner};
+use crate::game::units::UnitId;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PuzzleError {
+    MissingTag(&'static str),
+    InvalidTag(String, String),
+    /// The ply is 1-based.
+    IllegalMove { ply: usize, text: String },
+    /// The solution is empty, or doesn't end with the opponent mated.
+    NotMate,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::MissingTag(tag) => write!(f, "Missing tag {}", tag),
+            PuzzleError::InvalidTag(tag, value) => write!(f, "Invalid value '{}' for tag {}", value, tag),
+            PuzzleError::IllegalMove { ply, text } => write!(f, "Illegal move '{}' at ply {}", text, ply),
+            PuzzleError::NotMate => write!(f, "The solution doesn't end in mate"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+/// A "win in N" exercise: a position, and a line in which the player to move mates in
+/// [Puzzle::moves] of its moves, the opponent defending as well as it can.
+///
+/// The text format is tags, followed by the solution in [Move] notation, e.g.:
+/// ```text
+/// [Title "Pinned against the edge"]
+/// [ToMove "Player1"]
+/// [Player1 "Duke e1, Footman c3, Footman d3 Flipped"]
+/// [Player2 "Duke a3, Footman b2"]
+/// [Player1Bag "Footman"]
+///
+/// d3-c4 b2-b3 c4-b4
+/// ```
+/// Tokens are on their initial side unless followed by "Flipped". The title and bags are optional.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Puzzle {
+    pub title: String,
+    pub position: Position,
+    pub solution: Vec<Move>,
+}
+
+/// The Duke first, then the other tokens by square.
+fn print_tokens(board: &BitBoard, owner: Owner) -> String {
+    let mut tokens: Vec<(Coordinates, UnitId, CurrentSide)> = squares(board.owned_by(owner))
+        .map(|i| {
+            let (_, unit, side) = board.get(square_at(i)).unwrap();
+            (square_at(i), unit, side)
+        })
+        .collect();
+    tokens.sort_by_key(|t| t.1 != UnitId::Duke);
+    tokens
+        .iter()
+        .map(|(c, unit, side)| match side {
+            CurrentSide::Initial => format!("{} {}", unit.name(), c),
+            CurrentSide::Flipped => format!("{} {} Flipped", unit.name(), c),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn parse_tokens(board: &mut BitBoard, owner: Owner, s: &str) -> Option<()> {
+    for token in s.split(',').filter(|t| !t.trim().is_empty()) {
+        let mut words = token.split_whitespace();
+        let unit = UnitId::from_name(words.next()?)?;
+        let c: Coordinates = words.next()?.parse().ok()?;
+        let side = match words.next() {
+            None => CurrentSide::Initial,
+            Some("Flipped") => CurrentSide::Flipped,
+            Some(_) => return None,
+        };
+        if c.x >= GameBoard::BOARD_SIZE || c.y >= GameBoard::BOARD_SIZE || board.get(c).is_some() {
+            return None;
+        }
+        board.put(c, owner, unit, side);
+    }
+    Some(())
+}
+
+fn print_bag(counts: &[u8; UnitId::COUNT]) -> String {
+    UnitId::ALL
+        .iter()
+        .flat_map(|u| (0..counts[u.index()]).map(move |_| u.name()))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn parse_bag(s: &str) -> Option<[u8; UnitId::COUNT]> {
+    let mut result = [0u8; UnitId::COUNT];
+    for name in s.split_whitespace() {
+        let count = &mut result[UnitId::from_name(name)?.index()];
+        *count = count.checked_add(1)?;
+    }
+    Some(result)
+}
+
+impl Puzzle {
+    /// The number of moves of the player to move, e.g., 2 for a win in 2.
+    pub fn moves(&self) -> u32 {
+        (self.solution.len() as u32).div_ceil(2)
+    }
+
+    /// The player who has to find the win.
+    pub fn solver(&self) -> Owner {
+        self.position.to_move
+    }
+
+    /// Parses a file of several puzzles, each starting with its tags.
+    pub fn parse_all(text: &str) -> Result<Vec<Puzzle>, PuzzleError> {
+        split_tagged(text).iter().map(|c| c.parse()).collect()
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = &self.position;
+        if !self.title.is_empty() {
+            writeln!(f, "[Title \"{}\"]", self.title)?;
+        }
+        writeln!(f, "[ToMove \"{:?}\"]", p.to_move)?;
+        writeln!(f, "[Player1 \"{}\"]", print_tokens(&p.board, Owner::Player1))?;
+        writeln!(f, "[Player2 \"{}\"]", print_tokens(&p.board, Owner::Player2))?;
+        for owner in Owner::ALL.iter().filter(|o| p.bag_size(**o) > 0) {
+            writeln!(f, "[{:?}Bag \"{}\"]", owner, print_bag(p.bag(*owner)))?;
+        }
+        writeln!(f)?;
+        let moves: Vec<String> = self.solution.iter().map(|m| m.to_string()).collect();
+        writeln!(f, "{}", moves.join(" "))
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = PuzzleError;
+
+    /// Unknown tags and move numbers are ignored.
+    fn from_str(s: &str) -> Result<Puzzle, PuzzleError> {
+        let mut title = String::new();
+        let mut to_move = None;
+        let mut board = BitBoard::empty();
+        let mut bags = [[0; UnitId::COUNT]; 2];
+        let mut seen = [false; 2];
+        let mut move_words = Vec::new();
+        for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            if let Some((name, value)) = parse_tag(line) {
+                let invalid = || PuzzleError::InvalidTag(name.to_owned(), value.to_owned());
+                match name {
+                    "Title" => title = value.to_owned(),
+                    "ToMove" => to_move = Some(
+                        *Owner::ALL.iter().find(|o| format!("{:?}", o) == value).ok_or_else(invalid)?
+                    ),
+                    "Player1" | "Player2" => {
+                        let owner = if name == "Player1" { Owner::Player1 } else { Owner::Player2 };
+                        parse_tokens(&mut board, owner, value).ok_or_else(invalid)?;
+                        seen[owner.index()] = true;
+                    }
+                    "Player1Bag" => bags[0] = parse_bag(value).ok_or_else(invalid)?,
+                    "Player2Bag" => bags[1] = parse_bag(value).ok_or_else(invalid)?,
+                    _ => (),
+                }
+            } else {
+                move_words.extend(line.split_whitespace().filter(|w| !w.ends_with('.')));
+            }
+        }
+        for (owner, tag) in [(Owner::Player1, "Player1"), (Owner::Player2, "Player2")].iter() {
+            if !seen[owner.index()] {
+                return Err(PuzzleError::MissingTag(tag));
+            }
+            if board.duke_coordinates(*owner).is_none() {
+                let value = print_tokens(&board, *owner);
+                return Err(PuzzleError::InvalidTag((*tag).to_owned(), value));
+            }
+        }
+        let position = Position::new(board, to_move.ok_or(PuzzleError::MissingTag("ToMove"))?, bags);
+        let mut p = position;
+        let mut solution = Vec::new();
+        for word in move_words {
+            let m = p.parse_move(word).ok_or_else(|| PuzzleError::IllegalMove {
+                ply: solution.len() + 1,
+                text: word.to_owned(),
+            })?;
+            solution.push(m);
+            p = p.make(m);
+        }
+        let mut replies = MoveList::new();
+        p.legal_moves(&mut replies);
+        if solution.len() % 2 == 0 || !replies.is_empty() || !p.is_in_guard() {
+            return Err(PuzzleError::NotMate);
+        }
+        Ok(Puzzle { title, position, solution })
+    }
+}
+
+/// A win in 2 for tests: Player 2's Duke is pinned against the edge by Player 1's footmen.
+#[cfg(test)]
+pub(crate) const PINNED: &str = "\
+[Title \"Pinned against the edge\"]
+[ToMove \"Player1\"]
+[Player1 \"Duke e1, Footman c3, Footman d3 Flipped\"]
+[Player2 \"Duke a3, Footman b2\"]
+
+d3-c4 b2-b3 c4-b4
+";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_round_trips() {
+        let text = PINNED.replace("\n\n", "\n[Player1Bag \"Footman\"]\n\n");
+        let puzzle: Puzzle = text.parse().unwrap();
+        assert_eq!("Pinned against the edge", puzzle.title);
+        assert_eq!(2, puzzle.moves());
+        assert_eq!(Owner::Player1, puzzle.solver());
+        assert_eq!(1, puzzle.position.bag_size(Owner::Player1));
+        assert_eq!(Some((Owner::Player1, UnitId::Footman, CurrentSide::Flipped)), puzzle.position.board.get(square_at(15)));
+        assert_eq!(text, puzzle.to_string());
+    }
+
+    #[test]
+    fn parse_all_reads_several_puzzles() {
+        let puzzles = Puzzle::parse_all(&format!("{}\n{}", PINNED, PINNED.replace("[Title \"Pinned against the edge\"]\n", ""))).unwrap();
+        assert_eq!(2, puzzles.len());
+        assert_eq!("", puzzles[1].title);
+        assert_eq!(puzzles[0].solution, puzzles[1].solution);
+    }
+
+    #[test]
+    fn parse_reports_errors() {
+        assert_eq!(Err(PuzzleError::NotMate), PINNED.replace(" c4-b4", "").parse::<Puzzle>());
+        assert_eq!(Err(PuzzleError::NotMate), PINNED.replace(" b2-b3 c4-b4", "").parse::<Puzzle>());
+        assert_eq!(
+            Err(PuzzleError::IllegalMove { ply: 2, text: "b2-b4".to_owned() }),
+            PINNED.replace("b2-b3", "b2-b4").parse::<Puzzle>(),
+        );
+        assert_eq!(Err(PuzzleError::MissingTag("ToMove")), PINNED.replace("[ToMove \"Player1\"]", "").parse::<Puzzle>());
+        assert_eq!(
+            Err(PuzzleError::InvalidTag("Player2".to_owned(), "Footman b2".to_owned())),
+            PINNED.replace("Duke a3, ", "").parse::<Puzzle>(),
+        );
+        assert!(PINNED.replace("c3", "e1").parse::<Puzzle>().is_err());
+        let big = vec!["Footman"; 256].join(" ");
+        assert_eq!(
+            Err(PuzzleError::InvalidTag("Player1Bag".to_owned(), big.clone())),
+            PINNED.replace("\n\n", &format!("\n[Player1Bag \"{}\"]\n\n", big)).parse::<Puzzle>(),
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::moves::Move;
+use crate::game::position::Position;
+use crate::game::state::{DukeInitialLocation, FootmenSetup, GameState};
+use crate::game::token::{Owner, TokenBag};
+use crate::game::tree::GameTree;
+use crate::game::units::UnitId;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum GameResult {
+    Win(Owner),
+    Draw,
+    Unfinished,
+}
+
+impl GameResult {
+    pub(crate) fn notation(&self) -> &'static str {
+        match self {
+            GameResult::Win(Owner::Player1) => "1-0",
+            GameResult::Win(Owner::Player2) => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unfinished => "*",
+        }
+    }
+    pub(crate) fn parse(s: &str) -> Option<GameResult> {
+        [GameResult::Win(Owner::Player1), GameResult::Win(Owner::Player2), GameResult::Draw, GameResult::Unfinished]
+            .iter()
+            .find(|r| r.notation() == s)
+            .copied()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RecordError {
+    MissingTag(&'static str),
+    InvalidTag(String, String),
+    /// The ply is 1-based.
+    IllegalMove { ply: usize, text: String },
+    InvalidMoveText(String),
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::MissingTag(tag) => write!(f, "Missing tag {}", tag),
+            RecordError::InvalidTag(tag, value) => write!(f, "Invalid value '{}' for tag {}", value, tag),
+            RecordError::IllegalMove { ply, text } => write!(f, "Illegal move '{}' at ply {}", text, ply),
+            RecordError::InvalidMoveText(message) => write!(f, "Invalid move text: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+pub type Setup = (DukeInitialLocation, FootmenSetup);
+
+/// A complete game: the setup, the moves, and how it ended. Summons record the unit drawn, so
+/// replaying a record is deterministic.
+///
+/// The text format has a tag per line, followed by the moves in [Move] notation, numbered by
+/// turn, e.g.:
+/// ```text
+/// [Player1 "Left Sides"]
+/// [Player2 "Right Left"]
+/// [Bag "Footman Footman"]
+/// [Result "1-0"]
+///
+/// 1. c1-c2 c6-c5 2. Footman@d2 *
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GameRecord {
+    /// Indexed by [Owner::index].
+    pub setups: [Setup; 2],
+    /// Each player's bag at the start of the game.
+    pub bag: Vec<UnitId>,
+    pub moves: Vec<Move>,
+    pub result: GameResult,
+}
+
+fn print_setup(s: &Setup) -> String {
+    format!("{:?} {:?}", s.0, s.1)
+}
+
+fn parse_setup(s: &str) -> Option<Setup> {
+    let mut words = s.split_whitespace();
+    let duke = match words.next()? {
+        "Left" => DukeInitialLocation::Left,
+        "Right" => DukeInitialLocation::Right,
+        _ => return None,
+    };
+    let footmen = match words.next()? {
+        "Sides" => FootmenSetup::Sides,
+        "Left" => FootmenSetup::Left,
+        "Right" => FootmenSetup::Right,
+        _ => return None,
+    };
+    Some((duke, footmen)).filter(|_| words.next().is_none())
+}
+
+/// Parses a `[Name "value"]` line.
+pub(crate) fn parse_tag(line: &str) -> Option<(&str, &str)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    Some((name, value.trim().strip_prefix('"')?.strip_suffix('"')?))
+}
+
+/// Writes the tags of a record, and the blank line after them.
+pub(crate) fn write_tags(f: &mut fmt::Formatter<'_>, setups: &[Setup; 2], bag: &[UnitId], result: GameResult) -> fmt::Result {
+    let bag: Vec<&str> = bag.iter().map(|u| u.name()).collect();
+    writeln!(f, "[Player1 \"{}\"]", print_setup(&setups[0]))?;
+    writeln!(f, "[Player2 \"{}\"]", print_setup(&setups[1]))?;
+    writeln!(f, "[Bag \"{}\"]", bag.join(" "))?;
+    writeln!(f, "[Result \"{}\"]", result.notation())?;
+    writeln!(f)
+}
+
+/// The units of a Bag tag, which can't hold a Duke, nor more of a unit than a [Position] counts.
+fn parse_bag(value: &str) -> Option<Vec<UnitId>> {
+    let mut counts = [0u8; UnitId::COUNT];
+    let mut bag = Vec::new();
+    for name in value.split_whitespace() {
+        let unit = UnitId::from_name(name).filter(|u| *u != UnitId::Duke)?;
+        let count = &mut counts[unit.index()];
+        *count = count.checked_add(1)?;
+        bag.push(unit);
+    }
+    Some(bag)
+}
+
+/// Parses the tags into a record without moves, and returns it with the move text. Unknown tags are
+/// ignored.
+pub(crate) fn parse_header(s: &str) -> Result<(GameRecord, String), RecordError> {
+    let mut setups = [None, None];
+    let mut bag = None;
+    let mut result = GameResult::Unfinished;
+    let mut text = Vec::new();
+    for line in s.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        if let Some((name, value)) = parse_tag(line) {
+            let invalid = || RecordError::InvalidTag(name.to_owned(), value.to_owned());
+            match name {
+                "Player1" => setups[0] = Some(parse_setup(value).ok_or_else(invalid)?),
+                "Player2" => setups[1] = Some(parse_setup(value).ok_or_else(invalid)?),
+                "Bag" => bag = Some(parse_bag(value).ok_or_else(invalid)?),
+                "Result" => result = GameResult::parse(value).ok_or_else(invalid)?,
+                _ => (),
+            }
+        } else {
+            text.push(line);
+        }
+    }
+    let mut record = GameRecord::new(
+        [
+            setups[0].ok_or(RecordError::MissingTag("Player1"))?,
+            setups[1].ok_or(RecordError::MissingTag("Player2"))?,
+        ],
+        bag.ok_or(RecordError::MissingTag("Bag"))?,
+    );
+    record.result = result;
+    Ok((record, text.join("\n")))
+}
+
+/// Splits text into chunks which each start with a block of tags. Lines before the first tag are
+/// dropped.
+pub(crate) fn split_tagged(text: &str) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut in_body = true;
+    for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        let is_tag = parse_tag(line).is_some();
+        if is_tag && in_body {
+            chunks.push(String::new());
+        }
+        in_body = !is_tag;
+        if let Some(chunk) = chunks.last_mut() {
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+    }
+    chunks
+}
+
+impl GameRecord {
+    pub fn new(setups: [Setup; 2], bag: Vec<UnitId>) -> GameRecord {
+        GameRecord { setups, bag, moves: Vec::new(), result: GameResult::Unfinished }
+    }
+
+    pub fn initial_state(&self) -> GameState {
+        let bag = TokenBag::new(self.bag.iter().map(|u| u.token(Owner::Player1).token).collect());
+        GameState::new(&bag, self.setups[0], self.setups[1])
+    }
+
+    pub fn initial_position(&self) -> Position {
+        Position::from_state(&self.initial_state()).expect("Records only have standard units")
+    }
+
+    /// The position before every move, followed by the final position. Assumes the moves are legal,
+    /// which parsing verifies.
+    pub fn positions(&self) -> Vec<Position> {
+        let mut result = vec![self.initial_position()];
+        for m in self.moves.iter() {
+            let next = result.last().unwrap().make(*m);
+            result.push(next);
+        }
+        result
+    }
+
+    /// Parses a file of several records, each starting with its tags.
+    pub fn parse_all(text: &str) -> Result<Vec<GameRecord>, RecordError> {
+        split_tagged(text).iter().map(|c| c.parse()).collect()
+    }
+
+    /// Just the moves, numbered by turn, e.g., "1. c1-c2 c6-c5 2. Footman@d2".
+    pub fn move_text(&self) -> String {
+        self.moves
+            .chunks(2)
+            .enumerate()
+            .map(|(i, turn)| {
+                let moves: Vec<String> = turn.iter().map(|m| m.to_string()).collect();
+                format!("{}. {}", i + 1, moves.join(" "))
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tags(f, &self.setups, &self.bag, self.result)?;
+        let text = self.move_text();
+        if text.is_empty() {
+            writeln!(f, "{}", self.result.notation())
+        } else {
+            writeln!(f, "{} {}", text, self.result.notation())
+        }
+    }
+}
+
+impl FromStr for GameRecord {
+    type Err = RecordError;
+
+    /// Move numbers are optional, and unknown tags are ignored. Variations and comments are
+    /// dropped, leaving the main line of the [GameTree].
+    fn from_str(s: &str) -> Result<GameRecord, RecordError> {
+        Ok(s.parse::<GameTree>()?.main_line())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record() -> GameRecord {
+        let mut result = GameRecord::new(
+            [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Right, FootmenSetup::Left)],
+            vec![UnitId::Footman, UnitId::Footman],
+        );
+        let mut p = result.initial_position();
+        for text in ["c1-c2", "c6-c5", "Footman@d2"].iter() {
+            let m = p.parse_move(text).unwrap();
+            result.moves.push(m);
+            p = p.make(m);
+        }
+        result.result = GameResult::Win(Owner::Player1);
+        result
+    }
+
+    #[test]
+    fn display_writes_tags_and_numbered_moves() {
+        assert_eq!(
+            "[Player1 \"Left Sides\"]\n[Player2 \"Right Left\"]\n[Bag \"Footman Footman\"]\n[Result \"1-0\"]\n\n\
+             1. c1-c2 c6-c5 2. Footman@d2 1-0\n",
+            record().to_string(),
+        );
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        assert_eq!(Ok(record()), record().to_string().parse());
+    }
+
+    #[test]
+    fn parse_ignores_move_numbers_and_unknown_tags() {
+        let text = "[Event \"Casual\"]\n[Player1 \"Left Sides\"]\n[Player2 \"Right Left\"]\n[Bag \"Footman Footman\"]\n\
+                    [Result \"1-0\"]\nc1-c2 c6-c5\nFootman@d2";
+        assert_eq!(Ok(record()), text.parse());
+    }
+
+    #[test]
+    fn parse_reports_errors() {
+        let tags = "[Player1 \"Left Sides\"]\n[Player2 \"Right Left\"]\n[Bag \"Footman\"]\n";
+        assert_eq!(
+            Err(RecordError::IllegalMove { ply: 2, text: "c1-c2".to_owned() }),
+            format!("{}c1-c2 c1-c2", tags).parse::<GameRecord>(),
+        );
+        assert_eq!(Err(RecordError::MissingTag("Bag")), "[Player1 \"Left Sides\"]\n[Player2 \"Left Sides\"]".parse::<GameRecord>());
+        assert_eq!(
+            Err(RecordError::InvalidTag("Player1".to_owned(), "Up Sides".to_owned())),
+            "[Player1 \"Up Sides\"]".parse::<GameRecord>(),
+        );
+        let big = vec!["Footman"; 256].join(" ");
+        for bag in ["Footman Duke", big.as_str()].iter() {
+            assert_eq!(
+                Err(RecordError::InvalidTag("Bag".to_owned(), bag.to_string())),
+                tags.replace("Footman", bag).parse::<GameRecord>(),
+            );
+        }
+    }
+
+    #[test]
+    fn parse_all_splits_records_by_tags() {
+        let mut other = record();
+        other.moves.truncate(1);
+        other.result = GameResult::Unfinished;
+        let text = format!("{}\n{}{}", record(), other, other);
+        assert_eq!(Ok(vec![record(), other.clone(), other]), GameRecord::parse_all(&text));
+        assert_eq!(Ok(vec![]), GameRecord::parse_all(""));
+    }
+
+    #[test]
+    fn positions_include_the_final_one() {
+        let r = record();
+        let positions = r.positions();
+        assert_eq!(4, positions.len());
+        assert_eq!(r.initial_position(), positions[0]);
+        assert_eq!(1, positions[3].bag(Owner::Player1)[UnitId::Footman.index()]);
+    }
+}
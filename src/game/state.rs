@@ -1,9 +1,12 @@
 use crate::common::coordinates::Coordinates;
 use crate::game::board::GameBoard;
+use crate::game::bitboard::square_at;
+use crate::game::moves::{GameMove, Move};
 use crate::game::token::{DiscardBag, OwnedToken, Owner, TokenBag};
 use crate::game::units;
-use crate::game::units::footman;
+use crate::game::units::{footman, UnitId};
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum FootmenSetup {
     // Footmen are to the sides of the Duke
     Sides,
@@ -13,11 +16,13 @@ pub enum FootmenSetup {
     Right,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum DukeInitialLocation {
     Left,
     Right,
 }
 
+#[derive(Clone)]
 pub struct GameState {
     pub board: GameBoard,
     pub current_player_turn: Owner,
@@ -77,7 +82,132 @@ impl GameState {
         }
     }
 
-    pub fn rows(&self) -> &Vec<Vec<Option<OwnedToken>>> {
-        self.board.rows()
+    pub fn bag(&self, owner: Owner) -> &TokenBag {
+        match owner {
+            Owner::Player1 => &self.player_1_bag,
+            Owner::Player2 => &self.player_2_bag,
+        }
+    }
+    fn bag_mut(&mut self, owner: Owner) -> &mut TokenBag {
+        match owner {
+            Owner::Player1 => &mut self.player_1_bag,
+            Owner::Player2 => &mut self.player_2_bag,
+        }
+    }
+    pub fn discard(&self, owner: Owner) -> &DiscardBag {
+        match owner {
+            Owner::Player1 => &self.player_1_discard,
+            Owner::Player2 => &self.player_2_discard,
+        }
+    }
+    fn discard_mut(&mut self, owner: Owner) -> &mut DiscardBag {
+        match owner {
+            Owner::Player1 => &mut self.player_1_discard,
+            Owner::Player2 => &mut self.player_2_discard,
+        }
+    }
+
+    /// Applies m, which is assumed to be legal, and passes the turn. Captured tokens go to their
+    /// owner's discard pile.
+    pub fn make_move(&mut self, m: &GameMove) -> () {
+        if let Some(captured) = self.board.make_move(m) {
+            self.discard_mut(captured.owner).add(captured.token);
+        }
+        self.current_player_turn = self.current_player_turn.other();
+    }
+
+    /// Draws a random token from the current player's bag onto c, which is assumed to be a legal
+    /// summoning square, and passes the turn. Returns the summoned token, or [None] if the bag is
+    /// empty, in which case nothing changes.
+    pub fn summon(&mut self, c: Coordinates) -> Option<&OwnedToken> {
+        let owner = self.current_player_turn;
+        let token = self.bag_mut(owner).pull()?;
+        self.board.place(c, OwnedToken { token, owner });
+        self.current_player_turn = owner.other();
+        self.board.get(c)
+    }
+
+    /// Like [GameState::summon], but draws a token of the unit, e.g., to replay a game.
+    pub fn summon_unit(&mut self, c: Coordinates, unit: UnitId) -> Option<&OwnedToken> {
+        let owner = self.current_player_turn;
+        let token = self.bag_mut(owner).take(unit)?;
+        self.board.place(c, OwnedToken { token, owner });
+        self.current_player_turn = owner.other();
+        self.board.get(c)
+    }
+
+    /// Applies m, which is assumed to be legal, with summons drawing the move's unit.
+    pub fn apply(&mut self, m: Move) -> () {
+        match (m.to_game_move(), m.unit()) {
+            (Some(gm), _) => self.make_move(&gm),
+            (None, Some(unit)) => {
+                self.summon_unit(square_at(m.to()), unit);
+            }
+            (None, None) => (),
+        }
+    }
+}
+
+/// Both Dukes on the left with Footmen to their sides, and the given number of Footmen in
+/// Player 1's bag. For tests.
+#[cfg(test)]
+pub(crate) fn initial(footmen: usize) -> GameState {
+    GameState::new(
+        &TokenBag::new((0..footmen).map(|_| footman(Owner::Player1).token).collect()),
+        (DukeInitialLocation::Left, FootmenSetup::Sides),
+        (DukeInitialLocation::Left, FootmenSetup::Sides),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::bitboard::square_index;
+    use crate::game::token::{CurrentSide, TokenAction};
+
+    use super::*;
+
+    #[test]
+    fn make_move_passes_the_turn() {
+        let mut gs = initial(1);
+        let src = Coordinates { x: 2, y: 0 };
+        let dst = Coordinates { x: 2, y: 1 };
+        gs.make_move(&GameMove::Move { src, dst, action: TokenAction::Move });
+        assert_eq!(Owner::Player2, gs.current_player_turn);
+        assert_eq!(CurrentSide::Flipped, gs.board.get(dst).unwrap().token.current_side);
+    }
+
+    #[test]
+    fn captured_tokens_are_discarded() {
+        let mut gs = initial(1);
+        gs.board.place(Coordinates { x: 2, y: 1 }, footman(Owner::Player2));
+        let src = Coordinates { x: 2, y: 0 };
+        let dst = Coordinates { x: 2, y: 1 };
+        gs.make_move(&GameMove::Move { src, dst, action: TokenAction::Move });
+        assert_eq!(1, gs.discard(Owner::Player2).existing().len());
+        assert!(gs.discard(Owner::Player1).existing().is_empty());
+    }
+
+    #[test]
+    fn summon_draws_from_the_current_players_bag() {
+        let mut gs = initial(1);
+        let c = Coordinates { x: 3, y: 1 };
+        assert_eq!(Some(Owner::Player1), gs.summon(c).map(|t| t.owner));
+        assert!(gs.bag(Owner::Player1).remaining().is_empty());
+        assert_eq!(1, gs.bag(Owner::Player2).remaining().len());
+        assert_eq!(Owner::Player2, gs.current_player_turn);
+        gs.current_player_turn = Owner::Player1;
+        assert!(gs.summon(Coordinates { x: 2, y: 1 }).is_none());
+        assert_eq!(Owner::Player1, gs.current_player_turn);
+    }
+
+    #[test]
+    fn apply_summons_the_moves_unit() {
+        let mut gs = initial(1);
+        let c = Coordinates { x: 3, y: 1 };
+        gs.apply(Move::summon(square_index(c), UnitId::Footman));
+        assert_eq!(Some(UnitId::Footman), gs.board.get(c).and_then(|t| t.token.unit()));
+        assert_eq!(Owner::Player2, gs.current_player_turn);
+        gs.current_player_turn = Owner::Player1;
+        assert!(gs.summon_unit(Coordinates { x: 2, y: 1 }, UnitId::Footman).is_none());
     }
 }
@@ -1,17 +1,26 @@
 use rand::Rng;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 use crate::assert_not;
 use crate::common::board::Board;
+use crate::game::actions;
+use crate::game::actions::ActionTable;
 use crate::game::offset::Offsets;
+use crate::game::units::UnitId;
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum CurrentSide {
     Initial,
     Flipped,
 }
 
 impl CurrentSide {
+    pub const ALL: [CurrentSide; 2] = [CurrentSide::Initial, CurrentSide::Flipped];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
     pub fn flip(&self) -> CurrentSide {
         match self {
             CurrentSide::Initial => CurrentSide::Flipped,
@@ -21,7 +30,7 @@ impl CurrentSide {
 }
 
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum TokenAction {
     Move,
     Jump,
@@ -31,7 +40,7 @@ pub enum TokenAction {
     Strike,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct TokenSide {
     board: Board<TokenAction>,
 }
@@ -39,7 +48,7 @@ pub struct TokenSide {
 impl TokenSide {
     pub const SIDE: u16 = 5;
 
-    pub(in crate::game) fn new(map: HashMap<Offsets, TokenAction>) -> TokenSide {
+    pub(crate) fn new(map: HashMap<Offsets, TokenAction>) -> TokenSide {
         for (c, a) in &map {
             match a {
                 TokenAction::Jump =>
@@ -97,6 +106,14 @@ impl GameToken {
             CurrentSide::Flipped => &self.side_b,
         }
     }
+    /// [None] for tokens which aren't one of the standard units, by name and by both sides.
+    pub fn unit(&self) -> Option<UnitId> {
+        UnitId::from_name(&self.name).filter(|u| actions::is_standard(*u, self))
+    }
+    /// By name only, since the game revolves around the Duke whatever its sides.
+    pub fn is_duke(&self) -> bool {
+        self.name == UnitId::Duke.name()
+    }
     pub fn single_char_token(&self) -> char {
         let c = self.name.chars().next().unwrap();
         match self.current_side {
@@ -106,18 +123,32 @@ impl GameToken {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Owner {
     Player1,
     Player2,
 }
 
 impl Owner {
-    fn same_team(&self, other: &Self) -> bool {
-        self == other
+    pub const ALL: [Owner; 2] = [Owner::Player1, Owner::Player2];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+    pub fn other(&self) -> Owner {
+        match self {
+            Owner::Player1 => Owner::Player2,
+            Owner::Player2 => Owner::Player1,
+        }
     }
-    fn difference_team(&self, other: &Self) -> bool {
-        self != other
+    /// Token sides are defined from their owner's point of view, where Top is towards the enemy.
+    /// Player 1 starts on the first row, so its Top already points up the board; Player 2 starts on
+    /// the last row, so its offsets are mirrored vertically.
+    pub fn orient(&self, o: Offsets) -> Offsets {
+        match self {
+            Owner::Player1 => o,
+            Owner::Player2 => o.vertical_flipped(),
+        }
     }
 }
 
@@ -131,6 +162,22 @@ impl OwnedToken {
     pub fn single_char_token(&self) -> char {
         self.token.single_char_token()
     }
+    /// The grouped actions of the current side, oriented by the owner. Standard units are looked up
+    /// in the precompiled registry, other tokens are computed on the fly.
+    pub fn action_table(&self) -> Cow<'static, ActionTable> {
+        match self.token.unit() {
+            Some(unit) => Cow::Borrowed(actions::lookup(unit, self.token.current_side, self.owner)),
+            None => Cow::Owned(ActionTable::new(self.token.get_current_side(), self.owner)),
+        }
+    }
+    /// The actions of the current side, oriented by the owner, i.e., as they apply on the board.
+    pub fn current_actions(&self) -> Vec<(Offsets, TokenAction)> {
+        self.token.get_current_side()
+            .actions()
+            .into_iter()
+            .map(|(o, a)| (self.owner.orient(o), *a))
+            .collect()
+    }
 }
 
 pub trait Ownership {
@@ -173,6 +220,12 @@ impl TokenBag {
         }
     }
 
+    /// Draws a token of the unit, rather than a random one.
+    pub fn take(&mut self, unit: UnitId) -> Option<GameToken> {
+        let index = self.bag.iter().position(|t| t.unit() == Some(unit))?;
+        Some(self.bag.remove(index))
+    }
+
     pub fn remaining(&self) -> &Vec<GameToken> {
         &self.bag
     }
@@ -0,0 +1,537 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::game::moves::Move;
+use crate::game::position::Position;
+use crate::game::record::{parse_header, write_tags, GameRecord, GameResult, RecordError, Setup};
+use crate::game::state::GameState;
+use crate::game::units::UnitId;
+
+/// A move in a [GameTree], and the moves which may follow it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Node {
+    pub m: Move,
+    pub comment: String,
+    /// An evaluation of the position after the move for Player 1, in the engine's units.
+    pub eval: Option<i32>,
+    /// The main continuation first, then its alternatives.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(m: Move) -> Node {
+        Node { m, comment: String::new(), eval: None, children: Vec::new() }
+    }
+}
+
+/// A game with variations, and a cursor on one of its positions. Every line follows the first
+/// child of each node from the cursor on, the main line from the start.
+///
+/// The text format is the [GameRecord]'s, where comments are in braces after their move,
+/// evaluations start a comment as `[%eval <score>]`, and alternatives to a move follow it in
+/// parentheses, e.g.:
+/// ```text
+/// 1. c1-c2 {[%eval 25] Solid} (1. e1-e2 {Sharper}) 1... c6-c5 *
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GameTree {
+    pub setups: [Setup; 2],
+    pub bag: Vec<UnitId>,
+    pub result: GameResult,
+    /// About the game, before any move.
+    pub comment: String,
+    pub children: Vec<Node>,
+    /// The child indices from the start to the current position.
+    cursor: Vec<usize>,
+}
+
+impl GameTree {
+    pub fn new(setups: [Setup; 2], bag: Vec<UnitId>) -> GameTree {
+        GameTree {
+            setups,
+            bag,
+            result: GameResult::Unfinished,
+            comment: String::new(),
+            children: Vec::new(),
+            cursor: Vec::new(),
+        }
+    }
+
+    /// A tree with the record's moves as its main line.
+    pub fn from_record(record: &GameRecord) -> GameTree {
+        let mut result = GameTree::new(record.setups, record.bag.clone());
+        result.result = record.result;
+        for m in record.moves.iter() {
+            result.play(*m);
+        }
+        result.to_start();
+        result
+    }
+
+    /// The main line, without variations or annotations.
+    pub fn main_line(&self) -> GameRecord {
+        let mut result = GameRecord::new(self.setups, self.bag.clone());
+        result.result = self.result;
+        let mut children = &self.children;
+        while let Some(main) = children.first() {
+            result.moves.push(main.m);
+            children = &main.children;
+        }
+        result
+    }
+
+    pub fn cursor(&self) -> &[usize] {
+        &self.cursor
+    }
+
+    fn node(&self, path: &[usize]) -> Option<&Node> {
+        let (first, rest) = path.split_first()?;
+        let mut node = &self.children[*first];
+        for i in rest {
+            node = &node.children[*i];
+        }
+        Some(node)
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        let (first, rest) = path.split_first()?;
+        let mut node = &mut self.children[*first];
+        for i in rest {
+            node = &mut node.children[*i];
+        }
+        Some(node)
+    }
+
+    fn children_mut(&mut self, path: &[usize]) -> &mut Vec<Node> {
+        if path.is_empty() {
+            &mut self.children
+        } else {
+            &mut self.node_mut(path).unwrap().children
+        }
+    }
+
+    /// The node of the last move played, [None] at the start.
+    pub fn current(&self) -> Option<&Node> {
+        self.node(&self.cursor)
+    }
+
+    /// The moves which may follow, the main continuation first.
+    pub fn variations(&self) -> &[Node] {
+        self.current().map_or(&self.children, |n| &n.children)
+    }
+
+    /// The moves from the start to the cursor.
+    pub fn moves(&self) -> Vec<Move> {
+        (1..=self.cursor.len()).map(|i| self.node(&self.cursor[..i]).unwrap().m).collect()
+    }
+
+    pub fn initial_state(&self) -> GameState {
+        GameRecord::new(self.setups, self.bag.clone()).initial_state()
+    }
+
+    /// The state at the cursor, replayed from the start.
+    pub fn state(&self) -> GameState {
+        let mut result = self.initial_state();
+        for m in self.moves() {
+            result.apply(m);
+        }
+        result
+    }
+
+    pub fn position(&self) -> Position {
+        Position::from_state(&self.state()).expect("Records only have standard units")
+    }
+
+    /// Returns false if already at the start.
+    pub fn back(&mut self) -> bool {
+        self.cursor.pop().is_some()
+    }
+
+    /// Follows the variation with the index, 0 being the main continuation. Returns false if
+    /// there's no such variation.
+    pub fn forward(&mut self, variation: usize) -> bool {
+        if variation < self.variations().len() {
+            self.cursor.push(variation);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn to_start(&mut self) -> () {
+        self.cursor.clear();
+    }
+
+    /// Follows the main continuation to the end of the current line.
+    pub fn to_end(&mut self) -> () {
+        while self.forward(0) {}
+    }
+
+    /// Plays m, which is assumed to be legal, after the cursor. It's a new variation, unless it was
+    /// already played here.
+    pub fn play(&mut self, m: Move) -> () {
+        let cursor = self.cursor.clone();
+        let children = self.children_mut(&cursor);
+        let index = match children.iter().position(|n| n.m == m) {
+            Some(i) => i,
+            None => {
+                children.push(Node::new(m));
+                children.len() - 1
+            }
+        };
+        self.cursor.push(index);
+    }
+
+    /// Makes the variation the cursor is in the main continuation where it branches off. Returns
+    /// false if the cursor is on the main line.
+    pub fn promote(&mut self) -> bool {
+        let k = match self.cursor.iter().rposition(|i| *i != 0) {
+            Some(k) => k,
+            None => return false,
+        };
+        let parent = self.cursor[..k].to_vec();
+        let index = self.cursor[k];
+        let children = self.children_mut(&parent);
+        let node = children.remove(index);
+        children.insert(0, node);
+        self.cursor[k] = 0;
+        true
+    }
+
+    /// Deletes the last move played and everything after it, moving the cursor back. Returns false
+    /// at the start.
+    pub fn delete(&mut self) -> bool {
+        match self.cursor.pop() {
+            Some(i) => {
+                let cursor = self.cursor.clone();
+                self.children_mut(&cursor).remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Comments on the last move played, or on the game at the start. Braces are dropped, since
+    /// they end comments.
+    pub fn set_comment(&mut self, text: &str) -> () {
+        let text: String = text.chars().filter(|c| *c != '{' && *c != '}').collect();
+        let cursor = self.cursor.clone();
+        match self.node_mut(&cursor) {
+            Some(node) => node.comment = text,
+            None => self.comment = text,
+        }
+    }
+
+    /// Returns false at the start, where there's no move to annotate.
+    pub fn set_eval(&mut self, eval: Option<i32>) -> bool {
+        let cursor = self.cursor.clone();
+        match self.node_mut(&cursor) {
+            Some(node) => {
+                node.eval = eval;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The comment in braces, without any braces of its own, since they end comments.
+fn comment_text(eval: Option<i32>, comment: &str) -> Option<String> {
+    let comment: String = comment.chars().filter(|c| *c != '{' && *c != '}').collect();
+    match (eval, comment.is_empty()) {
+        (None, true) => None,
+        (None, false) => Some(format!("{{{}}}", comment)),
+        (Some(e), true) => Some(format!("{{[%eval {}]}}", e)),
+        (Some(e), false) => Some(format!("{{[%eval {}] {}}}", e, comment)),
+    }
+}
+
+/// Moves are numbered before Player 1's, and before Player 2's when they don't follow Player 1's.
+fn write_move(words: &mut Vec<String>, node: &Node, ply: usize, numbered: bool) -> bool {
+    if ply.is_multiple_of(2) {
+        words.push(format!("{}.", ply / 2 + 1));
+    } else if numbered {
+        words.push(format!("{}...", ply / 2 + 1));
+    }
+    words.push(node.m.to_string());
+    let comment = comment_text(node.eval, &node.comment);
+    let has_comment = comment.is_some();
+    words.extend(comment);
+    has_comment
+}
+
+fn write_line(words: &mut Vec<String>, children: &[Node], ply: usize, numbered: bool) -> () {
+    let mut children = children;
+    let mut ply = ply;
+    let mut numbered = numbered;
+    while let Some(main) = children.first() {
+        numbered = write_move(words, main, ply, numbered);
+        for alternative in children[1..].iter() {
+            words.push("(".to_owned());
+            let commented = write_move(words, alternative, ply, true);
+            write_line(words, &alternative.children, ply + 1, commented);
+            words.push(")".to_owned());
+            numbered = true;
+        }
+        children = &main.children;
+        ply += 1;
+    }
+}
+
+impl fmt::Display for GameTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_tags(f, &self.setups, &self.bag, self.result)?;
+        let mut words: Vec<String> = comment_text(None, &self.comment).into_iter().collect();
+        write_line(&mut words, &self.children, 0, false);
+        words.push(self.result.notation().to_owned());
+        writeln!(f, "{}", words.join(" ").replace("( ", "(").replace(" )", ")"))
+    }
+}
+
+enum Token<'a> {
+    Word(&'a str),
+    Comment(&'a str),
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token<'_>>, RecordError> {
+    let mut result = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let length = match c {
+            '{' => {
+                let end = rest.find('}').ok_or_else(|| RecordError::InvalidMoveText("Unclosed comment".to_owned()))?;
+                result.push(Token::Comment(rest[1..end].trim()));
+                end + 1
+            }
+            '}' => return Err(RecordError::InvalidMoveText("Unmatched '}'".to_owned())),
+            '(' => {
+                result.push(Token::Open);
+                1
+            }
+            ')' => {
+                result.push(Token::Close);
+                1
+            }
+            _ => {
+                let end = rest.find(|c: char| c.is_whitespace() || "{}()".contains(c)).unwrap_or(rest.len());
+                result.push(Token::Word(&rest[..end]));
+                end
+            }
+        };
+        rest = rest[length..].trim_start();
+    }
+    Ok(result)
+}
+
+/// Splits the evaluation, if any, from the comment.
+fn parse_comment(text: &str) -> (Option<i32>, String) {
+    let eval = text.strip_prefix("[%eval ").and_then(|rest| rest.split_once(']'));
+    match eval.and_then(|(e, rest)| Some((e.trim().parse().ok()?, rest))) {
+        Some((e, rest)) => (Some(e), rest.trim().to_owned()),
+        None => (None, text.to_owned()),
+    }
+}
+
+impl FromStr for GameTree {
+    type Err = RecordError;
+
+    /// Like [GameRecord]'s, plus comments and variations. The cursor is at the start.
+    fn from_str(s: &str) -> Result<GameTree, RecordError> {
+        let (header, text) = parse_header(s)?;
+        let mut tree = GameTree::from_record(&header);
+        // The path to the last move read, and the positions along it.
+        let mut line: Vec<usize> = Vec::new();
+        let mut positions = vec![header.initial_position()];
+        let mut variations = Vec::new();
+        for token in tokenize(&text)? {
+            match token {
+                Token::Word(w) if w.ends_with('.') || GameResult::parse(w).is_some() => (),
+                Token::Word(w) => {
+                    let p = *positions.last().unwrap();
+                    let m = p.parse_move(w).ok_or_else(|| RecordError::IllegalMove {
+                        ply: line.len() + 1,
+                        text: w.to_owned(),
+                    })?;
+                    let children = tree.children_mut(&line);
+                    children.push(Node::new(m));
+                    line.push(children.len() - 1);
+                    positions.push(p.make(m));
+                }
+                Token::Comment(c) => {
+                    let (eval, comment) = parse_comment(c);
+                    match tree.node_mut(&line) {
+                        Some(node) => {
+                            node.eval = eval;
+                            node.comment = comment;
+                        }
+                        None => tree.comment = comment,
+                    }
+                }
+                Token::Open => {
+                    if line.is_empty() {
+                        return Err(RecordError::InvalidMoveText("Variation before any move".to_owned()));
+                    }
+                    variations.push((line.clone(), positions.clone()));
+                    line.pop();
+                    positions.pop();
+                }
+                Token::Close => {
+                    let (l, p) = variations
+                        .pop()
+                        .ok_or_else(|| RecordError::InvalidMoveText("Unbalanced ')'".to_owned()))?;
+                    line = l;
+                    positions = p;
+                }
+            }
+        }
+        if !variations.is_empty() {
+            return Err(RecordError::InvalidMoveText("Unbalanced '('".to_owned()));
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::token::Owner;
+
+    use super::*;
+
+    const TEXT: &str = "\
+[Player1 \"Left Sides\"]
+[Player2 \"Left Sides\"]
+[Bag \"Footman Footman\"]
+[Result \"*\"]
+
+{A short game} 1. c1-c2 {[%eval 25] Solid} (1. e1-e2 {Sharper} 1... b6-b5 (1... d6-d5)) (1. Footman@d2) 1... b6-b5 \
+2. c2-c4 {[%eval -10]} *
+";
+
+    fn tree() -> GameTree {
+        TEXT.parse().unwrap()
+    }
+
+    fn names(moves: &[Move]) -> Vec<String> {
+        moves.iter().map(|m| m.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_round_trips() {
+        let t = tree();
+        assert_eq!("A short game", t.comment);
+        assert_eq!(3, t.children.len());
+        assert_eq!(Some(25), t.children[0].eval);
+        assert_eq!("Sharper", t.children[1].comment);
+        assert_eq!(2, t.children[1].children.len());
+        assert_eq!(TEXT, t.to_string());
+    }
+
+    #[test]
+    fn records_are_main_lines() {
+        let t = tree();
+        let record = t.main_line();
+        assert_eq!(vec!["c1-c2", "b6-b5", "c2-c4"], names(&record.moves));
+        assert_eq!(Ok(record.clone()), TEXT.parse::<GameRecord>());
+        assert_eq!(record.to_string(), GameTree::from_record(&record).to_string());
+    }
+
+    #[test]
+    fn navigation_follows_variations() {
+        let mut t = tree();
+        assert!(!t.back());
+        assert!(t.forward(1));
+        assert!(t.forward(1));
+        assert_eq!(vec!["e1-e2", "d6-d5"], names(&t.moves()));
+        assert!(!t.forward(0));
+        t.to_start();
+        t.to_end();
+        assert_eq!(vec!["c1-c2", "b6-b5", "c2-c4"], names(&t.moves()));
+        assert!(t.back());
+        assert_eq!(Owner::Player1, t.state().current_player_turn);
+        assert_eq!(t.position(), Position::from_state(&t.state()).unwrap());
+    }
+
+    #[test]
+    fn play_adds_variations_once() {
+        let mut t = GameTree::new(
+            [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Left, FootmenSetup::Sides)],
+            vec![UnitId::Footman],
+        );
+        let p = t.position();
+        t.play(p.parse_move("c1-c2").unwrap());
+        t.back();
+        t.play(p.parse_move("Footman@d2").unwrap());
+        t.back();
+        t.play(p.parse_move("c1-c2").unwrap());
+        assert_eq!(2, t.children.len());
+        assert_eq!(&[0], t.cursor());
+        t.back();
+        t.forward(1);
+        assert_eq!(0, t.position().bag(Owner::Player1)[UnitId::Footman.index()]);
+    }
+
+    #[test]
+    fn promote_and_delete_edit_the_tree() {
+        let mut t = tree();
+        t.forward(1);
+        t.forward(1);
+        assert!(t.promote());
+        assert_eq!(&[1, 0], t.cursor());
+        assert_eq!("d6-d5", t.children[1].children[0].m.to_string());
+        assert!(t.promote());
+        assert_eq!(&[0, 0], t.cursor());
+        assert_eq!("e1-e2", t.main_line().moves[0].to_string());
+        assert!(!t.promote());
+        assert!(t.delete());
+        assert_eq!(&[0], t.cursor());
+        assert_eq!(1, t.children[0].children.len());
+        t.to_start();
+        assert!(!t.delete());
+    }
+
+    #[test]
+    fn comments_and_evaluations_are_set_on_the_current_move() {
+        let mut t = tree();
+        t.set_comment("Opening {notes}");
+        assert!(!t.set_eval(Some(5)));
+        t.forward(2);
+        t.set_comment("Summons early");
+        assert!(t.set_eval(Some(-40)));
+        assert_eq!("Opening notes", t.comment);
+        let text = t.to_string();
+        assert!(text.contains("{Opening notes} 1. c1-c2"));
+        assert!(text.contains("(1. Footman@d2 {[%eval -40] Summons early})"));
+        assert_eq!(Ok(t.clone()), text.parse().map(|mut p: GameTree| {
+            p.forward(2);
+            p
+        }));
+    }
+
+    #[test]
+    fn braces_in_comments_survive_round_trips() {
+        let mut t = tree();
+        t.comment = "{a } b}".to_owned();
+        t.children[0].comment = "}".to_owned();
+        let parsed: GameTree = t.to_string().parse().unwrap();
+        assert_eq!("a  b", parsed.comment);
+        assert_eq!("", parsed.children[0].comment);
+    }
+
+    #[test]
+    fn parse_reports_errors() {
+        assert_eq!(
+            Err(RecordError::IllegalMove { ply: 2, text: "c2-c3".to_owned() }),
+            TEXT.replace("(1... d6-d5)", "(1... c2-c3)").parse::<GameTree>(),
+        );
+        assert!(TEXT.replace("(1. Footman@d2)", "(1. Footman@d2").parse::<GameTree>().is_err());
+        assert!(TEXT.replace("{A short game}", "{A short game").parse::<GameTree>().is_err());
+        assert!(TEXT.replace("{A short game} 1.", "(1.").parse::<GameTree>().is_err());
+        assert_eq!(
+            Err(RecordError::InvalidMoveText("Unmatched '}'".to_owned())),
+            TEXT.replace("{A short game}", "{A short} game}").parse::<GameTree>(),
+        );
+    }
+}
@@ -1,14 +1,41 @@
 use std::collections::HashMap;
 
+use crate::hashmap;
 use crate::game::offset::{Centerable, Offsets, HorizontalOffset, VerticalOffset};
 use crate::game::token::{GameToken, OwnedToken, Owner, TokenAction, TokenSide};
 
-macro_rules! hashmap {
-    ($( $key: expr => $val: expr ),*) => {{
-         let mut map = ::std::collections::HashMap::new();
-         $( map.insert($key, $val); )*
-         map
-    }}
+pub const DUKE: &str = "Duke";
+pub const FOOTMAN: &str = "Footman";
+
+/// Identifies the standard units, e.g., for lookup tables indexed by unit.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum UnitId {
+    Duke,
+    Footman,
+}
+
+impl UnitId {
+    pub const COUNT: usize = 2;
+    pub const ALL: [UnitId; UnitId::COUNT] = [UnitId::Duke, UnitId::Footman];
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnitId::Duke => DUKE,
+            UnitId::Footman => FOOTMAN,
+        }
+    }
+    pub fn from_name(name: &str) -> Option<UnitId> {
+        UnitId::ALL.iter().find(|u| u.name() == name).cloned()
+    }
+    pub fn token(&self, owner: Owner) -> OwnedToken {
+        match self {
+            UnitId::Duke => duke(owner),
+            UnitId::Footman => footman(owner),
+        }
+    }
 }
 
 pub fn duke(owner: Owner) -> OwnedToken {
@@ -21,7 +48,7 @@ pub fn duke(owner: Owner) -> OwnedToken {
         token: GameToken::new(
             sliders(HorizontalOffset::Left),
             sliders(VerticalOffset::Top),
-            "Duke".to_owned(),
+            DUKE.to_owned(),
         ),
         owner,
     }
@@ -49,12 +76,13 @@ pub fn footman(owner: Owner) -> OwnedToken {
                 Offsets { x: HorizontalOffset::Right, y: VerticalOffset::Bottom },
                 Offsets::centered(VerticalOffset::FarTop),
             ])),
-            "Footman".to_owned(),
+            FOOTMAN.to_owned(),
         ),
         owner,
     }
 }
 
+#[cfg(test)]
 mod test {
     use super::*;
 
@@ -77,4 +105,12 @@ mod test {
     fn footman_side_2_active_does_not_panic() {
         footman(Owner::Player1).token.side_b.actions();
     }
+
+    #[test]
+    fn unit_id_round_trips_through_name() {
+        for u in UnitId::ALL.iter() {
+            assert_eq!(Some(*u), UnitId::from_name(u.name()));
+            assert_eq!(u.name(), u.token(Owner::Player1).token.name);
+        }
+    }
 }
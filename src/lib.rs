@@ -0,0 +1,13 @@
+// Side-effecting functions are explicitly marked with `-> ()`.
+#![allow(clippy::unused_unit)]
+
+extern crate fstrings;
+
+pub mod analysis;
+pub mod cli;
+pub mod common;
+pub mod engine;
+pub mod game;
+pub mod server;
+pub mod tui;
+pub mod view;
@@ -1,9 +1,62 @@
-extern crate fstrings;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::path::Path;
 
-mod common;
-mod game;
-mod view;
+use duke_rust::cli::args::flag_value;
+use duke_rust::cli::puzzle::PuzzleSession;
+use duke_rust::cli::session::Session;
+use duke_rust::engine::book::OpeningBook;
+use duke_rust::engine::tablebase::Tablebases;
+use duke_rust::game::clock::{GameClock, RealTime, TimeControl};
+use duke_rust::game::puzzle::Puzzle;
+use duke_rust::game::state::{DukeInitialLocation, FootmenSetup};
+use duke_rust::game::tree::GameTree;
+use duke_rust::game::units::UnitId;
+use duke_rust::tui::app::{run, App};
+use duke_rust::tui::backend::TerminalBackend;
 
-fn main() {
-    println!("Hello Rust");
+/// The number of Footmen in each player's bag.
+const BAG_SIZE: usize = 3;
+
+/// Usage: `duke_rust [--tablebases <dir>] [--book <file>] [--clock <control>] [--tui]`, where the time
+/// control is e.g. "5m", "5m+3s" or "10m/30sx5", see [TimeControl], or `duke_rust --puzzles <file>` to
+/// solve puzzles instead of playing. With `--tui`, the game is played full-screen rather than by
+/// typing commands.
+fn main() -> io::Result<()> {
+    let tree = GameTree::new(
+        [(DukeInitialLocation::Left, FootmenSetup::Sides), (DukeInitialLocation::Left, FootmenSetup::Sides)],
+        vec![UnitId::Footman; BAG_SIZE],
+    );
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(file) = flag_value(&args, "--puzzles", "a file") {
+        let puzzles = Puzzle::parse_all(&fs::read_to_string(file)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut session = PuzzleSession::new(puzzles).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let stdin = io::stdin();
+        return session.run(stdin.lock(), &mut io::stdout());
+    }
+    let mut session = Session::new(tree);
+    if let Some(dir) = flag_value(&args, "--tablebases", "a directory") {
+        session.tablebases = Some(Tablebases::read_dir(Path::new(dir))?);
+    }
+    if let Some(file) = flag_value(&args, "--book", "a file") {
+        session.book = Some(OpeningBook::read(&mut BufReader::new(File::open(file)?))?);
+        session.options.use_book = true;
+    }
+    if let Some(control) = flag_value(&args, "--clock", "a time control") {
+        let control: TimeControl = control
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut clock = GameClock::new(control, Box::new(RealTime::new()));
+        clock.start(session.state.current_player_turn);
+        session.clock = Some(clock);
+    }
+    if args.iter().any(|a| a == "--tui") {
+        return run(&mut App::new(session), &mut TerminalBackend::new()?);
+    }
+    let stdin = io::stdin();
+    session.run(stdin.lock(), &mut io::stdout())
 }
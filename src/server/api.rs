@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::cli::session::{print_moves, Session};
+use crate::common::json::Json;
+use crate::engine::search::SearchOptions;
+use crate::game::bitboard::square_at;
+use crate::game::clock::{GameClock, RealTime, TimeControl};
+use crate::game::legality::{check, Attempt, IllegalMove};
+use crate::game::moves::MoveKind;
+use crate::game::state::{DukeInitialLocation, FootmenSetup};
+use crate::game::token::Owner;
+use crate::game::tree::GameTree;
+use crate::game::units::UnitId;
+use crate::server::http::{Reply, Request, Response};
+use crate::server::spectate::{HostedGame, Spectator, CLOCK_INTERVAL};
+use crate::view::dumb_printer::print_score;
+
+/// The number of Footmen in each player's bag, unless the game is created with a bag.
+const BAG_SIZE: usize = 3;
+/// Larger bags are refused, there's no room on the board for them anyway.
+const MAX_BAG_SIZE: usize = 32;
+
+/// The games being played, by ID, each behind its own lock so a slow bot only holds up its game.
+///
+/// | Request | Body | Response |
+/// |---|---|---|
+/// | `GET /games` | | `{"games": [ids]}` |
+/// | `POST /games` | `{"bag": [units], "clock": control}`, both optional | the state, see below |
+/// | `GET /games/<id>` | | the state |
+/// | `POST /games/<id>/moves` | `{"move": text}` | `{"played": description, "state": state}` |
+/// | `POST /games/<id>/bot` | `{"depth": n, "play": bool}`, both optional | `{"move", "score", "played", "state"}` |
+/// | `POST /games/<id>/undo` | | the state |
+/// | `GET /games/<id>/watch` | | a stream of events, see [HostedGame] |
+///
+/// The state is `{"id", "fen", "to_move", "moves", "legal_moves", "bags", "outcome", "clock"}`,
+/// see [crate::game::position::Position::fen]. Moves are in [crate::game::moves::Move] notation,
+/// and summons are played as "@" followed by the square. Errors are `{"error": message}`, and
+/// illegal moves also have a `"reason"`, e.g., `{"kind": "Obstructed", "blocking": "c2"}`, see
+/// [IllegalMove].
+pub struct Api {
+    /// The search options of new games, whose depth is also the deepest the bot may be asked for.
+    pub options: SearchOptions,
+    games: Mutex<Games>,
+}
+
+struct Games {
+    next_id: u64,
+    games: HashMap<u64, Arc<Mutex<HostedGame>>>,
+}
+
+fn millis(d: Duration) -> Json {
+    Json::from(d.as_millis() as u64)
+}
+
+pub(crate) fn clock_json(clock: &GameClock) -> Json {
+    let mut fields: Vec<(&str, Json)> = Owner::ALL
+        .iter()
+        .map(|&owner| {
+            let time = clock.time(owner).map(|t| {
+                Json::object(vec![
+                    ("text", t.to_string().into()),
+                    ("main_ms", millis(t.main)),
+                    ("periods", t.periods.into()),
+                    ("period_ms", millis(t.period)),
+                ])
+            });
+            (if owner == Owner::Player1 { "Player1" } else { "Player2" }, time.unwrap_or(Json::Null))
+        })
+        .collect();
+    fields.push(("running", clock.running().map(|o| format!("{:?}", o)).into()));
+    Json::object(fields)
+}
+
+/// The state of the game at the tree's cursor, for the API's responses.
+pub(crate) fn state_json(id: u64, session: &Session) -> Result<Json, String> {
+    let p = session.position()?;
+    let outcome = session.outcome()?;
+    let legal_moves: Vec<String> = match outcome {
+        Some(_) => Vec::new(),
+        None => print_moves(&p).split_whitespace().map(str::to_owned).collect(),
+    };
+    let moves: Vec<String> = session.tree.moves().iter().map(|m| m.to_string()).collect();
+    Ok(Json::object(vec![
+        ("id", id.into()),
+        ("fen", p.fen().into()),
+        ("to_move", format!("{:?}", p.to_move).into()),
+        ("moves", moves.into()),
+        ("legal_moves", legal_moves.into()),
+        ("bags", Json::object(vec![
+            ("Player1", p.bag_size(Owner::Player1).into()),
+            ("Player2", p.bag_size(Owner::Player2).into()),
+        ])),
+        ("outcome", outcome.into()),
+        ("clock", session.clock.as_ref().map_or(Json::Null, clock_json)),
+    ]))
+}
+
+/// Locks the mutex even if a request panicked while holding it, which the server answered with
+/// an error, so the game behind it stays playable.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn reason_json(reason: &IllegalMove) -> Json {
+    let (kind, squares) = reason.parts();
+    let mut fields = vec![("kind", kind.into())];
+    fields.extend(squares.into_iter().map(|(name, c)| (name, c.to_string().into())));
+    Json::object(fields)
+}
+
+/// The body as an object, an empty one if there's no body.
+fn body(r: &Request) -> Result<Json, Response> {
+    if r.body.trim().is_empty() {
+        return Ok(Json::Object(Vec::new()));
+    }
+    match r.body.parse() {
+        Ok(json @ Json::Object(_)) => Ok(json),
+        Ok(_) => Err(Response::error(400, "Expected a JSON object")),
+        Err(e) => Err(Response::error(400, &e.to_string())),
+    }
+}
+
+fn invalid(field: &str) -> Response {
+    Response::error(400, &format!("Invalid value for '{}'", field))
+}
+
+fn state(id: u64, session: &Session) -> Response {
+    match state_json(id, session) {
+        Ok(json) => Response::ok(json),
+        Err(e) => Response::error(500, &e),
+    }
+}
+
+fn with_state(id: u64, session: &Session, mut fields: Vec<(&str, Json)>) -> Response {
+    match state_json(id, session) {
+        Ok(json) => {
+            fields.push(("state", json));
+            Response::ok(Json::object(fields))
+        }
+        Err(e) => Response::error(500, &e),
+    }
+}
+
+impl Default for Api {
+    fn default() -> Self {
+        Api::new(SearchOptions::default())
+    }
+}
+
+impl Api {
+    pub fn new(options: SearchOptions) -> Api {
+        Api { options, games: Mutex::new(Games { next_id: 1, games: HashMap::new() }) }
+    }
+
+    fn game(&self, id: &str) -> Result<(u64, Arc<Mutex<HostedGame>>), Response> {
+        let not_found = || Response::error(404, &format!("No game {}", id));
+        let id: u64 = id.parse().map_err(|_| not_found())?;
+        let games = lock(&self.games);
+        games.games.get(&id).map(|g| (id, Arc::clone(g))).ok_or_else(not_found)
+    }
+
+    fn create(&self, r: &Request) -> Result<Response, Response> {
+        let body = body(r)?;
+        let summonable = |n: &Json| n.as_str().and_then(UnitId::from_name).filter(|u| *u != UnitId::Duke);
+        let bag = match body.get("bag") {
+            None => vec![UnitId::Footman; BAG_SIZE],
+            Some(names) => names
+                .as_array()
+                .filter(|names| names.len() <= MAX_BAG_SIZE)
+                .and_then(|names| names.iter().map(summonable).collect())
+                .ok_or_else(|| invalid("bag"))?,
+        };
+        let setup = (DukeInitialLocation::Left, FootmenSetup::Sides);
+        let mut session = Session::new(GameTree::new([setup, setup], bag));
+        session.options = self.options;
+        if let Some(control) = body.get("clock") {
+            let control: TimeControl = control.as_str().and_then(|c| c.parse().ok()).ok_or_else(|| invalid("clock"))?;
+            let mut clock = GameClock::new(control, Box::new(RealTime::new()));
+            clock.start(session.state.current_player_turn);
+            session.clock = Some(clock);
+        }
+        let id = {
+            let mut games = lock(&self.games);
+            games.next_id += 1;
+            games.next_id - 1
+        };
+        let json = state_json(id, &session).map_err(|e| Response::error(500, &e))?;
+        lock(&self.games).games.insert(id, Arc::new(Mutex::new(HostedGame::new(session))));
+        Ok(Response { status: 201, body: json })
+    }
+
+    fn play(&self, id: u64, game: &mut HostedGame, text: &str) -> Response {
+        if let Ok(Some(outcome)) = game.session.outcome() {
+            return Response::error(409, &outcome);
+        }
+        match game.session.make_move(text) {
+            Ok(played) => {
+                game.moved();
+                with_state(id, &game.session, vec![("played", played.into())])
+            }
+            Err(e) => {
+                let reason = text.parse::<Attempt>().ok().and_then(|a| check(&game.session.state, &a).err());
+                let mut fields = vec![("error", e.as_str().into())];
+                fields.extend(reason.map(|r| ("reason", reason_json(&r))));
+                Response { status: 422, body: Json::object(fields) }
+            }
+        }
+    }
+
+    fn bot(&self, id: u64, game: &mut HostedGame, r: &Request) -> Result<Response, Response> {
+        let session = &mut game.session;
+        let body = body(r)?;
+        let allowed = |d: &u32| *d > 0 && *d <= self.options.depth;
+        let depth = body.get("depth").map(|d| d.as_u32().filter(allowed).ok_or_else(|| invalid("depth"))).transpose()?;
+        let play = body.get("play").map(|p| p.as_bool().ok_or_else(|| invalid("play"))).transpose()?.unwrap_or(true);
+        if let Ok(Some(outcome)) = session.outcome() {
+            return Err(Response::error(409, &outcome));
+        }
+        let result = session.search(depth).map_err(|e| Response::error(500, &e))?;
+        let m = result.best_move.ok_or_else(|| Response::error(409, "No legal moves"))?;
+        let text = match m.kind() {
+            MoveKind::Summon => format!("@{}", square_at(m.to())),
+            _ => m.to_string(),
+        };
+        let mut fields = vec![("move", text.clone().into()), ("score", print_score(result.score).into())];
+        if !play {
+            return Ok(with_state(id, session, fields));
+        }
+        let played = session.make_move(&text).map_err(|e| Response::error(500, &e))?;
+        fields.push(("played", played.into()));
+        game.moved();
+        Ok(with_state(id, &game.session, fields))
+    }
+
+    fn route(&self, r: &Request) -> Result<Response, Response> {
+        match (r.method.as_str(), r.segments().as_slice()) {
+            ("GET", ["games"]) => {
+                let mut ids: Vec<u64> = lock(&self.games).games.keys().copied().collect();
+                ids.sort_unstable();
+                Ok(Response::ok(Json::object(vec![("games", ids.into())])))
+            }
+            ("POST", ["games"]) => self.create(r),
+            (method, ["games", id, action @ ..]) => {
+                let (id, game) = self.game(id)?;
+                let mut game = lock(&game);
+                match (method, action) {
+                    ("GET", []) => Ok(state(id, &game.session)),
+                    ("POST", ["moves"]) => {
+                        let body = body(r)?;
+                        let text = body.get("move").and_then(Json::as_str).ok_or_else(|| invalid("move"))?;
+                        Ok(self.play(id, &mut game, text))
+                    }
+                    ("POST", ["bot"]) => self.bot(id, &mut game, r),
+                    ("POST", ["undo"]) => match game.session.undo() {
+                        Ok(()) => {
+                            game.undone();
+                            Ok(state(id, &game.session))
+                        }
+                        Err(e) => Err(Response::error(409, &e)),
+                    },
+                    (_, []) | (_, ["moves"]) | (_, ["bot"]) | (_, ["undo"]) | (_, ["watch"]) => {
+                        Err(Response::error(405, &format!("{} isn't allowed on {}", method, r.path)))
+                    }
+                    _ => Err(Response::error(404, &format!("No such resource {}", r.path))),
+                }
+            }
+            (method, ["games"]) => Err(Response::error(405, &format!("{} isn't allowed on {}", method, r.path))),
+            _ => Err(Response::error(404, &format!("No such resource {}", r.path))),
+        }
+    }
+
+    pub fn handle(&self, r: &Request) -> Response {
+        self.route(r).unwrap_or_else(|e| e)
+    }
+
+    /// Like [Api::handle], but spectators of `GET /games/<id>/watch` get a stream of events, see
+    /// [HostedGame].
+    pub fn reply(&self, r: &Request) -> Reply {
+        match (r.method.as_str(), r.segments().as_slice()) {
+            ("GET", ["games", id, "watch"]) => match self.game(id) {
+                Ok((_, game)) => Reply::Events(Box::new(Spectator::new(game, CLOCK_INTERVAL))),
+                Err(e) => e.into(),
+            },
+            _ => self.handle(r).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{Shutdown, TcpListener, TcpStream};
+    use std::thread;
+
+    use crate::server::http::serve;
+
+    use super::*;
+
+    fn api() -> Api {
+        Api::new(SearchOptions { depth: 2, table_size: 1 << 10, ..SearchOptions::default() })
+    }
+
+    fn request(api: &Api, method: &str, path: &str, body: &str) -> Response {
+        api.handle(&Request::new(method, path, body))
+    }
+
+    fn field<'a>(json: &'a Json, path: &[&str]) -> &'a Json {
+        path.iter().fold(json, |j, k| j.get(k).unwrap_or_else(|| panic!("No {} in {}", k, json)))
+    }
+
+    #[test]
+    fn creates_games_and_reports_their_state() {
+        let api = api();
+        let created = request(&api, "POST", "/games", "");
+        assert_eq!(201, created.status);
+        assert_eq!(Some(1), field(&created.body, &["id"]).as_u32());
+        assert_eq!(Some("1fdf2/6/6/6/6/2FDF1 1 FFF fff"), field(&created.body, &["fen"]).as_str());
+        assert_eq!(Some(3), field(&created.body, &["bags", "Player2"]).as_u32());
+        assert_eq!(&Json::Null, field(&created.body, &["outcome"]));
+        let legal = field(&created.body, &["legal_moves"]).to_string();
+        assert!(legal.contains("\"c1-c2\"") && legal.contains("\"@d2\""), "{}", legal);
+
+        let second = request(&api, "POST", "/games", r#"{"bag": ["Footman"], "clock": "5m+3s"}"#);
+        assert_eq!(Some("1fdf2/6/6/6/6/2FDF1 1 F f"), field(&second.body, &["fen"]).as_str());
+        assert!(field(&second.body, &["clock", "Player1", "main_ms"]).as_u32().unwrap() > 290_000);
+        assert_eq!(Some("Player1"), field(&second.body, &["clock", "running"]).as_str());
+
+        assert_eq!(r#"{"games":[1,2]}"#, request(&api, "GET", "/games", "").body.to_string());
+        assert_eq!(created.body, request(&api, "GET", "/games/1", "").body);
+    }
+
+    #[test]
+    fn plays_and_undoes_moves() {
+        let api = api();
+        request(&api, "POST", "/games", "");
+        let played = request(&api, "POST", "/games/1/moves", r#"{"move": "c1-c2"}"#);
+        assert_eq!(200, played.status);
+        assert_eq!(Some("Player1 played c1-c2"), field(&played.body, &["played"]).as_str());
+        assert_eq!(r#"["c1-c2"]"#, field(&played.body, &["state", "moves"]).to_string());
+        assert_eq!(Some("Player2"), field(&played.body, &["state", "to_move"]).as_str());
+
+        let summoned = request(&api, "POST", "/games/1/moves", r#"{"move": "@c5"}"#);
+        assert_eq!(Some("Player2 summoned Footman on c5"), field(&summoned.body, &["played"]).as_str());
+
+        let undone = request(&api, "POST", "/games/1/undo", "");
+        assert_eq!(r#"["c1-c2"]"#, field(&undone.body, &["moves"]).to_string());
+        request(&api, "POST", "/games/1/undo", "");
+        let error = request(&api, "POST", "/games/1/undo", "");
+        assert_eq!(409, error.status);
+        assert_eq!(r#"{"error":"No move to delete"}"#, error.body.to_string());
+    }
+
+    #[test]
+    fn asks_the_bot() {
+        let api = api();
+        request(&api, "POST", "/games", "");
+        let suggested = request(&api, "POST", "/games/1/bot", r#"{"depth": 1, "play": false}"#);
+        assert_eq!(200, suggested.status);
+        assert!(suggested.body.get("played").is_none());
+        assert_eq!(r#"[]"#, field(&suggested.body, &["state", "moves"]).to_string());
+
+        let played = request(&api, "POST", "/games/1/bot", "");
+        let m = field(&played.body, &["move"]).as_str().unwrap();
+        assert!(field(&played.body, &["played"]).as_str().unwrap().starts_with("Player1"));
+        assert_eq!(format!("[\"{}\"]", m).replace('@', "Footman@"), field(&played.body, &["state", "moves"]).to_string());
+        assert!(field(&played.body, &["score"]).as_str().is_some());
+    }
+
+    #[test]
+    fn recovers_games_whose_requests_panicked() {
+        let api = api();
+        request(&api, "POST", "/games", "");
+        let (_, game) = api.game("1").unwrap();
+        let poisoner = thread::spawn(move || {
+            let _game = game.lock().unwrap();
+            panic!("The request failed");
+        });
+        assert!(poisoner.join().is_err());
+        assert_eq!(200, request(&api, "GET", "/games/1", "").status);
+        assert_eq!(200, request(&api, "POST", "/games/1/moves", r#"{"move": "c1-c2"}"#).status);
+    }
+
+    #[test]
+    fn returns_errors_as_json() {
+        let api = api();
+        request(&api, "POST", "/games", "");
+        let cases = [
+            ("GET", "/games/7", "", 404, "No game 7"),
+            ("GET", "/nowhere", "", 404, "No such resource /nowhere"),
+            ("DELETE", "/games/1", "", 405, "DELETE isn't allowed on /games/1"),
+            ("POST", "/games/1/moves", "{\"move\"", 400, "Unexpected end of JSON"),
+            ("POST", "/games/1/moves", "[]", 400, "Expected a JSON object"),
+            ("POST", "/games/1/moves", "{}", 400, "Invalid value for 'move'"),
+            ("POST", "/games/1/bot", r#"{"depth": -1}"#, 400, "Invalid value for 'depth'"),
+            ("POST", "/games/1/bot", r#"{"depth": 3}"#, 400, "Invalid value for 'depth'"),
+            ("POST", "/games/1/bot", r#"{"depth": 4000000000}"#, 400, "Invalid value for 'depth'"),
+            ("POST", "/games", r#"{"bag": ["Knight"]}"#, 400, "Invalid value for 'bag'"),
+            ("POST", "/games", r#"{"bag": ["Duke"]}"#, 400, "Invalid value for 'bag'"),
+            ("POST", "/games", &format!(r#"{{"bag": [{}]}}"#, vec![r#""Footman""#; 256].join(",")), 400, "Invalid value for 'bag'"),
+            ("POST", "/games", r#"{"clock": "soon"}"#, 400, "Invalid value for 'clock'"),
+            ("POST", "/games/1/moves", r#"{"move": "d1-d2"}"#, 422, "Illegal move 'd1-d2': "),
+        ];
+        for (method, path, body, status, error) in cases.iter() {
+            let response = request(&api, method, path, body);
+            assert_eq!(*status, response.status, "{} {}", method, path);
+            let message = field(&response.body, &["error"]).as_str().unwrap();
+            assert!(message.starts_with(error), "{}", message);
+        }
+        assert_eq!(r#"{"games":[1]}"#, request(&api, "GET", "/games", "").body.to_string());
+        let reasons = [
+            ("d1-d2", r#"{"kind":"NotOnTokenSide","from":"d1","to":"d2"}"#),
+            ("c3-c4", r#"{"kind":"NoToken","square":"c3"}"#),
+        ];
+        for (m, reason) in reasons.iter() {
+            let illegal = request(&api, "POST", "/games/1/moves", &format!(r#"{{"move": "{}"}}"#, m));
+            assert_eq!(*reason, field(&illegal.body, &["reason"]).to_string());
+        }
+    }
+
+    #[test]
+    fn serves_localhost_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let api = api();
+        thread::spawn(move || serve(listener, move |r: &Request| api.reply(r)));
+        let send = |text: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(text.as_bytes()).unwrap();
+            stream.shutdown(Shutdown::Write).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        assert!(send("POST /games HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 201 Created\r\n"));
+        let body = r#"{"move":"c1-c3"}"#;
+        let response = send(&format!("POST /games/1/moves HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body));
+        assert!(response.starts_with("HTTP/1.1 422 Unprocessable Entity\r\n"), "{}", response);
+        assert!(response.contains(r#"{"error":"Illegal move 'c1-c3': "#), "{}", response);
+    }
+
+    #[test]
+    fn streams_to_spectators() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let api = api();
+        request(&api, "POST", "/games", "");
+        request(&api, "POST", "/games/1/moves", r#"{"move": "c1-c2"}"#);
+        thread::spawn(move || serve(listener, move |r: &Request| api.reply(r)));
+
+        let mut watcher = TcpStream::connect(address).unwrap();
+        watcher.write_all(b"GET /games/1/watch HTTP/1.1\r\n\r\n").unwrap();
+        let mut lines = BufReader::new(watcher).lines().map(|l| l.unwrap());
+        assert_eq!("HTTP/1.1 200 OK", lines.next().unwrap());
+        let mut data = lines.by_ref().filter(|l| l.starts_with("data: "));
+        assert!(data.next().unwrap().contains(r#""moves":["c1-c2"]"#));
+
+        let body = r#"{"move":"b6-b5"}"#;
+        let mut player = TcpStream::connect(address).unwrap();
+        write!(player, "POST /games/1/moves HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        player.read_to_string(&mut String::new()).unwrap();
+        let moved = data.next().unwrap();
+        assert!(moved.starts_with(r#"data: {"move":"b6-b5","squares":{"b5":"f'","b6":null}"#), "{}", moved);
+
+        let mut missing = TcpStream::connect(address).unwrap();
+        missing.write_all(b"GET /games/7/watch HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        missing.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
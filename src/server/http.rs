@@ -0,0 +1,295 @@
+use std::fmt;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::common::json::Json;
+
+/// Larger bodies are refused, nothing the API takes comes close.
+const MAX_BODY: usize = 64 * 1024;
+/// How long a client may take to send its request before the connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Request {
+    pub method: String,
+    /// Without the query string.
+    pub path: String,
+    pub body: String,
+}
+
+impl Request {
+    pub fn new(method: &str, path: &str, body: &str) -> Request {
+        Request { method: method.to_owned(), path: path.to_owned(), body: body.to_owned() }
+    }
+
+    /// The path's segments, e.g., ["games", "1"] for "/games/1/".
+    pub fn segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|s| !s.is_empty()).collect()
+    }
+}
+
+/// Always JSON.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    pub fn ok(body: Json) -> Response {
+        Response { status: 200, body }
+    }
+
+    /// The body is `{"error": message}`.
+    pub fn error(status: u16, message: &str) -> Response {
+        Response { status, body: Json::object(vec![("error", message.into())]) }
+    }
+}
+
+/// A Server-Sent Event, written as "event: <name>" and "data: <data>" lines, or a ": ping"
+/// comment when it has no name.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Event {
+    pub name: String,
+    pub data: Json,
+}
+
+impl Event {
+    pub fn new(name: &str, data: Json) -> Event {
+        Event { name: name.to_owned(), data }
+    }
+
+    /// A comment clients ignore, to find out whether they're still there.
+    pub fn ping() -> Event {
+        Event::new("", Json::Null)
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.name.is_empty() {
+            return write!(f, ": ping\n\n");
+        }
+        write!(f, "event: {}\ndata: {}\n\n", self.name, self.data)
+    }
+}
+
+/// What a handler answers with: a single response, or events until the iterator ends or the
+/// client goes away.
+pub enum Reply {
+    Response(Response),
+    Events(Box<dyn Iterator<Item = Event> + Send>),
+}
+
+impl From<Response> for Reply {
+    fn from(r: Response) -> Reply {
+        Reply::Response(r)
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    Io(io::Error),
+    Malformed(String),
+    TooLarge(usize),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::Io(e) => write!(f, "{}", e),
+            HttpError::Malformed(line) => write!(f, "Malformed request '{}'", line),
+            HttpError::TooLarge(size) => write!(f, "Request body of {} bytes is too large", size),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl From<io::Error> for HttpError {
+    fn from(e: io::Error) -> HttpError {
+        HttpError::Io(e)
+    }
+}
+
+/// Reads the request line, the headers, of which only Content-Length matters, and the body.
+pub fn read_request<R: BufRead>(input: &mut R) -> Result<Request, HttpError> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    let mut words = line.split_whitespace();
+    let (method, target) = match (words.next(), words.next(), words.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => (method, target),
+        _ => return Err(HttpError::Malformed(line.trim_end().to_owned())),
+    };
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| HttpError::Malformed(header.trim_end().to_owned()))?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(HttpError::TooLarge(length));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    let path = target.split('?').next().unwrap_or_default();
+    Ok(Request::new(method, path, &String::from_utf8_lossy(&body)))
+}
+
+pub fn write_response<W: Write>(output: &mut W, response: &Response) -> io::Result<()> {
+    let body = response.body.to_string();
+    write!(
+        output,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        body.len(),
+        body,
+    )?;
+    output.flush()
+}
+
+/// Writes the events as an event stream, flushing after each one.
+pub fn write_events<W: Write, I: Iterator<Item = Event>>(output: &mut W, events: I) -> io::Result<()> {
+    write!(
+        output,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+    )?;
+    output.flush()?;
+    for event in events {
+        write!(output, "{}", event)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+fn handle_connection<H: Fn(&Request) -> Reply>(stream: TcpStream, handler: &H) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let reply = match read_request(&mut reader) {
+        Ok(request) => catch_unwind(AssertUnwindSafe(|| handler(&request)))
+            .unwrap_or_else(|_| Response::error(500, "The server failed to handle the request").into()),
+        Err(HttpError::Io(e)) => return Err(e),
+        Err(e @ HttpError::TooLarge(_)) => Response::error(413, &e.to_string()).into(),
+        Err(e) => Response::error(400, &e.to_string()).into(),
+    };
+    match reply {
+        Reply::Response(response) => write_response(&mut &stream, &response),
+        Reply::Events(events) => write_events(&mut &stream, events),
+    }
+}
+
+/// Answers every connection on its own thread, one request per connection, forever. Connections
+/// which fail to be accepted are only logged.
+pub fn serve<H: Fn(&Request) -> Reply + Send + Sync + 'static>(listener: TcpListener, handler: H) -> () {
+    let handler = Arc::new(handler);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let handler = Arc::clone(&handler);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, handler.as_ref()) {
+                eprintln!("{}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Read;
+    use std::net::Shutdown;
+
+    use super::*;
+
+    #[test]
+    fn reads_requests() {
+        let text = "POST /games/1/moves?x=1 HTTP/1.1\r\nHost: localhost\r\ncontent-length: 17\r\n\r\n{\"move\":\"c1-c2\"}\n";
+        let request = read_request(&mut text.as_bytes()).unwrap();
+        assert_eq!(Request::new("POST", "/games/1/moves", "{\"move\":\"c1-c2\"}\n"), request);
+        assert_eq!(vec!["games", "1", "moves"], request.segments());
+
+        assert!(matches!(read_request(&mut "nonsense\r\n\r\n".as_bytes()), Err(HttpError::Malformed(_))));
+        let huge = format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY + 1);
+        assert!(matches!(read_request(&mut huge.as_bytes()), Err(HttpError::TooLarge(_))));
+    }
+
+    #[test]
+    fn writes_responses() {
+        let mut output = Vec::new();
+        write_response(&mut output, &Response::error(404, "No game 7")).unwrap();
+        assert_eq!(
+            "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 21\r\n\
+Connection: close\r\n\r\n{\"error\":\"No game 7\"}",
+            String::from_utf8(output).unwrap(),
+        );
+    }
+
+    #[test]
+    fn writes_events() {
+        let mut output = Vec::new();
+        let events = vec![Event::new("move", Json::object(vec![("ply", 1u32.into())])), Event::new("clock", Json::Null), Event::ping()];
+        write_events(&mut output, events.into_iter()).unwrap();
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n\
+event: move\ndata: {\"ply\":1}\n\nevent: clock\ndata: null\n\n: ping\n\n",
+            String::from_utf8(output).unwrap(),
+        );
+    }
+
+    fn get_games<H: Fn(&Request) -> Reply + Send + Sync + 'static>(handler: H) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, handler));
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /games HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        stream.shutdown(Shutdown::Write).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_localhost() {
+        let response = get_games(|r: &Request| Response::ok(r.path.as_str().into()).into());
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n\"/games\""));
+    }
+
+    #[test]
+    fn answers_panics_with_errors() {
+        let response = get_games(|_: &Request| panic!("Handler failed"));
+        assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"), "{}", response);
+        assert!(response.ends_with("{\"error\":\"The server failed to handle the request\"}"), "{}", response);
+    }
+}
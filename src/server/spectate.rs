@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::cli::session::Session;
+use crate::common::json::Json;
+use crate::game::position::fen_token;
+use crate::game::token::Owner;
+use crate::server::api::{clock_json, lock};
+use crate::server::http::Event;
+
+/// How often spectators get the clocks while one is running.
+pub const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The occupied squares, e.g., "c1" => "F", as [crate::game::position::Position::fen] writes
+/// tokens.
+type Squares = BTreeMap<String, String>;
+
+/// A game on the server, and the channels to whoever watches it.
+///
+/// Spectators only see what both players see: the board, the moves, how many tokens are left in
+/// each bag but not which, the discard piles and the clocks. They get a `snapshot` event when
+/// they join, then `move` and `undo` events with just the squares which changed, and `clock`
+/// events every [CLOCK_INTERVAL] while a clock runs.
+pub struct HostedGame {
+    pub session: Session,
+    spectators: Vec<Sender<Event>>,
+    /// The squares as last sent to the spectators.
+    squares: Squares,
+}
+
+fn squares(session: &Session) -> Squares {
+    let p = match session.position() {
+        Ok(p) => p,
+        Err(_) => return Squares::new(),
+    };
+    session
+        .state
+        .board
+        .iter()
+        .filter_map(|(c, _)| p.board.get(c).map(|(owner, unit, side)| (c.to_string(), fen_token(owner, unit, side))))
+        .collect()
+}
+
+fn squares_json(squares: &BTreeMap<String, Option<String>>) -> Json {
+    Json::Object(squares.iter().map(|(k, v)| (k.clone(), v.clone().into())).collect())
+}
+
+impl HostedGame {
+    pub fn new(session: Session) -> HostedGame {
+        let squares = squares(&session);
+        HostedGame { session, spectators: Vec::new(), squares }
+    }
+
+    pub fn spectators(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// The public state shared by every event: whose turn it is, the bag sizes, the discard piles,
+    /// the outcome and the clocks.
+    fn public_fields(&self) -> Vec<(&'static str, Json)> {
+        let gs = &self.session.state;
+        let names = |owner: Owner| -> Vec<String> {
+            gs.discard(owner).existing().iter().map(|t| t.name.clone()).collect()
+        };
+        vec![
+            ("ply", (self.session.tree.cursor().len() as u32).into()),
+            ("to_move", format!("{:?}", gs.current_player_turn).into()),
+            ("bags", Json::object(vec![
+                ("Player1", (gs.bag(Owner::Player1).remaining().len() as u32).into()),
+                ("Player2", (gs.bag(Owner::Player2).remaining().len() as u32).into()),
+            ])),
+            ("discards", Json::object(vec![
+                ("Player1", names(Owner::Player1).into()),
+                ("Player2", names(Owner::Player2).into()),
+            ])),
+            ("outcome", self.session.outcome().ok().flatten().into()),
+            ("clock", self.session.clock.as_ref().map_or(Json::Null, clock_json)),
+        ]
+    }
+
+    /// Everything a spectator needs to show the game from now on.
+    pub fn snapshot(&self) -> Event {
+        let moves: Vec<String> = self.session.tree.moves().iter().map(|m| m.to_string()).collect();
+        let all = self.squares.iter().map(|(k, v)| (k.clone(), Some(v.clone()))).collect();
+        let board = self.session.position().map(|p| p.fen().split(' ').next().unwrap().to_owned()).ok();
+        let mut fields = vec![("board", board.into()), ("squares", squares_json(&all)), ("moves", moves.into())];
+        fields.extend(self.public_fields());
+        Event::new("snapshot", Json::object(fields))
+    }
+
+    /// Receives a snapshot first, then every event until the game is dropped.
+    pub fn watch(&mut self) -> Receiver<Event> {
+        let (sender, receiver) = channel();
+        // The receiver is still here, so this can't fail.
+        sender.send(self.snapshot()).unwrap();
+        self.spectators.push(sender);
+        receiver
+    }
+
+    /// Sends the event to every spectator, forgetting those who left.
+    fn broadcast(&mut self, event: Event) -> () {
+        self.spectators.retain(|s| s.send(event.clone()).is_ok());
+    }
+
+    /// The squares which changed since the last event, [None] for those emptied.
+    fn changed_squares(&mut self) -> BTreeMap<String, Option<String>> {
+        let now = squares(&self.session);
+        let mut changed: BTreeMap<String, Option<String>> = self
+            .squares
+            .keys()
+            .filter(|k| !now.contains_key(*k))
+            .map(|k| (k.clone(), None))
+            .collect();
+        changed.extend(now.iter().filter(|(k, v)| self.squares.get(*k) != Some(v)).map(|(k, v)| (k.clone(), Some(v.clone()))));
+        self.squares = now;
+        changed
+    }
+
+    /// Tells the spectators about the move just played.
+    pub fn moved(&mut self) -> () {
+        let m = self.session.tree.current().map(|n| n.m.to_string());
+        let mut fields = vec![("move", m.into()), ("squares", squares_json(&self.changed_squares()))];
+        fields.extend(self.public_fields());
+        self.broadcast(Event::new("move", Json::object(fields)));
+    }
+
+    /// Tells the spectators a move was taken back.
+    pub fn undone(&mut self) -> () {
+        let mut fields = vec![("squares", squares_json(&self.changed_squares()))];
+        fields.extend(self.public_fields());
+        self.broadcast(Event::new("undo", Json::object(fields)));
+    }
+
+    /// The clocks and, should a flag have fallen, the outcome.
+    fn clock_event(&self) -> Option<Event> {
+        let clock = self.session.clock.as_ref().filter(|c| c.running().is_some())?;
+        Some(Event::new(
+            "clock",
+            Json::object(vec![("clock", clock_json(clock)), ("outcome", self.session.outcome().ok().flatten().into())]),
+        ))
+    }
+}
+
+/// The events of a spectator, with the clocks while they run and nothing else happens, or a
+/// [Event::ping] when there's no clock, until the game ends.
+pub struct Spectator {
+    game: Arc<Mutex<HostedGame>>,
+    events: Receiver<Event>,
+    interval: Duration,
+    over: bool,
+}
+
+impl Spectator {
+    pub fn new(game: Arc<Mutex<HostedGame>>, interval: Duration) -> Spectator {
+        let events = lock(&game).watch();
+        Spectator { game, events, interval, over: false }
+    }
+}
+
+impl Iterator for Spectator {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if self.over {
+            return None;
+        }
+        let event = match self.events.recv_timeout(self.interval) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => lock(&self.game).clock_event().unwrap_or_else(Event::ping),
+            Err(RecvTimeoutError::Disconnected) => return None,
+        };
+        self.over = event.data.get("outcome").is_some_and(|o| *o != Json::Null);
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{TcpListener, TcpStream};
+
+    use crate::game::clock::{GameClock, ManualTime};
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::tree::GameTree;
+    use crate::game::units::UnitId;
+    use crate::server::http::write_events;
+
+    use super::*;
+
+    fn game() -> HostedGame {
+        let setup = (DukeInitialLocation::Left, FootmenSetup::Sides);
+        HostedGame::new(Session::new(GameTree::new([setup, setup], vec![UnitId::Footman; 3])))
+    }
+
+    fn play(game: &mut HostedGame, text: &str) -> () {
+        game.session.make_move(text).unwrap();
+        game.moved();
+    }
+
+    #[test]
+    fn snapshots_hide_the_bags() {
+        let mut game = game();
+        play(&mut game, "c1-c2");
+        let snapshot = game.watch().recv().unwrap();
+        assert_eq!("snapshot", snapshot.name);
+        let data = snapshot.data.to_string();
+        assert!(data.starts_with(r#"{"board":"1fdf2/6/6/6/2F'3/3DF1","squares":{"b6":"f","c2":"F'","c6":"d""#), "{}", data);
+        assert!(data.contains(r#""moves":["c1-c2"],"ply":1,"to_move":"Player2","bags":{"Player1":3,"Player2":3}"#), "{}", data);
+        assert!(!data.contains("Footman\"") && !data.contains("FFF"), "{}", data);
+    }
+
+    #[test]
+    fn spectators_get_diffs() {
+        let mut game = game();
+        let events = game.watch();
+        let late = {
+            play(&mut game, "c1-c2");
+            game.watch()
+        };
+        play(&mut game, "@c5");
+        game.session.undo().unwrap();
+        game.undone();
+
+        let names: Vec<String> = events.try_iter().map(|e| e.name).collect();
+        assert_eq!(vec!["snapshot", "move", "move", "undo"], names);
+        let late: Vec<Event> = late.try_iter().collect();
+        assert_eq!("snapshot", late[0].name);
+        assert_eq!(r#"{"c5":"f"}"#, late[1].data.get("squares").unwrap().to_string());
+        assert_eq!(Some("Footman@c5"), late[1].data.get("move").and_then(Json::as_str));
+        assert_eq!(Some(2), late[1].data.get("bags").and_then(|b| b.get("Player2")).and_then(Json::as_u32));
+        assert_eq!(r#"{"c5":null}"#, late[2].data.get("squares").unwrap().to_string());
+    }
+
+    #[test]
+    fn spectators_who_left_are_forgotten() {
+        let mut game = game();
+        drop(game.watch());
+        let _watching = game.watch();
+        assert_eq!(2, game.spectators());
+        play(&mut game, "c1-c2");
+        assert_eq!(1, game.spectators());
+    }
+
+    #[test]
+    fn streams_clocks_until_the_game_ends() {
+        let mut game = game();
+        let time = ManualTime::new();
+        let mut clock = GameClock::new("1m".parse().unwrap(), Box::new(time.clone()));
+        clock.start(Owner::Player1);
+        game.session.clock = Some(clock);
+        let game = Arc::new(Mutex::new(game));
+        let mut spectator = Spectator::new(Arc::clone(&game), Duration::from_millis(1));
+        assert_eq!("snapshot", spectator.next().unwrap().name);
+
+        time.advance(Duration::from_secs(30));
+        let tick = spectator.next().unwrap();
+        assert_eq!("clock", tick.name);
+        assert_eq!(Some("0:30.0"), tick.data.get("clock").and_then(|c| c.get("Player1")).and_then(|t| t.get("text")).and_then(Json::as_str));
+
+        time.advance(Duration::from_secs(31));
+        let flagged = spectator.next().unwrap();
+        assert_eq!(Some("Player1 lost on time, Player2 wins"), flagged.data.get("outcome").and_then(Json::as_str));
+        assert_eq!(None, spectator.next());
+    }
+
+    #[test]
+    fn pings_until_the_spectator_leaves() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+        let mut spectator = Spectator::new(Arc::new(Mutex::new(game())), Duration::from_millis(1));
+        assert_eq!("snapshot", spectator.next().unwrap().name);
+        assert_eq!(Some(Event::ping()), spectator.next());
+        drop(client);
+        assert!(write_events(&mut server, spectator).is_err());
+    }
+}
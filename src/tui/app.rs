@@ -0,0 +1,235 @@
+use std::io;
+
+use crate::analysis::destinations::{destinations, Destination};
+use crate::cli::session::Session;
+use crate::common::coordinates::Coordinates;
+use crate::game::token::{Owner, TokenAction};
+use crate::tui::backend::{Backend, Key};
+use crate::tui::screen::Screen;
+use crate::view::dumb_printer::{destination_marks, print_marked_board, print_token};
+
+const KEYS: &str = "Arrows: move cursor  Enter: select/play  Esc: deselect  q: quit";
+/// The column of the hovered token's card, right of the board.
+const CARD_X: u16 = 27;
+/// The column of the move list, right of the card.
+const MOVES_X: u16 = 52;
+
+/// A game played by moving a cursor over the board, and pressing Enter on a token and then on
+/// one of its destinations, or on an empty square to summon there.
+pub struct App {
+    pub session: Session,
+    pub cursor: Coordinates,
+    /// The token whose destinations are shown.
+    pub selected: Option<Coordinates>,
+    /// The last move, or why the last key did nothing.
+    pub message: String,
+}
+
+impl App {
+    pub fn new(session: Session) -> App {
+        App { session, cursor: Coordinates { x: 0, y: 0 }, selected: None, message: String::new() }
+    }
+
+    fn destinations(&self) -> Vec<Destination> {
+        self.selected.and_then(|s| destinations(&self.session.state, s).ok()).unwrap_or_default()
+    }
+
+    /// The move text [Session::make_move] expects for playing the selected token to c.
+    fn move_text(&self, selected: Coordinates, c: Coordinates) -> Option<String> {
+        let d = self.destinations().into_iter().find(|d| d.target == c)?;
+        Some(match (d.action, d.commanded) {
+            (TokenAction::Command, Some(from)) => format!("{}:{}-{}", selected, from, c),
+            (TokenAction::Strike, _) => format!("{}x{}", selected, c),
+            _ => format!("{}-{}", selected, c),
+        })
+    }
+
+    fn play(&mut self, text: &str) -> () {
+        self.selected = None;
+        self.message = match self.session.make_move(text) {
+            Ok(done) => match self.session.outcome() {
+                Ok(Some(outcome)) => format!("{}. {}", done, outcome),
+                _ => done,
+            },
+            Err(e) => e,
+        };
+    }
+
+    fn enter(&mut self) -> () {
+        let c = self.cursor;
+        match self.selected {
+            Some(s) if s == c => self.selected = None,
+            Some(s) => match self.move_text(s, c) {
+                Some(text) => self.play(&text),
+                None => self.message = format!("{} can't reach {}", s, c),
+            },
+            None if self.session.state.board.get(c).is_none() => self.play(&format!("@{}", c)),
+            None => match destinations(&self.session.state, c) {
+                Ok(_) => {
+                    self.selected = Some(c);
+                    self.message.clear();
+                }
+                Err(reason) => self.message = reason.to_string(),
+            },
+        }
+    }
+
+    /// Handles the key, false once the app should quit.
+    pub fn handle(&mut self, key: Key) -> bool {
+        let board = &self.session.state.board;
+        let c = &mut self.cursor;
+        match key {
+            Key::Up if c.y + 1 < board.height() => c.y += 1,
+            Key::Down if c.y > 0 => c.y -= 1,
+            Key::Right if c.x + 1 < board.width() => c.x += 1,
+            Key::Left if c.x > 0 => c.x -= 1,
+            Key::Enter => self.enter(),
+            Key::Escape => self.selected = None,
+            Key::Char('q') => return false,
+            _ => (),
+        }
+        true
+    }
+
+    /// The moves so far, numbered in pairs, as many of the latest as fit in height lines.
+    fn print_moves(&self, height: usize) -> String {
+        let moves = self.session.tree.moves();
+        let mut lines: Vec<String> = moves
+            .chunks(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                let printed: Vec<String> = pair.iter().map(|m| m.to_string()).collect();
+                format!("{}. {}", i + 1, printed.join(" "))
+            })
+            .collect();
+        let skipped = lines.len().saturating_sub(height);
+        lines.drain(..skipped);
+        lines.join("\n")
+    }
+
+    fn print_bags(&self) -> String {
+        let gs = &self.session.state;
+        [Owner::Player1, Owner::Player2]
+            .iter()
+            .map(|&owner| {
+                let discarded: Vec<&str> = gs.discard(owner).existing().iter().map(|t| t.name.as_str()).collect();
+                format!("{:?}: bag {}, discard [{}]", owner, gs.bag(owner).remaining().len(), discarded.join(" "))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn render(&self, width: u16, height: u16) -> Screen {
+        let mut screen = Screen::new(width, height);
+        let gs = &self.session.state;
+        screen.put(0, 0, &format!("{:?} to move", gs.current_player_turn));
+        let list = self.destinations();
+        let board = print_marked_board(gs, |c| {
+            if c == self.cursor {
+                ('>', '<')
+            } else {
+                self.selected.and_then(|s| destination_marks(s, &list, c)).unwrap_or((' ', ' '))
+            }
+        });
+        screen.put(0, 1, &board);
+        let below = 2 + 2 * gs.board.height();
+        if let Ok(Some(t)) = gs.board.try_get(self.cursor) {
+            screen.put(CARD_X, 0, &format!("{} ({:?})", t.token.name, t.owner));
+            screen.put(CARD_X, 1, &print_token(t));
+        }
+        screen.put(0, below, &self.print_bags());
+        screen.put(MOVES_X, 0, "Moves");
+        screen.put(MOVES_X, 1, &self.print_moves(usize::from(height.saturating_sub(4))));
+        screen.put(0, height.saturating_sub(2), &self.message);
+        screen.put(0, height.saturating_sub(1), KEYS);
+        screen
+    }
+}
+
+/// Draws the app after every key, until it quits or the keys run out.
+pub fn run<B: Backend>(app: &mut App, backend: &mut B) -> io::Result<()> {
+    loop {
+        let (width, height) = backend.size()?;
+        backend.draw(&app.render(width, height))?;
+        match backend.read_key()? {
+            Some(key) if app.handle(key) => (),
+            _ => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::tree::GameTree;
+    use crate::game::units::UnitId;
+    use crate::tui::backend::TestBackend;
+
+    use super::*;
+
+    fn app() -> App {
+        let setup = (DukeInitialLocation::Left, FootmenSetup::Sides);
+        App::new(Session::new(GameTree::new([setup, setup], vec![UnitId::Footman; 3])))
+    }
+
+    fn play(keys: &[Key]) -> (App, TestBackend) {
+        let mut app = app();
+        let mut backend = TestBackend::new(80, 24, keys);
+        run(&mut app, &mut backend).unwrap();
+        (app, backend)
+    }
+
+    #[test]
+    fn renders_board_bags_and_help() {
+        let (_, backend) = play(&[]);
+        let screen = backend.last();
+        assert_eq!(1, backend.frames.len());
+        assert_eq!("Player1 to move", &screen.lines()[0][..15]);
+        assert!(screen.contains("|> <|"));
+        assert!(screen.contains("Player1: bag 3, discard []"));
+        assert!(screen.contains("Player2: bag 3, discard []"));
+        assert_eq!(KEYS, screen.lines()[23]);
+    }
+
+    #[test]
+    fn shows_the_hovered_token() {
+        let (app, backend) = play(&[Key::Right, Key::Right]);
+        assert_eq!("c1", app.cursor.to_string());
+        assert!(backend.last().contains("Footman (Player1)"));
+    }
+
+    #[test]
+    fn plays_with_cursor_and_enter() {
+        use Key::*;
+        let (app, backend) = play(&[Right, Right, Enter, Up, Enter]);
+        assert_eq!(None, app.selected);
+        assert_eq!("Player1 played c1-c2", app.message);
+        let screen = backend.last();
+        assert!(screen.contains("Player2 to move"));
+        assert!(screen.contains("1. c1-c2"));
+
+        let selecting = &backend.frames[4];
+        assert!(selecting.contains("|[f]|"));
+        assert!(selecting.contains("M M"));
+    }
+
+    #[test]
+    fn explains_what_cannot_be_done() {
+        use Key::*;
+        let (app, _) = play(&[Right, Right, Right, Enter]);
+        assert_eq!("The token is blocked", app.message);
+
+        let (app, _) = play(&[Right, Right, Enter, Up, Up, Up, Enter]);
+        assert_eq!(Some("c1".parse().unwrap()), app.selected);
+        assert_eq!("c1 can't reach c4", app.message);
+
+        let (app, _) = play(&[Up, Up, Enter]);
+        assert!(app.message.starts_with("Illegal move '@a3'"), "{}", app.message);
+    }
+
+    #[test]
+    fn quits_on_q() {
+        let (_, backend) = play(&[Key::Char('q'), Key::Right]);
+        assert_eq!(1, backend.frames.len());
+    }
+}
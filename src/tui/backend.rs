@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::io;
+use std::io::{Stdout, Write};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::{cursor, event, execute, queue, style, terminal};
+
+use crate::tui::screen::Screen;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+    Char(char),
+}
+
+/// Where the UI is drawn, and its keys come from.
+pub trait Backend {
+    /// Columns and rows.
+    fn size(&self) -> io::Result<(u16, u16)>;
+    fn draw(&mut self, screen: &Screen) -> io::Result<()>;
+    /// Blocks until the next key. [None] once there are no more.
+    fn read_key(&mut self) -> io::Result<Option<Key>>;
+}
+
+/// Renders into a buffer and replays the given keys, for tests.
+pub struct TestBackend {
+    width: u16,
+    height: u16,
+    keys: VecDeque<Key>,
+    /// Every frame drawn, oldest first.
+    pub frames: Vec<Screen>,
+}
+
+impl TestBackend {
+    pub fn new(width: u16, height: u16, keys: &[Key]) -> TestBackend {
+        TestBackend { width, height, keys: keys.iter().copied().collect(), frames: Vec::new() }
+    }
+
+    /// Panics if nothing was drawn yet.
+    pub fn last(&self) -> &Screen {
+        self.frames.last().expect("Nothing was drawn")
+    }
+}
+
+impl Backend for TestBackend {
+    fn size(&self) -> io::Result<(u16, u16)> {
+        Ok((self.width, self.height))
+    }
+
+    fn draw(&mut self, screen: &Screen) -> io::Result<()> {
+        self.frames.push(screen.clone());
+        Ok(())
+    }
+
+    fn read_key(&mut self) -> io::Result<Option<Key>> {
+        Ok(self.keys.pop_front())
+    }
+}
+
+/// The real terminal, in raw mode on the alternate screen until dropped.
+pub struct TerminalBackend {
+    out: Stdout,
+}
+
+impl TerminalBackend {
+    pub fn new() -> io::Result<TerminalBackend> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(TerminalBackend { out })
+    }
+}
+
+impl Drop for TerminalBackend {
+    fn drop(&mut self) {
+        // Nothing more can be done if restoring the terminal fails.
+        let _ = execute!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn key(e: KeyEvent) -> Option<Key> {
+    if e.kind == KeyEventKind::Release {
+        return None;
+    }
+    match e.code {
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Esc => Some(Key::Escape),
+        KeyCode::Char(c) => Some(Key::Char(c)),
+        _ => None,
+    }
+}
+
+impl Backend for TerminalBackend {
+    fn size(&self) -> io::Result<(u16, u16)> {
+        terminal::size()
+    }
+
+    fn draw(&mut self, screen: &Screen) -> io::Result<()> {
+        queue!(self.out, terminal::Clear(terminal::ClearType::All))?;
+        for (y, line) in screen.lines().iter().enumerate() {
+            queue!(self.out, cursor::MoveTo(0, y as u16), style::Print(line))?;
+        }
+        self.out.flush()
+    }
+
+    fn read_key(&mut self) -> io::Result<Option<Key>> {
+        loop {
+            if let Event::Key(e) = event::read()? {
+                if let Some(k) = key(e) {
+                    return Ok(Some(k));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod app;
+pub mod backend;
+pub mod screen;
@@ -0,0 +1,56 @@
+use std::fmt;
+
+/// A frame of the UI as a grid of characters, which a [crate::tui::backend::Backend] draws.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Screen {
+    pub width: u16,
+    pub height: u16,
+    cells: Vec<Vec<char>>,
+}
+
+impl Screen {
+    pub fn new(width: u16, height: u16) -> Screen {
+        Screen { width, height, cells: vec![vec![' '; usize::from(width)]; usize::from(height)] }
+    }
+
+    /// Writes text with its top left corner at (x, y), where y grows downwards. Each line of text
+    /// starts again at x, and whatever doesn't fit is cut off.
+    pub fn put(&mut self, x: u16, y: u16, text: &str) -> () {
+        for (row, line) in self.cells.iter_mut().skip(usize::from(y)).zip(text.lines()) {
+            for (cell, c) in row.iter_mut().skip(usize::from(x)).zip(line.chars()) {
+                *cell = c;
+            }
+        }
+    }
+
+    /// The rows, without trailing spaces.
+    pub fn lines(&self) -> Vec<String> {
+        self.cells.iter().map(|row| row.iter().collect::<String>().trim_end().to_owned()).collect()
+    }
+
+    /// True if any row contains the text.
+    pub fn contains(&self, text: &str) -> bool {
+        self.lines().iter().any(|l| l.contains(text))
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.lines().join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn put_writes_blocks_and_clips() {
+        let mut screen = Screen::new(6, 3);
+        screen.put(1, 1, "abc\ndefghij\nklm");
+        screen.put(4, 0, "xy");
+        assert_eq!(vec!["    xy", " abc", " defgh"], screen.lines());
+        assert!(screen.contains("defgh"));
+        assert!(!screen.contains("klm"));
+    }
+}
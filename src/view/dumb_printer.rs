@@ -1,28 +1,394 @@
+use crate::analysis::blunders::{GameAnalysis, Judgement, MoveAnalysis, PlayerSummary};
+use crate::analysis::destinations::{Destination, Immobile};
+use crate::analysis::report::{PlayerReport, Report};
+use crate::analysis::summon::SummonReport;
+use crate::common::coordinates::{Coordinates, Vector};
 use crate::common::utils::{MkString, Vectors};
+use crate::engine::eval::{Score, MATE, MATE_BOUND};
+use crate::game::bitboard::square_at;
+use crate::game::moves::{Move, MoveKind};
 use crate::game::state::GameState;
-use crate::game::token::{OwnedToken};
+use crate::game::token::{OwnedToken, Owner, TokenAction, TokenSide};
 
+fn framed(mut rows: Vec<String>) -> String {
+    let length = rows[0].len();
+    rows.intercalate_full(
+        format!("/{}\\", "=".repeat(length - 2)),
+        "-".repeat(length),
+        format!("\\{}/", "=".repeat(length - 2)),
+    );
+    rows.join("\n")
+}
+
+/// Player 1 is at the bottom, so the last row is printed first.
 pub fn print_board(gs: &GameState) -> String {
-    fn to_row(row: &Vec<Option<OwnedToken>>) -> String {
-        row.iter()
-            .map(|o| o.as_ref().map_or(' ', |t| t.single_char_token()).to_string())
-            .collect::<Vec<String>>()
-            .mk_string_full("|", "|", "|")
-    }
-    let rows = || {
-        let mut result = gs.board
-            .rows()
-            .iter()
-            .map(|e| to_row(e))
-            .collect::<Vec<String>>();
-        let length = result[0].len();
-        result.intercalate_full(
-            format!("/{}\\", "=".repeat(length - 2)),
-            "-".repeat(length),
-            format!("\\{}/", "=".repeat(length - 2)),
-        );
-        result
+    let cells = gs.board
+        .iter()
+        .map(|(_, o)| o.map_or(' ', |t| t.single_char_token()).to_string())
+        .collect::<Vec<String>>();
+    let rows = cells
+        .chunks(usize::from(gs.board.width()))
+        .rev()
+        .map(|row| row.to_vec().mk_string_full("|", "|", "|"))
+        .collect::<Vec<String>>();
+    framed(rows)
+}
+
+/// Like [print_board], but every cell is flanked by '^' if it's threatened by Player 1 and by 'v'
+/// if it's threatened by Player 2, followed by a per-token summary of each player.
+pub fn print_report(gs: &GameState, report: &Report) -> String {
+    let p1_attacks = report.player_1.attacked_squares();
+    let p2_attacks = report.player_2.attacked_squares();
+    let mut result = print_marked_board(gs, |c| {
+        (
+            if p1_attacks.contains(&c) { '^' } else { ' ' },
+            if p2_attacks.contains(&c) { 'v' } else { ' ' },
+        )
+    });
+    for p in &[&report.player_1, &report.player_2] {
+        result.push('\n');
+        result.push_str(&print_player_report(p));
+    }
+    result
+}
+
+/// Like [print_board], but every cell is flanked by the characters marks returns for its
+/// coordinates.
+pub fn print_marked_board<F: Fn(Coordinates) -> (char, char)>(gs: &GameState, marks: F) -> String {
+    let cells = gs.board
+        .iter()
+        .map(|(c, o)| {
+            let (left, right) = marks(c);
+            format!("{}{}{}", left, o.map_or(' ', |t| t.single_char_token()), right)
+        })
+        .collect::<Vec<String>>();
+    let rows = cells
+        .chunks(usize::from(gs.board.width()))
+        .rev()
+        .map(|row| row.to_vec().mk_string_full("|", "|", "|"))
+        .collect::<Vec<String>>();
+    framed(rows)
+}
+
+/// The marks of [print_destinations]: the selected square in brackets, and every destination
+/// flanked by the [action_char] of how it's reached.
+pub fn destination_marks(selected: Coordinates, destinations: &[Destination], c: Coordinates) -> Option<(char, char)> {
+    if c == selected {
+        return Some(('[', ']'));
+    }
+    destinations.iter().find(|d| d.target == c).map(|d| (action_char(d.action), action_char(d.action)))
+}
+
+/// The board marked with [destination_marks], followed by the destinations, or why there are none.
+pub fn print_destinations(gs: &GameState, selected: Coordinates, destinations: &Result<Vec<Destination>, Immobile>) -> String {
+    let none = Vec::new();
+    let list = destinations.as_ref().unwrap_or(&none);
+    let mut lines = vec![print_marked_board(gs, |c| destination_marks(selected, list, c).unwrap_or((' ', ' ')))];
+    match destinations {
+        Ok(list) => lines.extend(list.iter().map(|d| {
+            format!(
+                "{}: {:?}{}{}",
+                d.target,
+                d.action,
+                d.commanded.map_or(String::new(), |from| format!(" from {}", from)),
+                if d.capture { ", capture" } else { "" },
+            )
+        })),
+        Err(reason) => lines.push(reason.to_string()),
+    }
+    lines.join("\n")
+}
+
+fn print_player_report(p: &PlayerReport) -> String {
+    let mut lines = vec![format!(
+        "{:?}: mobility {}{}",
+        p.owner,
+        p.mobility(),
+        if p.duke_in_guard { ", Duke in guard!" } else { "" },
+    )];
+    for t in &p.tokens {
+        lines.push(format!(
+            "  {} {}: mobility {}, attacks [{}]{}",
+            t.name,
+            t.coordinates,
+            t.mobility,
+            t.attacks.mk_string(" "),
+            if t.defended { "" } else { ", undefended" },
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Scores are printed as signed footmen, e.g., "+1.25".
+pub fn print_score(score: Score) -> String {
+    if score >= MATE_BOUND {
+        format!("mate in {}", (MATE - score + 1) / 2)
+    } else if score <= -MATE_BOUND {
+        format!("mated in {}", (MATE + score) / 2)
+    } else {
+        format!("{:+.2}", f64::from(score) / 100.0)
+    }
+}
+
+pub fn print_summon_report(report: &SummonReport) -> String {
+    let mut lines = vec!["Draw probabilities:".to_owned()];
+    if report.probabilities.is_empty() {
+        lines.push("  The bag is empty".to_owned());
+    }
+    for (name, p) in &report.probabilities {
+        lines.push(format!("  {}: {:.0}%", name, p * 100.0));
+    }
+    lines.push("Expected score of summoning on:".to_owned());
+    for (c, score) in &report.squares {
+        lines.push(format!("  {}: {}", c, print_score(*score)));
+    }
+    if let Some((m, score)) = report.best_move {
+        lines.push(format!("Best move: {} {}", m, print_score(score)));
+    }
+    lines.push(
+        if report.summoning_is_better() {
+            format!("Summon on {}", report.best_square().unwrap().0)
+        } else {
+            "Don't summon".to_owned()
+        }
+    );
+    lines.join("\n")
+}
+
+/// Summons are printed by square, since the unit is drawn.
+fn print_choice(m: Move) -> String {
+    match m.kind() {
+        MoveKind::Summon => format!("@{}", square_at(m.to())),
+        _ => m.to_string(),
+    }
+}
+
+fn print_move_analysis(a: &MoveAnalysis) -> String {
+    let mark = if a.judgement == Judgement::Blunder { "??" } else { "?!" };
+    let missed = if a.missed_duke_capture {
+        ", missed a Duke capture"
+    } else if a.missed_summon {
+        ", missed a summon"
+    } else {
+        ""
     };
-    let board = rows().join("\n");
-    board
-}
\ No newline at end of file
+    format!(
+        "{}. {:?} {}{} {}, best {} {}{}",
+        a.ply / 2 + 1,
+        a.player,
+        a.played,
+        mark,
+        print_score(a.after),
+        print_choice(a.best),
+        print_score(a.before),
+        missed,
+    )
+}
+
+fn print_player_summary(s: &PlayerSummary) -> String {
+    format!(
+        "{:?}: {} moves, {} inaccuracies, {} blunders, {} missed Duke captures, {} missed summons, \
+         average loss {:.2}",
+        s.player,
+        s.moves,
+        s.inaccuracies,
+        s.blunders,
+        s.missed_duke_captures,
+        s.missed_summons,
+        f64::from(s.average_loss) / 100.0,
+    )
+}
+
+/// Every inaccuracy and blunder, with scores relative to the player who moved, followed by a
+/// summary per player.
+pub fn print_game_analysis(analysis: &GameAnalysis) -> String {
+    let mut lines: Vec<String> = analysis
+        .moves
+        .iter()
+        .filter(|a| a.judgement != Judgement::Good)
+        .map(print_move_analysis)
+        .collect();
+    if lines.is_empty() {
+        lines.push("No inaccuracies".to_owned());
+    }
+    for player in [Owner::Player1, Owner::Player2].iter() {
+        lines.push(print_player_summary(&analysis.summary(*player)));
+    }
+    lines.join("\n")
+}
+
+fn action_char(a: TokenAction) -> char {
+    match a {
+        TokenAction::Move => 'M',
+        TokenAction::Jump => 'J',
+        TokenAction::Slide => 'S',
+        TokenAction::Command => 'C',
+        TokenAction::JumpSlide => 'Z',
+        TokenAction::Strike => 'X',
+    }
+}
+
+/// Prints the token's current side as it applies on the board printed by [print_board], i.e.,
+/// oriented by its owner.
+pub fn print_token(t: &OwnedToken) -> String {
+    let side = TokenSide::SIDE as i16;
+    let half = side / 2;
+    let actions = t.current_actions();
+    let rows = (0..side)
+        .rev()
+        .map(|y| {
+            (0..side)
+                .map(|x| {
+                    let v = Vector::new(x - half, y - half);
+                    if v.is_zero() {
+                        t.single_char_token()
+                    } else {
+                        actions
+                            .iter()
+                            .find(|(o, _)| Vector::from(*o) == v)
+                            .map_or(' ', |(_, a)| action_char(*a))
+                    }
+                })
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>()
+                .mk_string_full("|", "|", "|")
+        })
+        .collect::<Vec<String>>();
+    framed(rows)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analysis::blunders::Thresholds;
+    use crate::analysis::destinations::destinations;
+    use crate::game::state::{DukeInitialLocation, FootmenSetup};
+    use crate::game::token::TokenBag;
+    use crate::game::units;
+    use crate::game::units::UnitId;
+
+    use super::*;
+
+    #[test]
+    fn print_token_for_player_1_has_forward_on_top() {
+        let mut footman = units::footman(Owner::Player1);
+        footman.token.flip();
+        let printed = print_token(&footman);
+        let rows: Vec<&str> = printed.lines().collect();
+        assert_eq!("| | |M| | |", rows[1]);
+        assert_eq!("| |M| |M| |", rows[3]);
+        assert_eq!("| | |F| | |", rows[5]);
+        assert_eq!("| |M| |M| |", rows[7]);
+        assert_eq!("| | | | | |", rows[9]);
+    }
+
+    #[test]
+    fn print_token_for_player_2_is_mirrored() {
+        let mut footman = units::footman(Owner::Player2);
+        footman.token.flip();
+        let printed = print_token(&footman);
+        let rows: Vec<&str> = printed.lines().collect();
+        assert_eq!("| | | | | |", rows[1]);
+        assert_eq!("| | |M| | |", rows[9]);
+    }
+
+    #[test]
+    fn print_report_marks_threatened_squares() {
+        use crate::analysis::report::report;
+        use crate::game::state::{DukeInitialLocation, FootmenSetup};
+        use crate::game::token::TokenBag;
+
+        let gs = GameState::new(
+            &TokenBag::new(vec![]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        let printed = print_report(&gs, &report(&gs));
+        let rows: Vec<&str> = printed.lines().collect();
+        // Player 1's back row, at the bottom of the board.
+        assert_eq!("|   |^  |^f |^d |^f |^  |", rows[11]);
+        assert_eq!("|   |   |^  |   |^  |   |", rows[9]);
+        assert!(rows.contains(&"Player1: mobility 4"));
+        assert!(rows.contains(&"  Duke d1: mobility 0, attacks [c1 e1]"));
+    }
+
+    #[test]
+    fn print_score_in_footmen_and_mates() {
+        assert_eq!("+1.25", print_score(125));
+        assert_eq!("-0.50", print_score(-50));
+        assert_eq!("mate in 1", print_score(MATE - 1));
+        assert_eq!("mate in 2", print_score(MATE - 3));
+        assert_eq!("mated in 1", print_score(-MATE + 2));
+    }
+
+    #[test]
+    fn print_summon_report_recommends_the_best_square() {
+        use crate::common::coordinates::Coordinates;
+        use crate::game::moves::{Move, MoveKind};
+
+        let report = SummonReport {
+            probabilities: vec![("Footman".to_owned(), 1.0)],
+            squares: vec![(Coordinates { x: 2, y: 1 }, 50), (Coordinates { x: 3, y: 1 }, 80)],
+            best_move: Some((Move::new(2, 8, MoveKind::Move), 20)),
+        };
+        assert_eq!(
+            "Draw probabilities:\n  Footman: 100%\nExpected score of summoning on:\n  c2: +0.50\n  d2: +0.80\n\
+             Best move: c1-c2 +0.20\nSummon on d2",
+            print_summon_report(&report),
+        );
+    }
+
+    #[test]
+    fn print_game_analysis_lists_mistakes_and_summaries() {
+        let analysis = GameAnalysis {
+            moves: vec![
+                MoveAnalysis {
+                    ply: 2,
+                    player: Owner::Player1,
+                    played: Move::new(2, 8, MoveKind::Move),
+                    best: Move::summon(3, UnitId::Footman),
+                    before: 40,
+                    after: -30,
+                    judgement: Thresholds::default().judge(70),
+                    missed_duke_capture: false,
+                    missed_summon: true,
+                },
+                MoveAnalysis {
+                    ply: 3,
+                    player: Owner::Player2,
+                    played: Move::new(32, 26, MoveKind::Move),
+                    best: Move::new(33, 27, MoveKind::Move),
+                    before: 10,
+                    after: 0,
+                    judgement: Judgement::Good,
+                    missed_duke_capture: false,
+                    missed_summon: false,
+                },
+            ],
+        };
+        assert_eq!(
+            "2. Player1 c1-c2?! -0.30, best @d1 +0.40, missed a summon\n\
+             Player1: 1 moves, 1 inaccuracies, 0 blunders, 0 missed Duke captures, 1 missed summons, \
+             average loss 0.70\n\
+             Player2: 1 moves, 0 inaccuracies, 0 blunders, 0 missed Duke captures, 0 missed summons, \
+             average loss 0.10",
+            print_game_analysis(&analysis),
+        );
+    }
+
+    #[test]
+    fn print_destinations_marks_the_token_and_its_destinations() {
+        let gs = GameState::new(
+            &TokenBag::new(vec![]),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+            (DukeInitialLocation::Left, FootmenSetup::Sides),
+        );
+        let c1 = Coordinates { x: 2, y: 0 };
+        let printed = print_destinations(&gs, c1, &destinations(&gs, c1));
+        let rows: Vec<&str> = printed.lines().collect();
+        assert_eq!("|   |   |M M|   |   |   |", rows[9]);
+        assert_eq!("|   |M M|[f]| d | f |   |", rows[11]);
+        assert_eq!(&["c2: Move", "b1: Move"], &rows[13..]);
+        let d1 = Coordinates { x: 3, y: 0 };
+        assert!(print_destinations(&gs, d1, &destinations(&gs, d1)).ends_with("\nThe token is blocked"));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::common::json::Json;
+use crate::game::token::Owner;
+use crate::game::tree::GameTree;
+use crate::view::dumb_printer::print_score;
+use crate::view::svg::{board_svg, SvgOptions};
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>The Duke replay</title>
+<style>
+body{font-family:sans-serif;background:#f6f1e4;color:#3a3a3a;margin:2em}
+main{display:flex;gap:2em;align-items:flex-start}
+#controls{margin:1em 0}
+#controls button{font-size:1.2em;min-width:3em}
+#moves{max-width:24em;line-height:1.8}
+#moves span{cursor:pointer;padding:0.1em 0.3em;border-radius:0.2em}
+#moves span.current{background:#f2d94e}
+#comment{white-space:pre-wrap;font-style:italic;min-height:1.5em}
+</style>
+</head>
+<body>
+<main>
+<div>
+<div id="board"></div>
+<div id="controls">
+<button id="start" title="Start (Home)">|&lt;</button>
+<button id="back" title="Back (Left)">&lt;</button>
+<button id="forward" title="Forward (Right)">&gt;</button>
+<button id="end" title="End (End)">&gt;|</button>
+</div>
+</div>
+<div>
+<h3 id="title"></h3>
+<div id="comment"></div>
+<div id="moves"></div>
+<p id="result"></p>
+</div>
+</main>
+<script>
+const positions = /*POSITIONS*/;
+const result = /*RESULT*/;
+let current = 0;
+const moves = document.getElementById("moves");
+const spans = positions.map((p, i) => {
+  if (i === 0) return null;
+  if (p.number) moves.append(document.createTextNode(" " + p.number + " "));
+  const span = document.createElement("span");
+  span.textContent = p.move;
+  span.onclick = () => show(i);
+  moves.append(span);
+  return span;
+});
+document.getElementById("result").textContent = result;
+function show(i) {
+  current = Math.max(0, Math.min(positions.length - 1, i));
+  const p = positions[current];
+  document.getElementById("board").innerHTML = p.board;
+  document.getElementById("title").textContent = p.title;
+  document.getElementById("comment").textContent = [p.eval, p.comment].filter(t => t).join(" ");
+  spans.forEach((s, j) => s && s.classList.toggle("current", j === current));
+}
+document.getElementById("start").onclick = () => show(0);
+document.getElementById("back").onclick = () => show(current - 1);
+document.getElementById("forward").onclick = () => show(current + 1);
+document.getElementById("end").onclick = () => show(positions.length - 1);
+document.addEventListener("keydown", e => {
+  const next = {ArrowLeft: current - 1, ArrowRight: current + 1, Home: 0, End: positions.length - 1}[e.key];
+  if (next !== undefined) {
+    show(next);
+    e.preventDefault();
+  }
+});
+show(0);
+</script>
+</body>
+</html>
+"#;
+
+/// A page replaying the main line of the game, with every position embedded as an SVG board, see
+/// [board_svg], and the moves' comments and evaluations. It needs nothing but a browser.
+pub fn replay_html(tree: &GameTree) -> String {
+    let mut tree = tree.clone();
+    tree.to_start();
+    let mut positions = vec![Json::object(vec![
+        ("move", "".into()),
+        ("number", "".into()),
+        ("title", "Start".into()),
+        ("comment", tree.comment.clone().into()),
+        ("eval", "".into()),
+        ("board", board_svg(&tree.state(), &SvgOptions::default()).into()),
+    ])];
+    while tree.forward(0) {
+        let ply = tree.cursor().len();
+        let node = tree.current().unwrap().clone();
+        let player = if ply % 2 == 1 { Owner::Player1 } else { Owner::Player2 };
+        let number = if ply % 2 == 1 { format!("{}.", ply.div_ceil(2)) } else { String::new() };
+        let options = SvgOptions { last_move: Some(node.m), ..SvgOptions::default() };
+        positions.push(Json::object(vec![
+            ("move", node.m.to_string().into()),
+            ("number", number.into()),
+            ("title", format!("{}. {:?} played {}", ply.div_ceil(2), player, node.m).into()),
+            ("comment", node.comment.clone().into()),
+            ("eval", node.eval.map_or(String::new(), |e| format!("[{}]", print_score(e))).into()),
+            ("board", board_svg(&tree.state(), &options).into()),
+        ]));
+    }
+    let positions: Vec<String> = positions.iter().map(|p| p.to_string()).collect();
+    PAGE.replace("/*POSITIONS*/", &format!("[\n{}\n]", positions.join(",\n")))
+        .replace("/*RESULT*/", &Json::from(format!("Result: {}", tree.result.notation())).to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const GAME: &str = "\
+[Player1 \"Left Sides\"]
+[Player2 \"Left Sides\"]
+[Bag \"Footman Footman\"]
+
+{An </script> opening} 1. c1-c2 {[%eval 25] Solid} (1. e1-e2) 1... b6-b5 *
+";
+
+    #[test]
+    fn embeds_every_position_of_the_main_line() {
+        let html = replay_html(&GAME.parse().unwrap());
+        assert_eq!(3, html.matches("\"board\":\"\\u003csvg").count());
+        assert!(html.contains("\"move\":\"c1-c2\",\"number\":\"1.\""));
+        assert!(html.contains("\"move\":\"b6-b5\",\"number\":\"\""));
+        assert!(html.contains("\"title\":\"1. Player2 played b6-b5\""));
+        assert!(html.contains("\"comment\":\"Solid\",\"eval\":\"[+0.25]\""));
+        assert!(!html.contains("e1-e2"));
+        assert!(html.contains("const result = \"Result: *\";"));
+    }
+
+    #[test]
+    fn is_self_contained() {
+        let html = replay_html(&GAME.parse().unwrap());
+        assert_eq!(1, html.matches("</script>").count());
+        assert!(html.contains("An \\u003c/script> opening"));
+        for external in ["src=", "href=", "<link", "@import", "url(http"].iter() {
+            assert!(!html.contains(external), "{}", external);
+        }
+    }
+}
@@ -1 +1,3 @@
-pub mod dumb_printer;
\ No newline at end of file
+pub mod dumb_printer;
+pub mod html;
+pub mod svg;
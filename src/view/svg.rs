@@ -0,0 +1,332 @@
+use crate::common::coordinates::{Coordinates, Vector};
+use crate::game::bitboard::square_at;
+use crate::game::moves::{Move, MoveKind};
+use crate::game::state::GameState;
+use crate::game::token::{CurrentSide, GameToken, OwnedToken, Owner, TokenAction, TokenSide};
+
+/// The side of a board square, in pixels.
+const SQUARE: i32 = 60;
+/// The side of a cell of a token card.
+const CELL: i32 = 20;
+/// Room for the coordinate labels around the board.
+const MARGIN: i32 = 20;
+const STYLE: &str = "<style>\
+.square{fill:#e8dcc4;stroke:#8a7a5c}\
+.highlight{fill:#f2d94e;fill-opacity:0.6}\
+.player1{fill:#fbf6ea;stroke:#3a3a3a;stroke-width:2}\
+.player2{fill:#3a3a3a;stroke:#fbf6ea;stroke-width:2}\
+.player1-text{fill:#3a3a3a}\
+.player2-text{fill:#fbf6ea}\
+.label{fill:#5a4c32}\
+.arrow{stroke:#c0392b;stroke-width:5;stroke-opacity:0.8;marker-end:url(#arrowhead)}\
+.card{fill:#fbf6ea;stroke:#3a3a3a}\
+.current{stroke-width:3}\
+.icon{fill:#3a3a3a;stroke:#3a3a3a;stroke-width:1.5}\
+.hollow{fill:none}\
+text{font-family:sans-serif;text-anchor:middle;dominant-baseline:central}\
+</style>";
+
+/// What [board_svg] draws besides the tokens.
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Files and ranks around the board, e.g., "a" to "f" and "1" to "6".
+    pub coordinates: bool,
+    /// Drawn as an arrow, or a highlight of the square for a summon.
+    pub last_move: Option<Move>,
+    pub highlights: Vec<Coordinates>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { coordinates: true, last_move: None, highlights: Vec::new() }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn document(width: i32, height: i32, body: &[String]) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{}\n{}\n</svg>\n",
+        STYLE,
+        body.join("\n"),
+        w = width,
+        h = height,
+    )
+}
+
+fn owner_class(owner: Owner) -> &'static str {
+    match owner {
+        Owner::Player1 => "player1",
+        Owner::Player2 => "player2",
+    }
+}
+
+/// The action's icon centered on (x, y), pointing away from the token for slides. Moves are
+/// filled circles, jumps hollow ones, slides filled triangles, jump slides hollow ones, strikes
+/// stars, and commands hollow squares.
+fn action_icon(a: TokenAction, x: i32, y: i32, direction: Vector) -> String {
+    let r = CELL * 3 / 10;
+    let triangle = |class: &str| {
+        // The tip points in the direction, y growing downwards in SVG.
+        let (dx, dy) = (f64::from(direction.x), -f64::from(direction.y));
+        let length = (dx * dx + dy * dy).sqrt();
+        let (ux, uy) = (dx / length, dy / length);
+        let (cx, cy, r) = (f64::from(x), f64::from(y), f64::from(r));
+        let points = [
+            (cx + ux * r, cy + uy * r),
+            (cx - ux * r - uy * r, cy - uy * r + ux * r),
+            (cx - ux * r + uy * r, cy - uy * r - ux * r),
+        ];
+        let points: Vec<String> = points.iter().map(|(px, py)| format!("{:.1},{:.1}", px, py)).collect();
+        format!("<polygon class=\"{}\" points=\"{}\"/>", class, points.join(" "))
+    };
+    match a {
+        TokenAction::Move => format!("<circle class=\"icon move\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>", x, y, r),
+        TokenAction::Jump => format!("<circle class=\"icon hollow jump\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>", x, y, r),
+        TokenAction::Slide => triangle("icon slide"),
+        TokenAction::JumpSlide => triangle("icon hollow jump-slide"),
+        TokenAction::Strike => {
+            let points: Vec<String> = (0..8u32)
+                .map(|i| {
+                    let angle = f64::from(i) * std::f64::consts::FRAC_PI_4;
+                    let length = if i.is_multiple_of(2) { f64::from(r) } else { f64::from(r) / 2.5 };
+                    format!("{:.1},{:.1}", f64::from(x) + length * angle.sin(), f64::from(y) - length * angle.cos())
+                })
+                .collect();
+            format!("<polygon class=\"icon strike\" points=\"{}\"/>", points.join(" "))
+        }
+        TokenAction::Command => format!(
+            "<rect class=\"icon hollow command\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+            x - r,
+            y - r,
+            2 * r,
+            2 * r,
+        ),
+    }
+}
+
+/// A token on the board, with its name, and a marker in the corner which is filled once flipped.
+fn board_token(t: &OwnedToken, x: i32, y: i32) -> String {
+    let class = owner_class(t.owner);
+    let side = match t.token.current_side {
+        CurrentSide::Initial => "initial hollow",
+        CurrentSide::Flipped => "flipped",
+    };
+    [
+        format!(
+            "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"6\"/>",
+            class,
+            x + 4,
+            y + 4,
+            SQUARE - 8,
+            SQUARE - 8,
+        ),
+        format!(
+            "<text class=\"{}-text\" x=\"{}\" y=\"{}\" font-size=\"11\">{}</text>",
+            class,
+            x + SQUARE / 2,
+            y + SQUARE / 2,
+            escape(&t.token.name),
+        ),
+        format!(
+            "<circle class=\"icon {} {}-text\" cx=\"{}\" cy=\"{}\" r=\"4\"/>",
+            side,
+            class,
+            x + SQUARE - 13,
+            y + 13,
+        ),
+    ]
+    .join("\n")
+}
+
+/// The board with Player 1 at the bottom, as [crate::view::dumb_printer::print_board] prints it.
+pub fn board_svg(gs: &GameState, options: &SvgOptions) -> String {
+    let margin = if options.coordinates { MARGIN } else { 0 };
+    let (width, height) = (i32::from(gs.board.width()), i32::from(gs.board.height()));
+    let corner = |c: Coordinates| (margin + i32::from(c.x) * SQUARE, (height - 1 - i32::from(c.y)) * SQUARE);
+    let center = |c: Coordinates| {
+        let (x, y) = corner(c);
+        (x + SQUARE / 2, y + SQUARE / 2)
+    };
+    let mut body = vec![
+        "<defs><marker id=\"arrowhead\" markerWidth=\"4\" markerHeight=\"4\" refX=\"2\" refY=\"2\" orient=\"auto\">\
+<path d=\"M0,0 L4,2 L0,4 z\" fill=\"#c0392b\"/></marker></defs>"
+            .to_owned(),
+    ];
+    let mut highlights = options.highlights.clone();
+    if let Some(m) = options.last_move.filter(|m| m.kind() == MoveKind::Summon) {
+        highlights.push(square_at(m.to()));
+    }
+    for (c, t) in gs.board.iter() {
+        let (x, y) = corner(c);
+        body.push(format!("<rect class=\"square\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>", x, y, SQUARE, SQUARE));
+        if highlights.contains(&c) {
+            body.push(format!(
+                "<rect class=\"highlight\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                x,
+                y,
+                SQUARE,
+                SQUARE,
+            ));
+        }
+        if let Some(t) = t {
+            body.push(board_token(t, x, y));
+        }
+    }
+    if let Some(m) = options.last_move.filter(|m| m.kind() != MoveKind::Summon) {
+        let ((x1, y1), (x2, y2)) = (center(square_at(m.from())), center(square_at(m.to())));
+        body.push(format!("<line class=\"arrow\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>", x1, y1, x2, y2));
+    }
+    if options.coordinates {
+        for i in 0..width {
+            body.push(format!(
+                "<text class=\"label\" x=\"{}\" y=\"{}\" font-size=\"12\">{}</text>",
+                margin + i * SQUARE + SQUARE / 2,
+                height * SQUARE + MARGIN / 2,
+                (b'a' + i as u8) as char,
+            ));
+        }
+        for i in 0..height {
+            body.push(format!(
+                "<text class=\"label\" x=\"{}\" y=\"{}\" font-size=\"12\">{}</text>",
+                MARGIN / 2,
+                (height - 1 - i) * SQUARE + SQUARE / 2,
+                i + 1,
+            ));
+        }
+    }
+    document(width * SQUARE + margin, height * SQUARE + margin, &body)
+}
+
+/// One side of a card at (x, y), from its owner's point of view, i.e., Top is up.
+fn token_side(side: &TokenSide, x: i32, y: i32, current: bool) -> String {
+    let cells = i32::from(TokenSide::SIDE);
+    let half = cells / 2;
+    let mut body = vec![format!(
+        "<rect class=\"card{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\"/>",
+        if current { " current" } else { "" },
+        x,
+        y,
+        cells * CELL,
+        cells * CELL,
+    )];
+    body.push(format!(
+        "<rect class=\"icon\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+        x + half * CELL + CELL / 4,
+        y + half * CELL + CELL / 4,
+        CELL / 2,
+        CELL / 2,
+    ));
+    for (o, a) in side.actions() {
+        let v = Vector::from(o);
+        let cx = x + (i32::from(v.x) + half) * CELL + CELL / 2;
+        let cy = y + (half - i32::from(v.y)) * CELL + CELL / 2;
+        body.push(action_icon(*a, cx, cy, v));
+    }
+    body.join("\n")
+}
+
+/// Both sides of the token next to each other, the current one framed thicker, with the name
+/// above them.
+pub fn token_svg(t: &GameToken) -> String {
+    let side = i32::from(TokenSide::SIDE) * CELL;
+    let gap = CELL;
+    let mut body = vec![format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"14\">{}</text>",
+        side + gap / 2,
+        MARGIN / 2,
+        escape(&t.name),
+    )];
+    for (i, s) in CurrentSide::ALL.iter().enumerate() {
+        let side_of = match s {
+            CurrentSide::Initial => &t.side_a,
+            CurrentSide::Flipped => &t.side_b,
+        };
+        body.push(token_side(side_of, i as i32 * (side + gap), MARGIN, *s == t.current_side));
+    }
+    document(2 * side + gap, side + MARGIN, &body)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::game::moves::MoveList;
+    use crate::game::position::Position;
+    use crate::game::state::initial;
+    use crate::game::units;
+
+    use super::*;
+
+    fn count(svg: &str, text: &str) -> usize {
+        svg.matches(text).count()
+    }
+
+    #[test]
+    fn draws_squares_tokens_and_labels() {
+        let svg = board_svg(&initial(0), &SvgOptions::default());
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"380\" height=\"380\""));
+        assert_eq!(36, count(&svg, "class=\"square\""));
+        assert_eq!(3, count(&svg, "<rect class=\"player1\""));
+        assert_eq!(3, count(&svg, "<rect class=\"player2\""));
+        assert_eq!(6, count(&svg, "initial hollow"));
+        assert_eq!(2, count(&svg, ">Duke</text>"));
+        assert_eq!(12, count(&svg, "class=\"label\""));
+        assert!(!svg.contains("<line"));
+
+        let bare = board_svg(&initial(0), &SvgOptions { coordinates: false, ..SvgOptions::default() });
+        assert!(bare.contains("width=\"360\" height=\"360\""));
+        assert_eq!(0, count(&bare, "class=\"label\""));
+    }
+
+    #[test]
+    fn draws_last_move_and_highlights() {
+        let mut gs = initial(0);
+        let p = Position::from_state(&gs).unwrap();
+        let mut moves = MoveList::new();
+        p.legal_moves(&mut moves);
+        let m = moves.iter().copied().find(|m| m.to_string() == "c1-c2").unwrap();
+        gs.make_move(&m.to_game_move().unwrap());
+        let options = SvgOptions { last_move: Some(m), highlights: vec!["a1".parse().unwrap()], ..SvgOptions::default() };
+        let svg = board_svg(&gs, &options);
+        // c1 is the third square of the bottom row, c2 the one above it.
+        assert!(svg.contains("<line class=\"arrow\" x1=\"170\" y1=\"330\" x2=\"170\" y2=\"270\"/>"));
+        assert!(svg.contains("<rect class=\"highlight\" x=\"20\" y=\"300\""));
+        assert_eq!(1, count(&svg, "icon flipped"));
+    }
+
+    #[test]
+    fn draws_both_sides_of_cards() {
+        let mut footman = units::footman(Owner::Player1).token;
+        let svg = token_svg(&footman);
+        assert!(svg.contains(">Footman</text>"));
+        assert_eq!(2, count(&svg, "<rect class=\"card"));
+        assert_eq!(1, count(&svg, "card current\" x=\"0\""));
+        // Four orthogonal moves initially, four diagonal moves and a two square move forward once
+        // flipped.
+        assert_eq!(9, count(&svg, "class=\"icon move\""));
+        footman.flip();
+        assert_eq!(1, count(&token_svg(&footman), "card current\" x=\"120\""));
+    }
+
+    #[test]
+    fn has_an_icon_for_every_action() {
+        let direction = Vector::new(0, 1);
+        let icons: Vec<String> = [
+            TokenAction::Move,
+            TokenAction::Jump,
+            TokenAction::Slide,
+            TokenAction::Command,
+            TokenAction::JumpSlide,
+            TokenAction::Strike,
+        ]
+        .iter()
+        .map(|a| action_icon(*a, 10, 10, direction))
+        .collect();
+        for (icon, class) in icons.iter().zip(["move", "jump", "slide", "command", "jump-slide", "strike"].iter()) {
+            assert!(icon.contains(&format!("{}\"", class)), "{}", icon);
+        }
+        // The slide points up.
+        assert!(icons[2].starts_with("<polygon class=\"icon slide\" points=\"10.0,4.0 "));
+    }
+}